@@ -1,170 +1,80 @@
 use std::{fs::File, io::{BufRead, BufReader}};
 
+use swiftlog_proto::tsv;
+
 use eframe::egui;
 use egui::{TextEdit, RichText};
-use regex::Regex;
+use swiftlog_query::{Query, Record};
 
 // ─────────────────────────────────────────────────────────────────────────────
-// 로그 엔트리 & SelectQuery (SwiftLog와 호환되도록 최소 필드 구성)
+// 로그 엔트리 (SwiftLog와 호환되도록 최소 필드 구성)
 // ─────────────────────────────────────────────────────────────────────────────
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-#[repr(u8)]
-pub enum LogLevel { Trace=0, Debug=1, Info=2, Warn=3, Error=4 }
-
-impl LogLevel {
-    fn from_str(s: &str) -> Option<Self> {
-        match s.to_ascii_lowercase().as_str() {
-            "trace" => Some(LogLevel::Trace),
-            "debug" => Some(LogLevel::Debug),
-            "info"  => Some(LogLevel::Info),
-            "warn"  => Some(LogLevel::Warn),
-            "error" => Some(LogLevel::Error),
-            _ => None,
-        }
-    }
-}
+pub use swiftlog_proto::{FieldValue, LogLevel};
 
 #[derive(Clone, Debug)]
 pub struct LogEntry {
     pub ts_ms: u64,
     pub level: LogLevel,
     pub code: u16,
+    /// 표시/검색용 (UTF-8이 아닌 바이트는 대체 문자)
     pub msg: String,
+    /// 파일에 기록된 원래 메시지 바이트
+    pub msg_bytes: Vec<u8>,
+    pub fields: Vec<(String, FieldValue)>,
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct SelectQuery {
-    pub level_min: Option<LogLevel>,
-    pub level_max: Option<LogLevel>,
-    pub code_range: Option<(u16,u16)>,   // "code=100..200" 또는 단일 "code=123"
-    pub since_ms: Option<u64>,
-    pub until_ms: Option<u64>,
-    pub contains: Option<String>,        // LIKE '%text%'
-    pub regex: Option<Regex>,            // 정규식
-    pub limit: Option<usize>,
-    pub offset: usize,
-    pub latest: bool,                    // 최신 우선 정렬
-}
-
-// ─────────────────────────────────────────────────────────────────────────────
-// 쿼리 파서: "SelectLog"의 인자 문자열과 동일한 문법을 지원
-// 예) "latest limit=100 contains=swiftlog level>=info code=1000..1999"
-//     "regex=fail.*socket since=1759196530900"
-// ─────────────────────────────────────────────────────────────────────────────
-fn parse_select_query(s: &str) -> Result<SelectQuery, String> {
-    let mut q = SelectQuery::default();
-    if s.trim().is_empty() {
-        return Ok(q);
+/// SelectLog 조건식 평가 (서버와 같은 swiftlog-query). 파일에는 송신자 정보가 없어 source= / service=는 맞지 않음
+impl Record for LogEntry {
+    fn ts_ms(&self) -> u64 { self.ts_ms }
+    fn level(&self) -> LogLevel { self.level }
+    fn code(&self) -> u16 { self.code }
+    fn msg(&self) -> &str { &self.msg }
+    fn field_eq(&self, key: &str, want: &str) -> bool {
+        self.fields.iter().any(|(k, v)| k == key && v.eq_text(want))
     }
-    for tok in s.split_whitespace() {
-        if tok.eq_ignore_ascii_case("latest") {
-            q.latest = true;
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("limit=") {
-            q.limit = rest.parse::<usize>().ok();
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("offset=") {
-            q.offset = rest.parse::<usize>().unwrap_or(0);
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("level>=") {
-            q.level_min = LogLevel::from_str(rest);
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("level<=") {
-            q.level_max = LogLevel::from_str(rest);
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("code=") {
-            if let Some((a,b)) = rest.split_once("..") {
-                let lo = a.parse::<u16>().map_err(|_| "invalid code range lo")?;
-                let hi = b.parse::<u16>().map_err(|_| "invalid code range hi")?;
-                q.code_range = Some((lo.min(hi), lo.max(hi)));
-            } else {
-                let v = rest.parse::<u16>().map_err(|_| "invalid code value")?;
-                q.code_range = Some((v,v));
-            }
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("since=") {
-            q.since_ms = rest.parse::<u64>().ok();
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("until=") {
-            q.until_ms = rest.parse::<u64>().ok();
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("contains=") {
-            q.contains = Some(rest.to_string());
-            continue;
-        }
-        if let Some(rest) = tok.strip_prefix("regex=") {
-            // regex=... 전체를 그대로 패턴으로 사용
-            let re = Regex::new(rest).map_err(|e| format!("invalid regex: {e}"))?;
-            q.regex = Some(re);
-            continue;
-        }
-        // 알 수 없는 토큰은 무시(원하면 에러 처리)
-    }
-    Ok(q)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-/* TSV(.log) 로더
-   각 줄:  ts_ms \t level_u8 \t code \t message
-   예: 1759196530919\t2\t1001\thello swiftlog #0
+/* TSV(.log) 로더 — 줄 규칙/이스케이프는 swiftlog_proto::tsv (서버 기록, 백업과 같음)
+   각 줄:  ts_ms \t level_u8 \t code \t message [\t fields]
+   예: 1759196530919\t2\t1001\thello\nswiftlog #0\tuser_id=42 region="eu"
 */
 // ─────────────────────────────────────────────────────────────────────────────
 fn load_tsv(path: &str) -> Result<Vec<LogEntry>, String> {
     let f = File::open(path).map_err(|e| format!("open failed: {e}"))?;
+    let mut reader = BufReader::new(f);
     let mut out = Vec::new();
-    for (lnum, line) in BufReader::new(f).lines().enumerate() {
-        let line = line.map_err(|e| format!("read failed: {e}"))?;
+    let mut buf = Vec::new();
+    let mut lnum = 0;
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).map_err(|e| format!("read failed: {e}"))? == 0 { break; }
+        lnum += 1;
+        // 규칙대로 쓴 줄은 항상 UTF-8. 예전 파일의 날 바이트만 대체 문자로
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches('\n');
         if line.trim().is_empty() { continue; }
-        let mut it = line.splitn(4, '\t');
-        let ts = it.next().ok_or("missing ts_ms")?;
-        let lev = it.next().ok_or("missing level")?;
-        let code = it.next().ok_or("missing code")?;
-        let msg = it.next().unwrap_or("");
-
-        let ts_ms: u64 = ts.parse().map_err(|_| format!("line {}: invalid ts_ms", lnum+1))?;
-        let level_u8: u8 = lev.parse().map_err(|_| format!("line {}: invalid level", lnum+1))?;
-        let level = match level_u8 {
-            0 => LogLevel::Trace,
-            1 => LogLevel::Debug,
-            2 => LogLevel::Info,
-            3 => LogLevel::Warn,
-            _ => LogLevel::Error,
-        };
-        let code_u16: u16 = code.parse().map_err(|_| format!("line {}: invalid code", lnum+1))?;
-
-        out.push(LogEntry { ts_ms, level, code: code_u16, msg: msg.to_string() });
+        let r = tsv::parse_line(line).map_err(|e| format!("line {lnum}: {e}"))?;
+        out.push(LogEntry {
+            ts_ms: r.ts_ms,
+            level: LogLevel::from_u8_lossy(r.level_raw),
+            code: r.code,
+            msg: String::from_utf8_lossy(&r.msg).into_owned(),
+            msg_bytes: r.msg,
+            fields: r.fields,
+        });
     }
     Ok(out)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// 필터링/정렬 적용: SelectQuery → 인덱스 목록(self.filtered) 생성
+// 필터링/정렬 적용: Query → 인덱스 목록(self.filtered) 생성
+// 쿼리 문법은 서버 SelectLog와 같음 (swiftlog_query). 예) "latest limit=100 level>=info (code=1000..1999 or contains=swiftlog)"
 // ─────────────────────────────────────────────────────────────────────────────
-fn filter_indices(all: &[LogEntry], q: &SelectQuery) -> Vec<usize> {
+fn filter_indices(all: &[LogEntry], q: &Query) -> Vec<usize> {
     // 1) 조건 만족하는 인덱스 수집
     let mut idxs: Vec<usize> = all.iter().enumerate()
-        .filter(|(_, e)| {
-            if let Some(min) = q.level_min { if e.level < min { return false; } }
-            if let Some(max) = q.level_max { if e.level > max { return false; } }
-            if let Some((lo,hi)) = q.code_range { if e.code < lo || e.code > hi { return false; } }
-            if let Some(since) = q.since_ms { if e.ts_ms < since { return false; } }
-            if let Some(until) = q.until_ms { if e.ts_ms > until { return false; } }
-            if let Some(ref c) = q.contains {
-                if !e.msg.contains(c) { return false; }
-            }
-            if let Some(ref r) = q.regex {
-                if !r.is_match(&e.msg) { return false; }
-            }
-            true
-        })
+        .filter(|(_, e)| q.matches(*e))
         .map(|(i, _)| i)
         .collect();
 
@@ -188,6 +98,7 @@ fn filter_indices(all: &[LogEntry], q: &SelectQuery) -> Vec<usize> {
 // ─────────────────────────────────────────────────────────────────────────────
 // GUI 상태
 // ─────────────────────────────────────────────────────────────────────────────
+#[derive(Default)]
 struct ViewerApp {
     // 입력 상태
     path_input: String,
@@ -202,29 +113,16 @@ struct ViewerApp {
     last_info: Option<String>,
 }
 
-impl Default for ViewerApp {
-    fn default() -> Self {
-        Self {
-            path_input: String::new(),
-            query_input: String::new(),
-            all_logs: Vec::new(),
-            filtered: Vec::new(),
-            last_error: None,
-            last_info: None,
-        }
-    }
-}
-
 impl ViewerApp {
     fn apply_query(&mut self) {
         self.last_error = None;
-        match parse_select_query(&self.query_input) {
+        match swiftlog_query::parse(&self.query_input) {
             Ok(q) => {
                 self.filtered = filter_indices(&self.all_logs, &q);
                 self.last_info = Some(format!("filtered {} / {}", self.filtered.len(), self.all_logs.len()));
             }
             Err(e) => {
-                self.last_error = Some(e);
+                self.last_error = Some(format!("{e}\n{}", e.caret(&self.query_input)));
             }
         }
     }
@@ -296,7 +194,8 @@ impl eframe::App for ViewerApp {
                 ui.label(RichText::new(info).color(egui::Color32::from_rgb(100, 200, 100)));
             }
             if let Some(ref err) = self.last_error {
-                ui.label(RichText::new(err).color(egui::Color32::RED));
+                // 쿼리 오류는 위치 표시(^)가 맞도록 고정폭
+                ui.label(RichText::new(err).monospace().color(egui::Color32::RED));
             }
         });
 
@@ -324,13 +223,7 @@ impl eframe::App for ViewerApp {
                     let idx = self.filtered[row];
                     let e = &self.all_logs[idx];
 
-                    let level_str = match e.level {
-                        LogLevel::Trace => "Trace",
-                        LogLevel::Debug => "Debug",
-                        LogLevel::Info  => "Info",
-                        LogLevel::Warn  => "Warn",
-                        LogLevel::Error => "Error",
-                    };
+                    let level_str = e.level.as_str();
 
                     // (선택) ts_ms → 로컬 시각 문자열 (feature=chrono)
                     #[cfg(feature = "chrono")]
@@ -345,7 +238,12 @@ impl eframe::App for ViewerApp {
                     #[cfg(not(feature = "chrono"))]
                     let ts_fmt = e.ts_ms.to_string();
 
-                    ui.monospace(format!("{:<23} {:<7} {:<6} {}", ts_fmt, level_str, e.code, e.msg));
+                    if e.fields.is_empty() {
+                        ui.monospace(format!("{:<23} {:<7} {:<6} {}", ts_fmt, level_str, e.code, e.msg));
+                    } else {
+                        let fields = swiftlog_proto::format_fields(e.fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())));
+                        ui.monospace(format!("{:<23} {:<7} {:<6} {} | {}", ts_fmt, level_str, e.code, e.msg, fields));
+                    }
                 }
             });
         });
@@ -374,3 +272,65 @@ fn main() -> eframe::Result<()> {
         Box::new(|_cc| Box::new(ViewerApp::default())),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn temp_file(tag: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("swl_viewer_{tag}_{}.log", std::process::id()))
+    }
+
+    proptest! {
+        // 서버 기록(tsv::write_line) → 파일 → 뷰어 로더
+        #[test]
+        fn written_messages_load_back_exactly(
+            msgs in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..80), 1..20),
+        ) {
+            let mut data = Vec::new();
+            for (i, m) in msgs.iter().enumerate() {
+                tsv::write_line(&mut data, i as u64, 3, 7, m, [("n", swiftlog_proto::FieldValueRef::I64(i as i64))]);
+            }
+            let path = temp_file("prop");
+            std::fs::write(&path, &data).unwrap();
+            let rows = load_tsv(path.to_str().unwrap()).unwrap();
+            let _ = std::fs::remove_file(&path);
+            prop_assert_eq!(rows.len(), msgs.len());
+            for (row, m) in rows.iter().zip(&msgs) {
+                prop_assert_eq!(&row.msg_bytes, m);
+                prop_assert_eq!(row.level, LogLevel::Warn);
+                prop_assert_eq!(row.fields.len(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn legacy_raw_bytes_and_tabs_still_load() {
+        let path = temp_file("legacy");
+        std::fs::write(&path, b"1\t2\t3\tplain \xFF\tcol\n2\t4\t5\tescaped\\tab\tk=1\n").unwrap();
+        let rows = load_tsv(path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(rows[0].msg, "plain \u{FFFD}\tcol");
+        assert_eq!(rows[1].msg_bytes, b"escaped\tab");
+        assert_eq!(rows[1].fields, [("k".to_string(), FieldValue::I64(1))]);
+    }
+
+    #[test]
+    fn queries_use_the_server_grammar() {
+        let entry = |ts_ms, level, code, msg: &str, fields| LogEntry {
+            ts_ms, level, code, msg: msg.into(), msg_bytes: msg.as_bytes().to_vec(), fields,
+        };
+        let all = [
+            entry(1, LogLevel::Info, 200, "ok", vec![("region".into(), FieldValue::Str("eu".into()))]),
+            entry(2, LogLevel::Error, 500, "db down", vec![]),
+            entry(3, LogLevel::Warn, 503, "slow", vec![("region".into(), FieldValue::Str("us".into()))]),
+        ];
+        let run = |s: &str| filter_indices(&all, &swiftlog_query::parse(s).unwrap());
+        assert_eq!(run("latest code>=500 or field.region=eu"), [2, 1, 0]);
+        assert_eq!(run("not (level=error or field.region in (us))"), [0]);
+        assert_eq!(run("level>=warn limit=1 offset=1"), [2]);
+        // 예전에는 무시하던 모르는 토큰도 이제 위치와 함께 오류
+        assert_eq!(swiftlog_query::parse("level>=warn lmit=5").unwrap_err().pos, 12);
+    }
+}
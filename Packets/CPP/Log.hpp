@@ -22,21 +22,23 @@ typedef unsigned short     u16;
 typedef unsigned int       u32;
 typedef unsigned __int64   u64;
 
-// ===== 프로토콜 상수 (Rust와 동일하게 맞추세요) =====
-static const u32 LOG_MAGIC   = 0x4C4F4750u; // 'LOGP' 예시. Rust의 MAGIC으로 교체!
-static const u32 LOG_VERSION = 1u;          // Rust의 VERSION으로 교체!
+// ===== 프로토콜 상수 (swiftlog-proto 크레이트와 동일) =====
+//  BatchHeader : magic u32 | version u16 | count u16
+//  Record      : ts_ms u64 | level u8 | code u16 | msg_len u16 | msg bytes
+//  TCP는 배치 앞에 u32 길이 프리픽스를 붙입니다(send_tcp_bytes가 처리).
+static const u32 LOG_MAGIC   = 0x31474C53u; // 'SLG1' (LE)
+static const u16 LOG_VERSION = 1;
 
 // ===== LogLevel (Rust와 동일 매핑) =====
 enum LogLevel : u8 { Trace=0, Debug=1, Info=2, Warn=3, Error=4 };
 
 // ===== 레코드 표현 =====
 struct LogRecord {
-    u64 id;       // 일반적으로 0으로 전송 (서버가 부여)
     u64 ts_ms;    // epoch millis
     u8  level;    // LogLevel
     u16 code;     // 앱 정의 코드
     std::string msg_utf8; // UTF-8
-    LogRecord() : id(0), ts_ms(0), level(0), code(0) {}
+    LogRecord() : ts_ms(0), level(0), code(0) {}
 };
 
 // ===== 유틸: 현재 epoch millis =====
@@ -57,23 +59,27 @@ static inline u64 now_epoch_millis() {
 static inline bool serialize_log_batch(const std::vector<LogRecord>& logs,
                                        std::vector<u8>& out_bytes,
                                        u32 magic /*=LOG_MAGIC*/,
-                                       u32 version /*=LOG_VERSION*/,
+                                       u16 version /*=LOG_VERSION*/,
                                        std::string* err /*=0*/)
 {
     out_bytes.clear();
     out_bytes.reserve(16 + logs.size() * 64); // 대충 넉넉히
 
-    // 헤더: MAGIC, VERSION (LE)
-    // (x86/amd64는 little-endian이므로 그대로 push)
-    // u32
+    if (logs.size() > 0xFFFF) {
+        if (err) *err = "too many records in one batch (> 65535)";
+        return false;
+    }
+    u16 count = (u16)logs.size();
+
+    // 헤더: MAGIC u32, VERSION u16, COUNT u16 (LE)
     out_bytes.push_back((u8)( magic        & 0xFF));
     out_bytes.push_back((u8)((magic >> 8)  & 0xFF));
     out_bytes.push_back((u8)((magic >> 16) & 0xFF));
     out_bytes.push_back((u8)((magic >> 24) & 0xFF));
     out_bytes.push_back((u8)( version        & 0xFF));
     out_bytes.push_back((u8)((version >> 8)  & 0xFF));
-    out_bytes.push_back((u8)((version >> 16) & 0xFF));
-    out_bytes.push_back((u8)((version >> 24) & 0xFF));
+    out_bytes.push_back((u8)( count        & 0xFF));
+    out_bytes.push_back((u8)((count >> 8)  & 0xFF));
 
     // 레코드들
     for (size_t i = 0; i < logs.size(); ++i) {
@@ -84,8 +90,6 @@ static inline bool serialize_log_batch(const std::vector<LogRecord>& logs,
         }
         u16 msg_len = (u16)r.msg_utf8.size();
 
-        // u64 id
-        for (int k=0;k<8;++k) out_bytes.push_back((u8)((r.id    >> (8*k)) & 0xFF));
         // u64 ts_ms
         for (int k=0;k<8;++k) out_bytes.push_back((u8)((r.ts_ms >> (8*k)) & 0xFF));
         // u8 level
@@ -180,9 +184,15 @@ static inline bool send_tcp_bytes(const char* host, unsigned short port,
             if (err) *err = "connect() failed"; break;
         }
 
-        // 전체 버퍼 전송
-        const char* p = (const char*)&bytes[0];
-        int remain = (int)bytes.size();
+        // [len u32 LE] 프레임 + 배치 전송
+        std::vector<u8> frame;
+        frame.reserve(4 + bytes.size());
+        u32 len = (u32)bytes.size();
+        for (int k=0;k<4;++k) frame.push_back((u8)((len >> (8*k)) & 0xFF));
+        frame.insert(frame.end(), bytes.begin(), bytes.end());
+
+        const char* p = (const char*)&frame[0];
+        int remain = (int)frame.size();
         while (remain > 0) {
             int n = send(s, p, remain, 0);
             if (n <= 0) { if (err) *err = "send() failed"; break; }
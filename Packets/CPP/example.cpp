@@ -12,7 +12,6 @@ int main() {
     // 로그 2개 배치 만들기
     std::vector<LogRecord> batch;
     LogRecord a;
-    a.id = 0; // 서버에서 부여하므로 0 권장
     a.ts_ms = now_epoch_millis();
     a.level = (u8)Info; // Rust LogLevel::Info = 2
     a.code  = 1001;
@@ -20,7 +19,6 @@ int main() {
     batch.push_back(a);
 
     LogRecord b;
-    b.id = 0;
     b.ts_ms = now_epoch_millis();
     b.level = (u8)Error; // 4
     b.code  = 5001;
@@ -37,10 +35,10 @@ int main() {
 
     // 전송 (택1) — Rust 에이전트의 수신 포트/프로토콜에 맞추세요
     // UDP:
-    // bool ok = send_udp_bytes("127.0.0.1", 9100, bytes, &err);
+    // bool ok = send_udp_bytes("127.0.0.1", 9050, bytes, &err);
 
     // TCP:
-    bool ok = send_tcp_bytes("127.0.0.1", 9101, bytes, &err);
+    bool ok = send_tcp_bytes("127.0.0.1", 9502, bytes, &err);
 
     if (!ok) {
         std::cout << "Send error: " << err << "\n";
@@ -0,0 +1,115 @@
+// LogStore::select 벤치마크 (criterion)
+//
+//   cargo bench -p SwiftLog --bench select
+//
+// 저장소는 서버 바이너리 안의 모듈이라 소스 파일을 #[path]로 직접 가져옵니다.
+// 200k건(샤드 8개, 레벨/코드 고르게) 기준으로 콘솔/HTTP에서 자주 쓰는 조회를 잽니다.
+//
+// 참고 수치 (1 vCPU VM, release)
+//   latest limit=50        : 전체 복사+정렬 ~28.8ms → k-way 병합 ~3.9µs
+//   limit=50 offset=10000  : ~25.3ms → ~0.47ms
+//   보조 인덱스 (store.index, 훑기 → 인덱스)
+//     code=7               : ~2.8ms → ~15µs
+//     code=100..109        : ~2.2ms → ~0.19ms
+//     since/until 1분      : ~3.1ms → ~0.44ms
+//     level>=error (20%)   : 후보가 많아 인덱스를 쓰지 않음
+//   토큰 인덱스 (store.text_index)
+//     word:123456          : contains= 훑기 ~12ms → ~3µs
+//     word:12345*          : ~8µs
+//     phrase:took 3ms latest limit=20 : ~0.5ms → ~20µs
+//     대신 토큰 키/목록이 레코드 사이사이에 할당되어, 인덱스를 못 쓰는 전체 훑기(all)는 ~11ms → ~21ms
+#![allow(non_snake_case)] // 크레이트 이름이 "SwiftLog"
+#![allow(dead_code, unused_imports)] // 서버 모듈 중 벤치에서 안 쓰는 부분 (테스트 모듈의 import 포함)
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// 서버 모듈을 main.rs와 같은 이름으로 이 크레이트 루트에 (crate:: 경로가 그대로 맞도록). LogStore가 닿는 것만
+#[path = "../src/parser.rs"]
+mod parser;
+#[path = "../src/writer.rs"]
+mod writer;
+#[path = "../src/udp.rs"]
+mod udp;
+#[path = "../src/tcp.rs"]
+mod tcp;
+#[path = "../src/log_domain.rs"]
+mod log_domain;
+#[path = "../src/log_store.rs"]
+mod log_store;
+#[path = "../src/log_index.rs"]
+mod log_index;
+#[path = "../src/console_select.rs"]
+mod console_select;
+#[path = "../src/backup_quota.rs"]
+mod backup_quota;
+#[path = "../src/config.rs"]
+mod config;
+#[path = "../src/pipeline.rs"]
+mod pipeline;
+#[path = "../src/diag.rs"]
+mod diag;
+#[path = "../src/stats.rs"]
+mod stats;
+#[path = "../src/subscribe.rs"]
+mod subscribe;
+#[path = "../src/segment.rs"]
+mod segment;
+#[path = "../src/rotation.rs"]
+mod rotation;
+#[path = "../src/routing.rs"]
+mod routing;
+#[path = "../src/format.rs"]
+mod format;
+
+use log_domain::{Log, LogLevel};
+use log_store::{LogStore, SelectQuery, TextTerm};
+
+const RECORDS: u64 = 200_000;
+
+fn store(index: bool) -> LogStore {
+    let mut store = LogStore::with_capacity(RECORDS as usize, 8);
+    if index {
+        store = store.with_index(60_000, true);
+    }
+    for i in 0..RECORDS {
+        let level = LogLevel::ALL[(i % 5) as usize];
+        // 10ms 간격 → 200k건이 약 33분
+        store.append(Log::new_unassigned(i * 10, level, (i % 1000) as u16, format!("request {i} took {}ms", i % 97)));
+    }
+    store
+}
+
+fn select(c: &mut Criterion) {
+    let queries = [
+        // ShowLogList
+        ("latest limit=50", SelectQuery { latest: true, limit: Some(50), ..Default::default() }),
+        ("level>=error latest limit=100", SelectQuery {
+            level_min: Some(LogLevel::Error), latest: true, limit: Some(100), ..Default::default()
+        }),
+        ("limit=50 offset=10000", SelectQuery { limit: Some(50), offset: 10_000, ..Default::default() }),
+        ("level>=error", SelectQuery { level_min: Some(LogLevel::Error), ..Default::default() }),
+        ("code=7", SelectQuery { code_in: Some(vec![7]), ..Default::default() }),
+        ("code=100..109", SelectQuery { code_range: Some((100, 109)), ..Default::default() }),
+        ("since/until 1 minute", SelectQuery { since_ms: Some(600_000), until_ms: Some(659_999), ..Default::default() }),
+        ("contains=took 3ms latest limit=20", SelectQuery {
+            contains: Some("took 3ms".into()), latest: true, limit: Some(20), ..Default::default()
+        }),
+        ("contains=request 123456 (scan)", SelectQuery { contains: Some("request 123456 ".into()), ..Default::default() }),
+        ("word:123456", SelectQuery { text: vec![TextTerm::Word("123456".into())], ..Default::default() }),
+        ("word:12345*", SelectQuery { text: vec![TextTerm::Prefix("12345".into())], ..Default::default() }),
+        ("phrase:took 3ms latest limit=20", SelectQuery {
+            text: vec![TextTerm::Phrase(vec!["took".into(), "3ms".into()])], latest: true, limit: Some(20), ..Default::default()
+        }),
+        ("all", SelectQuery::default()),
+    ];
+    for (group, index) in [("select_200k", false), ("select_200k_indexed", true)] {
+        let store = store(index);
+        let mut g = c.benchmark_group(group);
+        for (name, q) in &queries {
+            g.bench_function(*name, |b| b.iter(|| black_box(store.select(black_box(q))).len()));
+        }
+        g.finish();
+    }
+}
+
+criterion_group!(benches, select);
+criterion_main!(benches);
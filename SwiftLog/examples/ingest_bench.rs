@@ -0,0 +1,110 @@
+// 수신 처리량 벤치마크 (end-to-end)
+//
+// 실행 중인 서버에 UDP/TCP로 최대 속도로 로그를 보내고, 서버가 파일에 기록한 줄 수로
+// 초당 처리 레코드 수를 잽니다. 송신측이 보낸 양이 아니라 서버가 실제로 받아 쓴 양입니다.
+//
+//   cargo run --release -p SwiftLog -- --log-dir /tmp/swl-bench --auto-backup off \
+//       --max-file-bytes 100000000000 < /dev/null &          (측정 중 롤링되면 줄 수가 줄어듦)
+//   cargo run --release -p SwiftLog --example ingest_bench -- --log /tmp/swl-bench/app.log
+//
+// 옵션: --udp <addr> --tcp <addr> --secs <n> --senders <n> --log <path>
+//
+// 참고 수치 (1 vCPU VM, 송신 4스레드, 5초, release)
+//   5ms 폴링 루프      : udp ~4,500 rec/s   tcp ~158,000 rec/s
+//   수신 스레드 구조    : udp ~88,000 rec/s  tcp ~210,000 rec/s
+// 폴링 루프는 틱당 데이터그램 1개만 읽어 UDP가 200 datagram/s에 묶여 있었습니다.
+#![allow(non_snake_case)] // 크레이트 이름이 "SwiftLog"
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use SwiftLog::{LogLevel, Logger};
+
+struct Opts {
+    udp: SocketAddr,
+    tcp: SocketAddr,
+    secs: u64,
+    senders: usize,
+    log: String,
+}
+
+fn parse_opts() -> Opts {
+    let mut o = Opts {
+        udp: "127.0.0.1:9050".parse().unwrap(),
+        tcp: "127.0.0.1:9502".parse().unwrap(),
+        secs: 5,
+        senders: 4,
+        log: "logs/app.log".into(),
+    };
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    for pair in args.chunks(2) {
+        let (k, v) = (pair[0].as_str(), pair.get(1).map(String::as_str).unwrap_or(""));
+        match k {
+            "--udp" => o.udp = v.parse().expect("--udp"),
+            "--tcp" => o.tcp = v.parse().expect("--tcp"),
+            "--secs" => o.secs = v.parse().expect("--secs"),
+            "--senders" => o.senders = v.parse().expect("--senders"),
+            "--log" => o.log = v.to_string(),
+            _ => panic!("unknown option {k}"),
+        }
+    }
+    o
+}
+
+fn count_lines(path: &str) -> u64 {
+    File::open(path).map(|f| BufReader::new(f).split(b'\n').count() as u64).unwrap_or(0)
+}
+
+// 파일 줄 수가 500ms 동안 그대로면 서버가 다 쓴 것으로 봄
+fn settled_lines(path: &str) -> u64 {
+    let mut last = count_lines(path);
+    loop {
+        thread::sleep(Duration::from_millis(500));
+        let now = count_lines(path);
+        if now == last { return now; }
+        last = now;
+    }
+}
+
+fn run(name: &str, o: &Opts, connect: fn(SocketAddr) -> std::io::Result<Logger>, dst: SocketAddr) {
+    let before = settled_lines(&o.log);
+    let deadline = Instant::now() + Duration::from_secs(o.secs);
+    let start = Instant::now();
+
+    let handles: Vec<_> = (0..o.senders).map(|t| {
+        thread::spawn(move || {
+            let mut logger = connect(dst).expect("connect");
+            let mut sent = 0u64;
+            while Instant::now() < deadline {
+                for _ in 0..256 {
+                    logger.log(LogLevel::Info, 1000 + t as u16, "bench payload: user=42 action=login ok");
+                    sent += 1;
+                }
+            }
+            let _ = logger.flush();
+            (sent, logger.dropped_count())
+        })
+    }).collect();
+
+    let (mut sent, mut dropped) = (0u64, 0u64);
+    for h in handles {
+        let (s, d) = h.join().unwrap();
+        sent += s;
+        dropped += d;
+    }
+    let send_secs = start.elapsed().as_secs_f64();
+    let received = settled_lines(&o.log) - before;
+    println!(
+        "{name}: sent {sent} (client-dropped {dropped}), server wrote {received} in {send_secs:.1}s \
+         → {:.0} records/s",
+        received as f64 / send_secs
+    );
+}
+
+fn main() {
+    let o = parse_opts();
+    run("udp", &o, Logger::new_udp, o.udp);
+    run("tcp", &o, Logger::new_tcp, o.tcp);
+}
@@ -1,4 +1,5 @@
 // src/backup_quota.rs
+use std::fmt;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -11,43 +12,62 @@ pub enum QuotaError {
 }
 impl From<io::Error> for QuotaError { fn from(e: io::Error) -> Self { QuotaError::Io(e) } }
 
+impl fmt::Display for QuotaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaError::DirTooLarge { current, incoming, max } =>
+                write!(f, "backup dir quota exceeded: used={current} incoming={incoming} max={max}"),
+            QuotaError::FsFreeTooSmall { free, incoming, min_free } =>
+                write!(f, "not enough free space: free={free} incoming={incoming} min_free={min_free}"),
+            QuotaError::Io(e) => write!(f, "io error: {e}"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct QuotaConfig {
     pub max_dir_bytes: u64,
     pub min_fs_free_bytes: u64,
 }
 
-pub fn ensure_backup_quota(dir: &Path, new_file_estimate: u64, cfg: QuotaConfig) -> Result<(), QuotaError> {
-    let used = dir_size(dir).unwrap_or(0);
+/// 백업 한 건이 쓸 수 있는 양. 시작할 때 디렉터리 사용량/여유 공간을 한 번 재고,
+/// 쓰는 동안 `charge`로 누적해 넘는 순간 실패 (내용을 미리 만들어 크기를 잴 필요 없음)
+pub struct QuotaBudget {
+    cfg: QuotaConfig,
+    used: u64,
+    free: Option<u64>,
+    written: u64,
+}
 
-    // 1) 디렉토리 쿼터
-    if used.saturating_add(new_file_estimate) > cfg.max_dir_bytes {
-        return Err(QuotaError::DirTooLarge {
-            current: used,
-            incoming: new_file_estimate,
-            max: cfg.max_dir_bytes,
-        });
+impl QuotaBudget {
+    pub fn start(dir: &Path, cfg: QuotaConfig) -> Result<Self, QuotaError> {
+        let used = dir_size(dir).unwrap_or(0);
+        // 비윈도우 등: 여유공간 체크 생략(디렉토리 쿼터만 사용)
+        let free = if cfg.min_fs_free_bytes > 0 { fs_free_space_bytes(dir)? } else { None };
+        let mut b = Self { cfg, used, free, written: 0 };
+        b.charge(0)?;
+        Ok(b)
     }
 
-    // 2) 파일시스템 여유 공간
-    if cfg.min_fs_free_bytes > 0 {
-        match fs_free_space_bytes(dir)? {
-            Some(free) => {
-                if free.saturating_sub(new_file_estimate) < cfg.min_fs_free_bytes {
-                    return Err(QuotaError::FsFreeTooSmall {
-                        free,
-                        incoming: new_file_estimate,
-                        min_free: cfg.min_fs_free_bytes,
-                    });
-                }
-            }
-            None => {
-                // 비윈도우 등: 여유공간 체크 생략(디렉토리 쿼터만 사용)
+    /// n바이트를 더 써도 되는지 확인하고 누적
+    pub fn charge(&mut self, n: u64) -> Result<(), QuotaError> {
+        let incoming = self.written.saturating_add(n);
+
+        // 1) 디렉토리 쿼터
+        if self.used.saturating_add(incoming) > self.cfg.max_dir_bytes {
+            return Err(QuotaError::DirTooLarge { current: self.used, incoming, max: self.cfg.max_dir_bytes });
+        }
+
+        // 2) 파일시스템 여유 공간
+        if let Some(free) = self.free {
+            if free.saturating_sub(incoming) < self.cfg.min_fs_free_bytes {
+                return Err(QuotaError::FsFreeTooSmall { free, incoming, min_free: self.cfg.min_fs_free_bytes });
             }
         }
-    }
 
-    Ok(())
+        self.written = incoming;
+        Ok(())
+    }
 }
 
 pub fn dir_size(root: &Path) -> io::Result<u64> {
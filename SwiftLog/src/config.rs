@@ -0,0 +1,413 @@
+// src/config.rs
+// 서버 설정: 기본값 → TOML 파일(--config) → CLI 플래그 순으로 덮어씁니다.
+// 파일/플래그 모두 모르는 키는 거부합니다(오타가 기본값으로 조용히 넘어가지 않도록).
+//
+//   swiftlog --config swiftlog.toml --udp-bind 0.0.0.0:9050 --store-capacity 500000
+//
+// 예시 파일은 SwiftLog/swiftlog.example.toml 참고.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backup_quota::QuotaConfig;
+use crate::console_select::ConsoleSelect;
+use crate::diag::{self, DiagConfig};
+use crate::format::OutputFormat;
+use crate::pipeline::{OnFull, PipelineConfig};
+use crate::rotation::{Compression, RotateEvery, RotationConfig};
+use crate::routing::{self, RouteConfig};
+use crate::segment::SegmentConfig;
+use crate::subscribe::SubscribeConfig;
+use crate::writer::FsyncPolicy;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub net: NetConfig,
+    pub writer: WriterConfig,
+    pub store: StoreConfig,
+    pub segments: SegmentConfig,
+    pub pipeline: PipelineConfig,
+    pub auto_backup: AutoBackupConfig,
+    pub backup: BackupConfig,
+    pub diag: DiagConfig,
+    pub subscribe: SubscribeConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetConfig {
+    pub udp_bind: String,
+    pub tcp_bind: String,
+    /// HTTP API 리스너 (`/logs`, `/stats`, `/metrics`, `/backup`). 비우면 띄우지 않음
+    pub http_bind: String,
+    /// 동시에 받는 TCP 연결 수 (연결마다 스레드 하나). 넘으면 accept 직후 닫음
+    pub max_tcp_connections: usize,
+    /// 동시에 처리하는 HTTP 연결 수 (연결마다 스레드 하나, /backup은 그 스레드에서 실행). 넘으면 503
+    pub max_http_connections: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WriterConfig {
+    /// 로그 파일 디렉터리 / 파일 이름(<base>.log)
+    pub dir: String,
+    pub base: String,
+    /// 줄 형식: "tsv" | "jsonl" | "csv" | "syslog" (format.rs). 시작 시 파일 복구는 tsv일 때만
+    pub format: OutputFormat,
+    /// 디스크 동기화: "never" | "records:<n>" | "ms:<n>"
+    pub fsync: FsyncPolicy,
+    /// 롤링(크기/시각) + 롤링된 파일의 압축/보존 (rotation.rs)
+    pub rotation: RotationConfig,
+    /// 조건별 출력 파일 (routing.rs). 처음 맞는 route로, 없으면 <base>.log
+    pub routes: Vec<RouteConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreConfig {
+    /// 인메모리 링 버퍼 총 건수 / 샤드 수
+    pub capacity: usize,
+    pub shards: usize,
+    /// 시작 시 세그먼트(없으면 <base>.log 파일들)에서 최신 capacity건을 다시 채움 (replay.rs)
+    pub replay: bool,
+    /// 레벨/코드/시간 구간 보조 인덱스 (log_index.rs). 레코드당 ID 3개만큼 메모리를 더 씀
+    pub index: bool,
+    /// 시간 인덱스 구간 크기
+    pub index_bucket_ms: u64,
+    /// 메시지 토큰 인덱스 (word: / phrase: 검색, index가 켜져 있을 때만). 메시지 단어 수만큼 ID를 더 씀
+    pub text_index: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoBackupConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// SelectLog와 같은 쿼리 문법 ("" = 전체)
+    pub query: String,
+    /// 백업 파일 디렉터리 (파일명은 backup_<epoch>.<형식 확장자>). HTTP `/backup`도 여기에 씀
+    pub dir: String,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    pub max_dir_bytes: u64,
+    pub min_fs_free_bytes: u64,
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            udp_bind: "127.0.0.1:9050".into(),
+            tcp_bind: "127.0.0.1:9502".into(),
+            http_bind: String::new(),
+            max_tcp_connections: 256,
+            max_http_connections: 16,
+        }
+    }
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self { dir: "logs".into(), base: "app".into(), format: OutputFormat::Tsv, fsync: FsyncPolicy::Millis(1000), rotation: RotationConfig::default(),
+            routes: Vec::new() }
+    }
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self { capacity: 200_000, shards: 8, replay: true, index: true, index_bucket_ms: 60_000, text_index: true } // 총 20만 건, 8 샤드
+    }
+}
+
+impl Default for AutoBackupConfig {
+    fn default() -> Self {
+        Self { enabled: true, interval_secs: 60, query: "latest limit=10000".into(), dir: "auto".into(),
+            format: OutputFormat::Tsv }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            max_dir_bytes: 5 * 1024 * 1024 * 1024, // 5 GiB
+            min_fs_free_bytes: 512 * 1024 * 1024,  // 512 MiB
+        }
+    }
+}
+
+impl BackupConfig {
+    pub fn quota(&self) -> QuotaConfig {
+        QuotaConfig { max_dir_bytes: self.max_dir_bytes, min_fs_free_bytes: self.min_fs_free_bytes }
+    }
+}
+
+/// 파싱 결과: 서버를 띄우거나, 도움말만 출력
+pub enum Cli {
+    Run(Box<Config>),
+    Help,
+}
+
+const USAGE: &str = "\
+Usage: SwiftLog [--config <file.toml>] [options]
+
+Options (config file key in brackets):
+  --udp-bind <addr>               [net.udp_bind]
+  --tcp-bind <addr>               [net.tcp_bind]
+  --http-bind <addr>              [net.http_bind] (empty = off)
+  --max-tcp-connections <n>       [net.max_tcp_connections]
+  --max-http-connections <n>      [net.max_http_connections]
+  --log-dir <dir>                 [writer.dir]
+  --log-base <name>               [writer.base]
+  --log-format <fmt>              [writer.format] (tsv | jsonl | csv | syslog)
+  --fsync <policy>                [writer.fsync] (never | records:<n> | ms:<n>)
+  --max-file-bytes <n>            [writer.rotation.max_file_bytes] (0 = no size rotation)
+  --rotate-every <when>           [writer.rotation.every] (never | hourly | daily)
+  --log-keep-files <n>            [writer.rotation.keep_files]
+  --log-max-age-secs <n>          [writer.rotation.max_age_secs]
+  --log-max-total-bytes <n>       [writer.rotation.max_total_bytes] (0 = unlimited)
+  --log-compress <codec>          [writer.rotation.compress] (none | gzip | zstd)
+  --route <name>:<query>          [[writer.routes]] (repeatable; default rotation)
+  --store-capacity <n>            [store.capacity]
+  --store-shards <n>              [store.shards]
+  --no-replay                     [store.replay = false]
+  --no-store-index                [store.index = false]
+  --store-index-bucket-ms <n>     [store.index_bucket_ms]
+  --no-text-index                 [store.text_index = false]
+  --segments <on|off>             [segments.enabled]
+  --segment-dir <dir>             [segments.dir]
+  --max-segment-bytes <n>         [segments.max_segment_bytes]
+  --segment-index-every <n>       [segments.index_every]
+  --segment-max-total-bytes <n>   [segments.max_total_bytes] (0 = unlimited)
+  --parse-queue <n>               [pipeline.parse_queue]
+  --write-queue <n>               [pipeline.write_queue]
+  --udp-on-full <block|drop>      [pipeline.udp_on_full]
+  --tcp-on-full <block|drop>      [pipeline.tcp_on_full]
+  --write-on-full <block|drop>    [pipeline.write_on_full]
+  --auto-backup <on|off>          [auto_backup.enabled]
+  --auto-backup-interval <secs>   [auto_backup.interval_secs]
+  --auto-backup-query <query>     [auto_backup.query]
+  --auto-backup-dir <dir>         [auto_backup.dir]
+  --auto-backup-format <fmt>      [auto_backup.format] (tsv | jsonl | csv | syslog)
+  --backup-max-dir-bytes <n>      [backup.max_dir_bytes]
+  --backup-min-free-bytes <n>     [backup.min_fs_free_bytes]
+  --diag-level <level|off>        [diag.level]
+  --diag-file <path>              [diag.file]
+  --subscribe-bind <addr>         [subscribe.bind] (empty = off)
+  --subscribe-buffer <n>          [subscribe.buffer]
+  --max-subscribers <n>           [subscribe.max_subscribers]
+  -h, --help";
+
+pub fn usage() -> &'static str { USAGE }
+
+// 값 없이 쓰는 플래그
+const SWITCHES: &[&str] = &["--no-replay", "--no-store-index", "--no-text-index"];
+
+impl Config {
+    pub fn from_toml_str(s: &str) -> Result<Self, String> {
+        let cfg: Config = toml::from_str(s).map_err(|e| e.to_string())?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_toml_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// argv(프로그램 이름 제외) → 설정. `--config`는 위치와 상관없이 먼저 적용됩니다.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Cli, String> {
+        let mut pairs: Vec<(String, String)> = Vec::new();
+        let mut config_path = None;
+        let mut it = args.into_iter();
+        while let Some(arg) = it.next() {
+            if arg == "-h" || arg == "--help" { return Ok(Cli::Help); }
+            if !arg.starts_with("--") { return Err(format!("unexpected argument: {arg}")); }
+            if SWITCHES.contains(&arg.as_str()) {
+                pairs.push((arg, String::new()));
+                continue;
+            }
+            // --key=value / --key value 모두 허용
+            let (key, value) = match arg.split_once('=') {
+                Some((k, v)) => (k.to_string(), v.to_string()),
+                None => {
+                    let v = it.next().ok_or_else(|| format!("missing value for {arg}"))?;
+                    (arg, v)
+                }
+            };
+            if key == "--config" { config_path = Some(value); } else { pairs.push((key, value)); }
+        }
+
+        let mut cfg = match config_path {
+            Some(p) => Config::load(Path::new(&p))?,
+            None => Config::default(),
+        };
+        for (key, value) in &pairs {
+            cfg.apply_flag(key, value)?;
+        }
+        cfg.validate()?;
+        Ok(Cli::Run(Box::new(cfg)))
+    }
+
+    fn apply_flag(&mut self, key: &str, v: &str) -> Result<(), String> {
+        fn num<T: std::str::FromStr>(key: &str, v: &str) -> Result<T, String> {
+            v.parse().map_err(|_| format!("invalid value for {key}: {v}"))
+        }
+        fn on_full(key: &str, v: &str) -> Result<OnFull, String> {
+            OnFull::parse(v).ok_or_else(|| format!("invalid value for {key}: {v} (block|drop)"))
+        }
+        fn out_format(key: &str, v: &str) -> Result<OutputFormat, String> {
+            OutputFormat::parse(v).ok_or_else(|| format!("invalid value for {key}: {v} (tsv|jsonl|csv|syslog)"))
+        }
+        fn on_off(key: &str, v: &str) -> Result<bool, String> {
+            match v {
+                "on" | "true" | "1" => Ok(true),
+                "off" | "false" | "0" => Ok(false),
+                _ => Err(format!("invalid value for {key}: {v} (on|off)")),
+            }
+        }
+        match key {
+            "--udp-bind" => self.net.udp_bind = v.to_string(),
+            "--tcp-bind" => self.net.tcp_bind = v.to_string(),
+            "--http-bind" => self.net.http_bind = v.to_string(),
+            "--max-tcp-connections" => self.net.max_tcp_connections = num(key, v)?,
+            "--max-http-connections" => self.net.max_http_connections = num(key, v)?,
+            "--log-dir" => self.writer.dir = v.to_string(),
+            "--log-base" => self.writer.base = v.to_string(),
+            "--log-format" => self.writer.format = out_format(key, v)?,
+            "--fsync" => self.writer.fsync = FsyncPolicy::try_from(v.to_string())?,
+            "--max-file-bytes" => self.writer.rotation.max_file_bytes = num(key, v)?,
+            "--rotate-every" => self.writer.rotation.every = RotateEvery::parse(v)
+                .ok_or_else(|| format!("invalid value for {key}: {v} (never|hourly|daily)"))?,
+            "--log-keep-files" => self.writer.rotation.keep_files = num(key, v)?,
+            "--log-max-age-secs" => self.writer.rotation.max_age_secs = num(key, v)?,
+            "--log-max-total-bytes" => self.writer.rotation.max_total_bytes = num(key, v)?,
+            "--log-compress" => self.writer.rotation.compress = Compression::parse(v)
+                .ok_or_else(|| format!("invalid value for {key}: {v} (none|gzip|zstd)"))?,
+            "--route" => {
+                let (name, query) = v.split_once(':').ok_or_else(|| format!("invalid value for {key}: {v} (<name>:<query>)"))?;
+                self.writer.routes.push(RouteConfig { name: name.into(), query: query.into(), ..Default::default() });
+            }
+            "--store-capacity" => self.store.capacity = num(key, v)?,
+            "--store-shards" => self.store.shards = num(key, v)?,
+            "--no-replay" => self.store.replay = false,
+            "--no-store-index" => self.store.index = false,
+            "--store-index-bucket-ms" => self.store.index_bucket_ms = num(key, v)?,
+            "--no-text-index" => self.store.text_index = false,
+            "--segments" => self.segments.enabled = on_off(key, v)?,
+            "--segment-dir" => self.segments.dir = v.to_string(),
+            "--max-segment-bytes" => self.segments.max_segment_bytes = num(key, v)?,
+            "--segment-index-every" => self.segments.index_every = num(key, v)?,
+            "--segment-max-total-bytes" => self.segments.max_total_bytes = num(key, v)?,
+            "--parse-queue" => self.pipeline.parse_queue = num(key, v)?,
+            "--write-queue" => self.pipeline.write_queue = num(key, v)?,
+            "--udp-on-full" => self.pipeline.udp_on_full = on_full(key, v)?,
+            "--tcp-on-full" => self.pipeline.tcp_on_full = on_full(key, v)?,
+            "--write-on-full" => self.pipeline.write_on_full = on_full(key, v)?,
+            "--auto-backup" => self.auto_backup.enabled = on_off(key, v)?,
+            "--auto-backup-interval" => self.auto_backup.interval_secs = num(key, v)?,
+            "--auto-backup-query" => self.auto_backup.query = v.to_string(),
+            "--auto-backup-dir" => self.auto_backup.dir = v.to_string(),
+            "--auto-backup-format" => self.auto_backup.format = out_format(key, v)?,
+            "--backup-max-dir-bytes" => self.backup.max_dir_bytes = num(key, v)?,
+            "--backup-min-free-bytes" => self.backup.min_fs_free_bytes = num(key, v)?,
+            "--diag-level" => self.diag.level = v.to_string(),
+            "--diag-file" => self.diag.file = v.to_string(),
+            "--subscribe-bind" => self.subscribe.bind = v.to_string(),
+            "--subscribe-buffer" => self.subscribe.buffer = num(key, v)?,
+            "--max-subscribers" => self.subscribe.max_subscribers = num(key, v)?,
+            _ => return Err(format!("unknown option: {key}")),
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        diag::parse_level(&self.diag.level)?;
+        if self.net.max_tcp_connections == 0 { return Err("net.max_tcp_connections must be > 0".into()); }
+        if self.net.max_http_connections == 0 { return Err("net.max_http_connections must be > 0".into()); }
+        if self.store.shards == 0 { return Err("store.shards must be > 0".into()); }
+        if self.store.capacity == 0 { return Err("store.capacity must be > 0".into()); }
+        if self.store.index_bucket_ms == 0 { return Err("store.index_bucket_ms must be > 0".into()); }
+        if self.pipeline.parse_queue == 0 || self.pipeline.write_queue == 0 {
+            return Err("pipeline queue sizes must be > 0".into());
+        }
+        if self.subscribe.buffer == 0 { return Err("subscribe.buffer must be > 0".into()); }
+        if self.subscribe.max_subscribers == 0 { return Err("subscribe.max_subscribers must be > 0".into()); }
+        if self.writer.base.is_empty() { return Err("writer.base must not be empty".into()); }
+        routing::validate(&self.writer.base, &self.writer.routes)?;
+        if self.segments.max_segment_bytes == 0 || self.segments.index_every == 0 {
+            return Err("segments.max_segment_bytes and segments.index_every must be > 0".into());
+        }
+        if self.auto_backup.enabled && self.auto_backup.interval_secs == 0 {
+            return Err("auto_backup.interval_secs must be > 0".into());
+        }
+        ConsoleSelect::parse_backup_args(&self.auto_backup.query).map_err(|e| format!("auto_backup.query: {e}"))?;
+        Ok(())
+    }
+}
+
+// 시작 시 출력용: 그대로 --config 파일로 쓸 수 있는 TOML
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = toml::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(text.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: &[&str]) -> Vec<String> { v.iter().map(|s| s.to_string()).collect() }
+
+    #[test]
+    fn partial_file_keeps_defaults_and_unknown_keys_are_rejected() {
+        let cfg = Config::from_toml_str("[store]\ncapacity = 1000\n").unwrap();
+        assert_eq!(cfg.store.capacity, 1000);
+        assert_eq!(cfg.store.shards, 8);
+        assert_eq!(cfg.net, NetConfig::default());
+
+        assert!(Config::from_toml_str("[store]\ncapasity = 1000\n").is_err());
+        assert!(Config::from_toml_str("[stroe]\ncapacity = 1000\n").is_err());
+        assert!(Config::from_toml_str("[store]\nshards = 0\n").is_err());
+        assert!(Config::from_toml_str("[auto_backup]\nquery = \"level>=loud\"\n").is_err());
+    }
+
+    #[test]
+    fn example_file_matches_defaults() {
+        let cfg = Config::from_toml_str(include_str!("../swiftlog.example.toml")).unwrap();
+        assert_eq!(cfg, Config::default());
+    }
+
+    #[test]
+    fn cli_overrides_file_and_printed_config_round_trips() {
+        let path = std::env::temp_dir().join(format!("swiftlog_cfg_{}.toml", std::process::id()));
+        std::fs::write(&path, "[net]\nudp_bind = \"0.0.0.0:1\"\ntcp_bind = \"0.0.0.0:2\"\n\
+            [[writer.routes]]\nname = \"errors\"\nquery = \"level>=error\"\n[writer.routes.rotation]\nevery = \"daily\"\n").unwrap();
+
+        let cli = Config::from_args(args(&[
+            "--udp-bind=0.0.0.0:9", "--config", path.to_str().unwrap(), "--no-replay", "--auto-backup", "off",
+            "--route", "net:code=5000..5999",
+        ])).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let Cli::Run(cfg) = cli else { panic!("expected run") };
+        assert_eq!(cfg.net.udp_bind, "0.0.0.0:9");
+        assert_eq!(cfg.net.tcp_bind, "0.0.0.0:2");
+        assert!(!cfg.auto_backup.enabled);
+        assert!(!cfg.store.replay);
+        let routes: Vec<(&str, &str)> = cfg.writer.routes.iter().map(|r| (r.name.as_str(), r.query.as_str())).collect();
+        assert_eq!(routes, [("errors", "level>=error"), ("net", "code=5000..5999")]);
+        assert_eq!(cfg.writer.routes[0].rotation.every, RotateEvery::Daily);
+
+        assert_eq!(Config::from_toml_str(&cfg.to_string()).unwrap(), *cfg);
+        assert!(Config::from_args(args(&["--udp-bnd", "x"])).is_err());
+        assert!(Config::from_args(args(&["--store-shards", "many"])).is_err());
+        assert!(Config::from_args(args(&["--route", "app:level>=error"])).is_err());
+    }
+}
@@ -54,7 +54,9 @@ pub fn render_home(app: &str, ver: &str, total_logs: usize, animated: bool) {
     println!("Commands:");
     println!("  ShowLogList");
     println!("  SelectLog <query>");
-    println!("  BackupLog <path> [\"query\"]");
+    println!("  BackupLog <path> [\"query\"]  (형식은 확장자: .tsv .jsonl .csv .syslog)");
+    println!("  Queues        (파이프라인 큐 깊이/드롭)");
+    println!("  Stats [json]  (수신/거부/기록 통계)");
     println!("  ClearScreen   (또는 clear / cls)");
     println!("  Home          (또는 home)");
     println!("  Help          (또는 help / h)");
@@ -62,5 +64,11 @@ pub fn render_home(app: &str, ver: &str, total_logs: usize, animated: bool) {
     println!("예시:");
     println!("  SelectLog latest limit=100");
     println!("  SelectLog level>=Warn code=1000..1999");
+    println!("  SelectLog field.user_id=42 field.region=eu");
+    println!("  SelectLog service=game-server source=10.0.0.7");
+    println!("  SelectLog word:timeout word:req_7f3a phrase:\"connection reset\" word:disk*");
+    println!("  SelectLog level>=Warn and (code in (500, 503) or not service=api) latest limit=20");
+    println!("  SelectLog level!=Debug field.region in (eu, us) contains!=\"health check\"");
     println!("  BackupLog error_100.tsv \"level>=Error latest limit=100\"");
+    println!("  BackupLog warn.jsonl \"level>=Warn\"");
 }
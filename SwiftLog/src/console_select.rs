@@ -1,165 +1,290 @@
 use std::sync::Arc;
-use shell_words;
-use regex::Regex;
+use std::thread;
 use std::fs::File;
 use std::path::Path;
 use std::io::{BufWriter, Write};
 use crate::log_store::{LogStore, SelectQuery};
-use crate::log_domain::LogLevel;
+use crate::log_domain::Log;
+use swiftlog_proto::format_fields;
+use swiftlog_query::ParseError;
+use crate::format::{OutputFormat, Row};
 
 
-use crate::backup_quota::{ensure_backup_quota, QuotaConfig};
-use crate::proto::{BACKUP_MAX_DIR_BYTES, BACKUP_MIN_FS_FREE_BYTES};
+use crate::backup_quota::{QuotaBudget, QuotaConfig};
+use crate::stats;
 
-pub struct ConsoleSelect { store: Arc<LogStore> }
+pub struct ConsoleSelect { store: Arc<LogStore>, quota: QuotaConfig }
 impl ConsoleSelect {
-    pub fn new(store: Arc<LogStore>) -> Self { Self { store } }
+    pub fn new(store: Arc<LogStore>, quota: QuotaConfig) -> Self { Self { store, quota } }
 
-    pub fn store_len(&self) -> usize { self.store.len() } 
+    pub fn store_len(&self) -> usize { self.store.len() }
+    pub fn store(&self) -> &LogStore { &self.store }
 
     pub fn handle_show_list(&self) {
         let q = SelectQuery { latest: true, limit: Some(50), ..Default::default() };
         self.print(&q);
     }
     pub fn handle_select(&self, args: &str) {
-        let q = self.parse(args).unwrap_or_default();
-        self.print(&q);
+        match Self::parse(args) {
+            Ok(q) => self.print(&q),
+            Err(e) => {
+                eprintln!("bad query: {e}");
+                for line in e.caret(args).lines() { eprintln!("  {line}"); }
+            }
+        }
     }
     
     fn print(&self, q: &SelectQuery) {
         let rows = self.store.select(q);
-        println!("{:>6} | {:>13} | {:>5} | {:>5} | {}", "ID","TS(ms)","LVL","CODE","MESSAGE");
-        println!("{}", "-".repeat(80));
+        println!("{:>6} | {:>13} | {:>5} | {:>5} | {:<21} | MESSAGE", "ID","TS(ms)","LVL","CODE","SOURCE");
+        println!("{}", "-".repeat(104));
         for e in rows {
-            println!("{:>6} | {:>13} | {:>5} | {:>5} | {}",
-                e.id, e.ts_ms, format!("{:?}", e.level), e.code, truncate(&e.msg, 200));
+            println!("{:>6} | {:>13} | {:>5} | {:>5} | {:<21} | {}{}",
+                e.id, e.ts_ms, format!("{:?}", e.level), e.code, source_label(&e),
+                truncate(&e.msg, 200), fields_suffix(&e, " | "));
         }
     }
 
-    fn parse(&self, s: &str) -> Result<SelectQuery, String> {
-        let mut q = SelectQuery::default();
-        for tok in shell_words::split(s).map_err(|e| e.to_string())? {
-            if tok.eq_ignore_ascii_case("latest") { q.latest = true; continue; }
-            if let Some((k,v)) = tok.split_once('=') {
-                match k.to_ascii_lowercase().as_str() {
-                    "limit"  => q.limit = Some(v.parse::<usize>().map_err(|_| "limit")?),
-                    "offset" => q.offset = v.parse::<usize>().map_err(|_| "offset")?,
-                    "since"  => q.since_ms = Some(v.parse::<u64>().map_err(|_| "since")?),
-                    "until"  => q.until_ms = Some(v.parse::<u64>().map_err(|_| "until")?),
-                    "contains" => q.contains = Some(v.trim_matches('"').to_string()),
-                    "regex"  => q.regex = Some(Regex::new(v.trim_matches('"')).map_err(|e| e.to_string())?),
-                    "code_in" => {
-                        q.code_in = Some(v.split(',').filter_map(|t| t.parse::<u16>().ok()).collect());
-                    }
-                    k if k=="level>=" => q.level_min = Some(parse_level(v)?),
-                    k if k=="level<=" => q.level_max = Some(parse_level(v)?),
-                    "code" if v.contains("..") => {
-                        let (a,b)=v.split_once("..").ok_or("code range")?;
-                        q.code_range=Some((a.parse().map_err(|_|"code lo")?, b.parse().map_err(|_|"code hi")?));
-                    }
-                    other => return Err(format!("unknown key: {}", other)),
-                }
-            } else if tok.starts_with("level>=") { q.level_min=Some(parse_level(&tok[7..])?);
-            } else if tok.starts_with("level<=") { q.level_max=Some(parse_level(&tok[7..])?); }
+    /// SelectLog 쿼리 문법(swiftlog-query) → SelectQuery (콘솔, HTTP, 구독, 라우팅, 백업이 공유)
+    pub fn parse(s: &str) -> Result<SelectQuery, ParseError> {
+        swiftlog_query::parse(s).map(SelectQuery::from)
+    }
+
+    /// BackupLog 인자 → SelectQuery. 쿼리 전체를 따옴표로 감싸도(`"level>=Error latest"`) 그냥 써도 됨
+    pub fn parse_backup_args(args: &str) -> Result<SelectQuery, String> {
+        let args = args.trim();
+        let words = shell_words::split(args).map_err(|e| e.to_string())?;
+        let quoted = args.len() >= 2 && (args.starts_with('"') && args.ends_with('"')
+            || args.starts_with('\'') && args.ends_with('\''));
+        match words.as_slice() {
+            [whole] if quoted => Self::parse(whole),
+            _ => Self::parse(args),
         }
-        Ok(q)
+        .map_err(|e| e.to_string())
     }
 
-    pub fn handle_backup(&self, output_path: &str, args: &str) -> Result<(), String> {
-        // args → SelectQuery 변환. 프로젝트에 실제 파서가 있다면 그걸 쓰세요.
-        // (예: SelectQuery::from_shell_words(args)?). 없으면 기본값으로.
-        let query = if args.trim().is_empty() {
-            SelectQuery::default()
-        } else {
-            // TODO: 실제 파서가 있으면 아래 줄 교체
-            SelectQuery::default()
-        };
+    /// 이미 파싱한 쿼리로 백업 (HTTP `/backup`). 쓴 행 수를 돌려줌
+    pub fn backup_query(&self, output_path: &str, query: &SelectQuery, format: OutputFormat) -> Result<usize, String> {
+        handle_backup(&self.store, output_path, query, format, self.quota)
+    }
 
-        // self가 LogStore를 보관한다고 가정(Arc<LogStore>면 &*로 빌리면 됩니다)
-        let store: &crate::log_store::LogStore = &self.store;
-        super::console_select::handle_backup(store, output_path, &query)
+    /// `BackupLog <path> [query]`, 자동 백업. SelectLog와 같은 파서를 쓰고, 형식은 확장자로
+    /// (.jsonl / .csv / .syslog, 그 밖은 tsv). 메인 루프를 막지 않도록 백그라운드 스레드에서 쓰며
+    /// 쿼리 오류는 바로 Err, 끝나면 그 스레드에서 `done(쓴 행 수 또는 오류)`
+    pub fn start_backup<F>(&self, output_path: &str, args: &str, done: F) -> Result<(), String>
+    where
+        F: FnOnce(Result<usize, String>) + Send + 'static,
+    {
+        let query = Self::parse_backup_args(args).map_err(|e| format!("bad query {:?}: {e}", args.trim()))?;
+        let (store, quota, path) = (self.store.clone(), self.quota, output_path.to_string());
+        thread::Builder::new().name("swiftlog-backup".into())
+            .spawn(move || done(handle_backup(&store, &path, &query, OutputFormat::for_path(&path), quota)))
+            .map(|_| ())
+            .map_err(|e| format!("spawn failed: {e}"))
     }
 
 }
-fn parse_level(s: &str) -> Result<LogLevel,String>{
-    match s.to_ascii_lowercase().as_str(){
-        "trace"=>Ok(LogLevel::Trace),"debug"=>Ok(LogLevel::Debug),"info"=>Ok(LogLevel::Info),
-        "warn"=>Ok(LogLevel::Warn),"error"=>Ok(LogLevel::Error),_=>Err("invalid level".into())
-    }
+// 콘솔 SOURCE 컬럼: service@host, 정보가 없으면 peer
+fn source_label(log: &Log) -> String {
+    log.source.as_deref().map_or_else(|| "-".into(), |s| s.label())
+}
+// 필드가 있으면 "<sep>k=v k=v", 없으면 빈 문자열
+fn fields_suffix(log: &Log, sep: &str) -> String {
+    if log.fields.is_empty() { return String::new(); }
+    format!("{sep}{}", format_fields(log.fields.iter().map(|(k, v)| (k.as_str(), v.as_ref()))))
 }
 fn truncate(s:&str,n:usize)->String{ if s.len()<=n{s.to_string()}else{format!("{}…",&s[..n])} }
 
 
-    pub fn handle_backup(
+// 백업이 한 번에 메모리로 가져오는 행 수
+const BACKUP_PAGE: usize = 4096;
+
+/// 백업 파일 쓰기 (쓴 행 수). 성공/실패는 stats 백업 카운터에 집계
+pub fn handle_backup(
+    store: &LogStore,
+    output_path: &str,
+    query: &SelectQuery,
+    format: OutputFormat,
+    quota: QuotaConfig,
+) -> Result<usize, String> {
+    stats::add(&stats::BACKUPS_RUNNING, 1);
+    let res = write_backup(store, output_path, query, format, quota);
+    stats::BACKUPS_RUNNING.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+    stats::add(if res.is_ok() { &stats::BACKUPS_OK } else { &stats::BACKUPS_FAILED }, 1);
+    res
+}
+
+// <dir>/.<name>.partial 에 행 단위로 쓰고, 다 쓰면 fsync 후 <name>으로 rename.
+// 실패(쿼터 초과 포함)하면 임시 파일을 지우므로 반쯤 쓴 백업 파일은 남지 않음
+fn write_backup(
     store: &LogStore,
     output_path: &str,
     query: &SelectQuery,
-) -> Result<(), String> {
+    format: OutputFormat,
+    quota: QuotaConfig,
+) -> Result<usize, String> {
     let out_path = Path::new(output_path);
-    let parent = out_path.parent().ok_or_else(|| "invalid output path".to_string())?;
+    let (Some(parent), Some(name)) = (out_path.parent(), out_path.file_name()) else {
+        return Err("invalid output path".to_string());
+    };
     std::fs::create_dir_all(parent).map_err(|e| format!("create dir failed: {e}"))?;
+    let mut budget = QuotaBudget::start(parent, quota).map_err(|e| e.to_string())?;
 
-    // 1) 예상 크기 계산
-    let results = store.select(query);
-    let mut expected_bytes: u64 = 0;
-    for log in &results {
-        let msg = escape_tsv(&log.msg);
-        expected_bytes = expected_bytes
-            .saturating_add(num_len_u64(log.ts_ms) as u64)
-            .saturating_add(1) // \t
-            .saturating_add(3) // level 자리수 여유
-            .saturating_add(1) // \t
-            .saturating_add(num_len_u16(log.code) as u64)
-            .saturating_add(1) // \t
-            .saturating_add(msg.len() as u64)
-            .saturating_add(1); // \n
-    }
-
-    // 2) 쿼터 체크
-    let cfg = QuotaConfig {
-        max_dir_bytes: BACKUP_MAX_DIR_BYTES,
-        min_fs_free_bytes: BACKUP_MIN_FS_FREE_BYTES,
-    };
-    ensure_backup_quota(parent, expected_bytes, cfg)
-        .map_err(|e| format!("{e:?}"))?;
+    let tmp = parent.join(format!(".{}.partial", name.to_string_lossy()));
+    let res = write_rows(store, query, &tmp, format, &mut budget)
+        .and_then(|n| std::fs::rename(&tmp, out_path).map(|()| n).map_err(|e| format!("rename failed: {e}")));
+    if res.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    res
+}
 
-    // 3) 실제 쓰기
-    let file = File::create(&out_path)
-        .map_err(|e| format!("create file failed: {e}"))?;
+// 결과를 BACKUP_PAGE건씩 받아 한 행씩 만들어 바로 씀 (전체 결과를 메모리에 모으지 않음). 쓴 행 수
+fn write_rows(
+    store: &LogStore,
+    query: &SelectQuery,
+    tmp: &Path,
+    format: OutputFormat,
+    budget: &mut QuotaBudget,
+) -> Result<usize, String> {
+    let file = File::create(tmp).map_err(|e| format!("create file failed: {e}"))?;
     let mut w = BufWriter::new(file);
-    for log in &results {
-        let line = format!(
-            "{}\t{}\t{}\t{}\n",
-            log.ts_ms as u64,
-            log.level as u8,
-            log.code,
-            escape_tsv(&log.msg),
-        );
-        w.write_all(line.as_bytes())
-            .map_err(|e| format!("write failed: {e}"))?;
-    }
-    w.flush().map_err(|e| format!("flush failed: {e}"))?;
-    Ok(())
+    let f = format.formatter();
+    put(&mut w, budget, f.header())?;
+    let mut line = Vec::new();
+    let n = store.select_pages(query, BACKUP_PAGE, |rows| -> Result<(), String> {
+        for log in rows {
+            line.clear();
+            f.write_row(&mut line, &Row::from_log(log));
+            put(&mut w, budget, &line)?;
+        }
+        Ok(())
+    })?;
+    let file = w.into_inner().map_err(|e| format!("flush failed: {}", e.error()))?;
+    file.sync_all().map_err(|e| format!("sync failed: {e}"))?;
+    Ok(n)
 }
 
+// 쿼터 안에서만 씀
+fn put(w: &mut impl Write, budget: &mut QuotaBudget, bytes: &[u8]) -> Result<(), String> {
+    budget.charge(bytes.len() as u64).map_err(|e| e.to_string())?;
+    w.write_all(bytes).map_err(|e| format!("write failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_domain::LogLevel;
+    use crate::log_store::TextTerm;
 
-fn escape_tsv(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        match ch {
-            '\n' => out.push_str("\\n"),
-            '\r' => out.push_str("\\r"),
-            '\t' => out.push_str("\\t"),
-            _ => out.push(ch),
+    fn console(dir: &Path) -> ConsoleSelect {
+        let store = Arc::new(LogStore::with_capacity(100, 2));
+        for (ts, level, code, msg) in [
+            (1, LogLevel::Info, 10, "boot"),
+            (2, LogLevel::Error, 20, "disk full"),
+            (3, LogLevel::Warn, 30, "slow disk"),
+            (4, LogLevel::Error, 40, "net down"),
+        ] {
+            store.append(Log::new_unassigned(ts, level, code, msg));
         }
+        let _ = std::fs::remove_dir_all(dir);
+        let quota = QuotaConfig { max_dir_bytes: 1 << 20, min_fs_free_bytes: 0 };
+        ConsoleSelect::new(store, quota)
+    }
+
+    // 백그라운드 백업이 끝날 때까지 기다림
+    fn backup(c: &ConsoleSelect, path: &str, args: &str) -> Result<usize, String> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        c.start_backup(path, args, move |res| tx.send(res).unwrap())?;
+        rx.recv().unwrap()
+    }
+
+    fn codes(path: &Path) -> Vec<u16> {
+        std::fs::read_to_string(path).unwrap().lines()
+            .map(|l| swiftlog_proto::tsv::parse_line(l).unwrap().code).collect()
+    }
+
+    #[test]
+    fn backup_honors_quoted_and_bare_queries() {
+        let dir = std::env::temp_dir().join(format!("swiftlog_backuplog_{}", std::process::id()));
+        let c = console(&dir);
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        // 콘솔 예시 그대로: 쿼리 전체를 큰따옴표로
+        assert_eq!(backup(&c, &path("a.tsv"), "\"level>=Error latest limit=1\"").unwrap(), 1);
+        assert_eq!(codes(&dir.join("a.tsv")), [40]);
+        // 작은따옴표 / 따옴표 없이 / 값에만 따옴표
+        assert_eq!(backup(&c, &path("b.tsv"), "'level>=Warn'").unwrap(), 3);
+        assert_eq!(backup(&c, &path("c.tsv"), "level>=Warn code=20..30").unwrap(), 2);
+        assert_eq!(backup(&c, &path("d.tsv"), "contains=\"disk full\"").unwrap(), 1);
+        assert_eq!(codes(&dir.join("d.tsv")), [20]);
+        assert_eq!(backup(&c, &path("e.tsv"), "\"contains='slow disk' latest\"").unwrap(), 1);
+        // 인자 없으면 전체
+        assert_eq!(backup(&c, &path("all.tsv"), "").unwrap(), 4);
+        assert_eq!(codes(&dir.join("all.tsv")), [10, 20, 30, 40]);
+        let _ = std::fs::remove_dir_all(&dir);
     }
-    out
-}
 
-fn num_len_u64(mut x: u64) -> usize {
-    if x == 0 { return 1; }
-    let mut n = 0;
-    while x > 0 { x /= 10; n += 1; }
-    n
+    #[test]
+    fn text_terms_are_tokenized_like_messages() {
+        let q = ConsoleSelect::parse(r#"word:Timeout word:req-7f3a phrase:"Connection reset" WORD:disk* level>=warn"#).unwrap();
+        assert_eq!(q.text, [
+            TextTerm::Word("timeout".into()),
+            TextTerm::Phrase(vec!["req".into(), "7f3a".into()]),
+            TextTerm::Phrase(vec!["connection".into(), "reset".into()]),
+            TextTerm::Prefix("disk".into()),
+        ]);
+        assert_eq!(q.level_min, Some(LogLevel::Warn));
+        for bad in ["word:*", "word:--", "word:a-b*", "phrase:"] {
+            assert!(ConsoleSelect::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn boolean_queries_keep_top_level_conditions_for_the_index() {
+        let c = console(&std::env::temp_dir().join("swiftlog_unused"));
+        let q = ConsoleSelect::parse("level>=warn and (code=20 or contains=net) latest").unwrap();
+        assert_eq!((q.level_min, q.latest), (Some(LogLevel::Warn), true));
+        assert!(q.expr.is_some());
+        let codes = |s: &str| c.store().select(&ConsoleSelect::parse(s).unwrap()).iter().map(|l| l.code).collect::<Vec<_>>();
+        assert_eq!(codes("level>=warn and (code=20 or contains=net) latest"), [40, 20]);
+        assert_eq!(codes("not level=error"), [10, 30]);
+        assert_eq!(codes("code in (10, 40) or word:slow"), [10, 30, 40]);
+        assert_eq!(codes("level!=error code>=30"), [30]);
+        assert_eq!(ConsoleSelect::parse("level>=warn or").unwrap_err().pos, 14);
+    }
+
+    #[test]
+    fn bad_backup_query_is_reported_and_writes_nothing() {
+        let dir = std::env::temp_dir().join(format!("swiftlog_backuplog_bad_{}", std::process::id()));
+        let c = console(&dir);
+        for args in ["\"level>=Loud\"", "limit=many", "lates", "\"unterminated"] {
+            let path = dir.join("x.tsv");
+            let err = backup(&c, path.to_str().unwrap(), args).unwrap_err();
+            assert!(err.starts_with("bad query"), "{args}: {err}");
+            assert!(!path.exists());
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn quota_is_enforced_while_streaming_and_leaves_no_partial_file() {
+        let dir = std::env::temp_dir().join(format!("swiftlog_backuplog_quota_{}", std::process::id()));
+        let mut c = console(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("keep.tsv"), "old\n").unwrap();
+        // 기존 4바이트 + 헤더 없는 TSV 두 줄까지만 들어가는 쿼터
+        c.quota.max_dir_bytes = 4 + 40;
+        let keep = dir.join("keep.tsv");
+        let err = backup(&c, keep.to_str().unwrap(), "").unwrap_err();
+        assert!(err.contains("quota exceeded"), "{err}");
+        // 실패하면 기존 파일은 그대로, 임시 파일도 남지 않음
+        assert_eq!(std::fs::read_to_string(&keep).unwrap(), "old\n");
+        let names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, ["keep.tsv"]);
+
+        assert_eq!(backup(&c, keep.to_str().unwrap(), "level>=Error").unwrap(), 2);
+        assert_eq!(codes(&keep), [20, 40]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
-fn num_len_u16(x: u16) -> usize { num_len_u64(x as u64) }
\ No newline at end of file
@@ -0,0 +1,163 @@
+// src/diag.rs
+// SwiftLog 서버 자신의 진단 로그 (수집한 로그와는 별개)
+//
+//   diag!(Warn, "pipeline", "dropped {n} batch(es)");
+//
+// - 레벨 미만이면 원자 변수 하나만 읽고 끝납니다(포맷팅도 안 함). 기본 레벨은 info라서
+//   레코드/배치마다 찍는 debug·trace는 기본으로 꺼져 있습니다.
+// - target(구성 요소 이름)마다 초당 `rate_per_sec`건까지만 출력하고, 넘친 건수는 다음 구간에
+//   "suppressed N" 한 줄로 알립니다.
+// - 출력은 stderr, `file`을 지정하면 그 파일에도 덧붙입니다.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::log_domain::LogLevel;
+
+// LogLevel(0..=4) 위의 값 = 모두 끔
+const OFF: u8 = 5;
+const WINDOW_MS: u64 = 1000;
+
+static THRESHOLD: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+static RATE_PER_SEC: AtomicU32 = AtomicU32::new(20);
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+struct State {
+    file: Option<File>,
+    limiter: RateLimiter,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiagConfig {
+    /// trace | debug | info | warn | error | off
+    pub level: String,
+    /// 비우면 stderr만
+    pub file: String,
+    /// target당 초당 최대 출력 건수
+    pub rate_per_sec: u32,
+}
+
+impl Default for DiagConfig {
+    fn default() -> Self {
+        Self { level: "info".into(), file: String::new(), rate_per_sec: 20 }
+    }
+}
+
+/// "off"면 None, 모르는 이름이면 Err
+pub fn parse_level(s: &str) -> Result<Option<LogLevel>, String> {
+    if s.eq_ignore_ascii_case("off") { return Ok(None); }
+    LogLevel::parse(s).map(Some).ok_or_else(|| format!("invalid diag level: {s}"))
+}
+
+pub fn init(cfg: &DiagConfig) -> io::Result<()> {
+    let level = parse_level(&cfg.level).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let file = if cfg.file.is_empty() {
+        None
+    } else {
+        Some(OpenOptions::new().create(true).append(true).open(&cfg.file)?)
+    };
+    THRESHOLD.store(level.map_or(OFF, |l| l as u8), Ordering::Relaxed);
+    RATE_PER_SEC.store(cfg.rate_per_sec, Ordering::Relaxed);
+    *STATE.lock().unwrap() = Some(State { file, limiter: RateLimiter::default() });
+    Ok(())
+}
+
+#[inline]
+pub fn enabled(level: LogLevel) -> bool {
+    level as u8 >= THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// `diag!`에서 호출. 레벨 검사는 매크로가 먼저 합니다.
+pub fn emit(level: LogLevel, target: &'static str, args: fmt::Arguments<'_>) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let mut guard = STATE.lock().unwrap();
+    let state = guard.get_or_insert_with(|| State { file: None, limiter: RateLimiter::default() });
+
+    let mut out = String::new();
+    match state.limiter.admit(target, now, RATE_PER_SEC.load(Ordering::Relaxed)) {
+        Admit::Drop => return,
+        Admit::Pass { suppressed } => {
+            if suppressed > 0 {
+                out.push_str(&format!("{now} {:<5} [{target}] suppressed {suppressed} message(s)\n", "WARN"));
+            }
+            out.push_str(&format!("{now} {:<5} [{target}] {args}\n", level.as_str().to_ascii_uppercase()));
+        }
+    }
+    let _ = io::stderr().write_all(out.as_bytes());
+    if let Some(f) = state.file.as_mut() {
+        let _ = f.write_all(out.as_bytes());
+    }
+}
+
+macro_rules! diag {
+    ($lvl:ident, $target:expr, $($arg:tt)+) => {
+        if $crate::diag::enabled($crate::log_domain::LogLevel::$lvl) {
+            $crate::diag::emit($crate::log_domain::LogLevel::$lvl, $target, format_args!($($arg)+));
+        }
+    };
+}
+pub(crate) use diag;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Admit {
+    /// 출력. 직전 구간에서 버린 건수가 있으면 함께
+    Pass { suppressed: u64 },
+    Drop,
+}
+
+#[derive(Default)]
+struct RateLimiter {
+    // target → (구간 시작, 구간 내 건수, 버린 건수)
+    windows: HashMap<&'static str, (u64, u32, u64)>,
+}
+
+impl RateLimiter {
+    fn admit(&mut self, target: &'static str, now_ms: u64, rate: u32) -> Admit {
+        let w = self.windows.entry(target).or_insert((now_ms, 0, 0));
+        let mut suppressed = 0;
+        if now_ms.saturating_sub(w.0) >= WINDOW_MS {
+            suppressed = w.2;
+            *w = (now_ms, 0, 0);
+        }
+        if w.1 >= rate {
+            w.2 += 1;
+            return Admit::Drop;
+        }
+        w.1 += 1;
+        Admit::Pass { suppressed }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_per_target_and_reports_suppressed() {
+        let mut r = RateLimiter::default();
+        assert_eq!(r.admit("a", 0, 2), Admit::Pass { suppressed: 0 });
+        assert_eq!(r.admit("a", 10, 2), Admit::Pass { suppressed: 0 });
+        assert_eq!(r.admit("a", 20, 2), Admit::Drop);
+        assert_eq!(r.admit("a", 30, 2), Admit::Drop);
+        // 다른 target은 따로 셈
+        assert_eq!(r.admit("b", 30, 2), Admit::Pass { suppressed: 0 });
+        // 다음 구간 첫 출력에 버린 건수가 붙음
+        assert_eq!(r.admit("a", 1000, 2), Admit::Pass { suppressed: 2 });
+        assert_eq!(r.admit("a", 1001, 2), Admit::Pass { suppressed: 0 });
+    }
+
+    #[test]
+    fn levels_parse_with_off() {
+        assert_eq!(parse_level("OFF"), Ok(None));
+        assert_eq!(parse_level("debug"), Ok(Some(LogLevel::Debug)));
+        assert!(parse_level("verbose").is_err());
+    }
+}
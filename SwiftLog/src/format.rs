@@ -0,0 +1,355 @@
+// src/format.rs
+// 출력 형식: 라이브 기록(writer.format, route별 format)과 백업(BackupLog, /backup)이 같은 Formatter를 씁니다.
+//
+//   tsv    : ts \t level \t code \t msg [\t fields]   이스케이프 규칙은 swiftlog_proto::tsv (뷰어/복구와 공유)
+//   jsonl  : {"id":..,"ts_ms":..,"level":"Info","code":..,"msg":"..","fields":{..},"source":{..}} 한 줄씩
+//   csv    : RFC 4180, 첫 줄 헤더 ts_ms,level,code,msg,fields (파일마다, 롤링 후에도)
+//   syslog : RFC 5424 — <PRI>1 TIMESTAMP HOST APP PROCID MSGID [fields@32473 k="v"] MSG
+//            facility user(1), severity는 레벨에서 (Error=3, Warn=4, Info=6, Debug/Trace=7), MSGID = code
+//
+// 입력은 `Row` — 와이어 레코드(저장 전, msg가 UTF-8이 아닐 수 있음)와 저장된 Log 둘 다에서 만듭니다.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use swiftlog_proto::{format_fields, tsv, FieldValueRef, RecordRef};
+
+use crate::log_domain::{Log, LogLevel, Source};
+
+/// 포맷터 입력 한 행
+pub struct Row<'a> {
+    /// 저장소 ID (저장 전이거나 저장하지 않은 레코드는 None)
+    pub id: Option<u64>,
+    pub ts_ms: u64,
+    /// 와이어 값 그대로 (범위 밖일 수 있음)
+    pub level_raw: u8,
+    pub code: u16,
+    pub msg: &'a [u8],
+    pub fields: Vec<(&'a str, FieldValueRef<'a>)>,
+    pub source: Option<&'a Source>,
+}
+
+impl<'a> Row<'a> {
+    pub fn from_record(rec: &RecordRef<'a>, source: Option<&'a Source>) -> Self {
+        Self {
+            id: None,
+            ts_ms: rec.ts_ms,
+            level_raw: rec.level_raw,
+            code: rec.code,
+            msg: rec.msg,
+            fields: rec.fields.iter().collect(),
+            source,
+        }
+    }
+
+    pub fn from_log(log: &'a Log) -> Self {
+        Self {
+            id: Some(log.id),
+            ts_ms: log.ts_ms,
+            level_raw: log.level as u8,
+            code: log.code,
+            msg: log.msg.as_bytes(),
+            fields: log.fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())).collect(),
+            source: log.source.as_deref(),
+        }
+    }
+
+    fn level(&self) -> LogLevel { LogLevel::from_u8_lossy(self.level_raw) }
+
+    fn fields_text(&self) -> String { format_fields(self.fields.iter().copied()) }
+}
+
+pub trait Formatter: Send + Sync {
+    /// 파일 맨 앞에 한 번 (비어 있으면 없음)
+    fn header(&self) -> &'static [u8] { b"" }
+    /// 한 행을 줄바꿈까지 붙여 씀
+    fn write_row(&self, out: &mut Vec<u8>, row: &Row<'_>);
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Tsv,
+    Jsonl,
+    Csv,
+    Syslog,
+}
+
+impl OutputFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "tsv" => Some(OutputFormat::Tsv),
+            "jsonl" => Some(OutputFormat::Jsonl),
+            "csv" => Some(OutputFormat::Csv),
+            "syslog" => Some(OutputFormat::Syslog),
+            _ => None,
+        }
+    }
+
+    /// 백업 파일 확장자
+    pub fn ext(self) -> &'static str {
+        match self {
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Syslog => "syslog",
+        }
+    }
+
+    /// 백업 경로의 확장자로 고름 (.jsonl/.ndjson, .csv, .syslog — 그 밖은 tsv)
+    pub fn for_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("jsonl" | "ndjson") => OutputFormat::Jsonl,
+            Some("csv") => OutputFormat::Csv,
+            Some("syslog") => OutputFormat::Syslog,
+            _ => OutputFormat::Tsv,
+        }
+    }
+
+    pub fn formatter(self) -> Box<dyn Formatter> {
+        match self {
+            OutputFormat::Tsv => Box::new(Tsv),
+            OutputFormat::Jsonl => Box::new(JsonLines),
+            OutputFormat::Csv => Box::new(Csv),
+            OutputFormat::Syslog => Box::new(Syslog),
+        }
+    }
+}
+
+pub struct Tsv;
+pub struct JsonLines;
+pub struct Csv;
+pub struct Syslog;
+
+impl Formatter for Tsv {
+    fn write_row(&self, out: &mut Vec<u8>, row: &Row<'_>) {
+        tsv::write_line(out, row.ts_ms, row.level_raw, row.code, row.msg, row.fields.iter().copied());
+    }
+}
+
+impl Formatter for JsonLines {
+    fn write_row(&self, out: &mut Vec<u8>, row: &Row<'_>) {
+        let fields: Map<String, Value> = row.fields.iter().map(|(k, v)| {
+            let v = match *v {
+                FieldValueRef::Str(s) => json!(s),
+                FieldValueRef::I64(i) => json!(i),
+                FieldValueRef::F64(f) => json!(f), // NaN/inf → null
+                FieldValueRef::Bool(b) => json!(b),
+            };
+            (k.to_string(), v)
+        }).collect();
+        let source = row.source.map(|s| {
+            let mut o = json!({ "peer": s.peer.to_string() });
+            if let Some(id) = &s.identity {
+                o["service"] = json!(id.service);
+                o["host"] = json!(id.host);
+                o["pid"] = json!(id.pid);
+            }
+            o
+        });
+        let v = json!({
+            "id": row.id,
+            "ts_ms": row.ts_ms,
+            "level": row.level().as_str(),
+            "code": row.code,
+            "msg": String::from_utf8_lossy(row.msg),
+            "fields": fields,
+            "source": source,
+        });
+        // Value 직렬화는 실패하지 않음
+        let _ = serde_json::to_writer(&mut *out, &v);
+        out.push(b'\n');
+    }
+}
+
+impl Formatter for Csv {
+    fn header(&self) -> &'static [u8] { b"ts_ms,level,code,msg,fields\r\n" }
+
+    fn write_row(&self, out: &mut Vec<u8>, row: &Row<'_>) {
+        out.extend_from_slice(format!("{},{},{},", row.ts_ms, row.level().as_str(), row.code).as_bytes());
+        csv_cell(out, &String::from_utf8_lossy(row.msg));
+        out.push(b',');
+        csv_cell(out, &row.fields_text());
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+// 구분자/따옴표/줄바꿈이 있으면 큰따옴표로 감싸고 안의 "는 ""로
+fn csv_cell(out: &mut Vec<u8>, s: &str) {
+    if !s.contains([',', '"', '\n', '\r']) {
+        out.extend_from_slice(s.as_bytes());
+        return;
+    }
+    out.push(b'"');
+    out.extend_from_slice(s.replace('"', "\"\"").as_bytes());
+    out.push(b'"');
+}
+
+// RFC 5424 SD-ID: 사설 SD 이름은 name@<private enterprise number> 형식
+const SD_ID: &str = "fields@32473";
+const FACILITY_USER: u8 = 1;
+
+impl Formatter for Syslog {
+    fn write_row(&self, out: &mut Vec<u8>, row: &Row<'_>) {
+        let severity = match row.level() {
+            LogLevel::Error => 3,
+            LogLevel::Warn => 4,
+            LogLevel::Info => 6,
+            LogLevel::Debug | LogLevel::Trace => 7,
+        };
+        let (host, app, pid) = match row.source {
+            Some(s) => match &s.identity {
+                Some(id) => (nil_or(&id.host), nil_or(&id.service), id.pid.to_string()),
+                None => (s.peer.ip().to_string(), "-".to_string(), "-".to_string()),
+            },
+            None => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        let mut line = format!("<{}>1 {} {host} {app} {pid} {} ", FACILITY_USER * 8 + severity, rfc3339(row.ts_ms), row.code);
+        if row.fields.is_empty() {
+            line.push('-');
+        } else {
+            line.push('[');
+            line.push_str(SD_ID);
+            for (k, v) in &row.fields {
+                line.push_str(&format!(" {}=\"", sd_name(k)));
+                let text = match v {
+                    FieldValueRef::Str(s) => s.to_string(),
+                    other => other.to_string(),
+                };
+                // RFC 5424 이스케이프 + 줄바꿈/탭은 tsv처럼 \n \r \t (한 레코드 한 줄)
+                for ch in text.chars() {
+                    match ch {
+                        '"' | '\\' | ']' => { line.push('\\'); line.push(ch); }
+                        '\n' => line.push_str("\\n"),
+                        '\r' => line.push_str("\\r"),
+                        '\t' => line.push_str("\\t"),
+                        _ => line.push(ch),
+                    }
+                }
+                line.push('"');
+            }
+            line.push(']');
+        }
+        out.extend_from_slice(line.as_bytes());
+        if !row.msg.is_empty() {
+            out.push(b' ');
+            tsv::escape_msg(out, row.msg); // 한 줄 유지
+        }
+        out.push(b'\n');
+    }
+}
+
+// 헤더 필드: 비었거나 공백이 있으면 NILVALUE, 출력 가능한 ASCII만
+fn nil_or(s: &str) -> String {
+    if s.is_empty() { return "-".into(); }
+    s.chars().map(|c| if c.is_ascii_graphic() { c } else { '_' }).collect()
+}
+
+// SD-NAME: '=', ' ', ']', '"' 금지, 최대 32자. 다 걸러져 비면 `_` (빈 이름은 문법 위반)
+fn sd_name(k: &str) -> String {
+    let name: String = k.chars().filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"')).take(32).collect();
+    if name.is_empty() { "_".into() } else { name }
+}
+
+/// epoch ms → "YYYY-MM-DDTHH:MM:SS.mmmZ" (UTC)
+fn rfc3339(ts_ms: u64) -> String {
+    let secs = ts_ms / 1000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // civil_from_days (H. Hinnant), 1970-01-01 기준
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}.{:03}Z", rem / 3600, rem % 3600 / 60, rem % 60, ts_ms % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use swiftlog_proto::{FieldValue, Identity};
+
+    fn sample() -> Log {
+        let src = Source {
+            peer: "10.0.0.7:5000".parse().unwrap(),
+            identity: Some(Identity { service: "api".into(), host: "web-1".into(), pid: 42 }),
+        };
+        let mut log = Log::new_unassigned(1_700_000_000_123, LogLevel::Warn, 503, "slow, \"db\"\nretry\tnow")
+            .with_fields(vec![("user".into(), FieldValue::Str("a]b".into())), ("ms".into(), FieldValue::I64(250))])
+            .with_source(Some(Arc::new(src)));
+        log.id = 9;
+        log
+    }
+
+    fn render(f: OutputFormat, log: &Log) -> String {
+        let mut out = f.formatter().header().to_vec();
+        f.formatter().write_row(&mut out, &Row::from_log(log));
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn each_format_renders_one_line_per_row() {
+        let log = sample();
+        assert_eq!(render(OutputFormat::Tsv, &log),
+            "1700000000123\t3\t503\tslow, \"db\"\\nretry\\tnow\tuser=\"a]b\" ms=250\n");
+        assert_eq!(render(OutputFormat::Csv, &log),
+            "ts_ms,level,code,msg,fields\r\n1700000000123,Warn,503,\"slow, \"\"db\"\"\nretry\tnow\",\"user=\"\"a]b\"\" ms=250\"\r\n");
+        assert_eq!(render(OutputFormat::Syslog, &log),
+            "<12>1 2023-11-14T22:13:20.123Z web-1 api 42 503 [fields@32473 user=\"a\\]b\" ms=\"250\"] slow, \"db\"\\nretry\\tnow\n");
+
+        let line = render(OutputFormat::Jsonl, &log);
+        assert_eq!(line.matches('\n').count(), 1);
+        let v: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["id"], 9);
+        assert_eq!(v["level"], "Warn");
+        assert_eq!(v["msg"], log.msg);
+        assert_eq!(v["fields"]["ms"], 250);
+        assert_eq!(v["source"]["host"], "web-1");
+    }
+
+    #[test]
+    fn syslog_param_names_are_never_empty() {
+        let log = Log::new_unassigned(0, LogLevel::Info, 1, "")
+            .with_fields(vec![("=".into(), FieldValue::I64(1)), (" ".into(), FieldValue::I64(2)), ("한글".into(), FieldValue::I64(3))]);
+        assert_eq!(render(OutputFormat::Syslog, &log),
+            "<14>1 1970-01-01T00:00:00.000Z - - - 1 [fields@32473 _=\"1\" _=\"2\" _=\"3\"]\n");
+    }
+
+    #[test]
+    fn syslog_field_values_stay_on_one_line() {
+        let log = Log::new_unassigned(0, LogLevel::Info, 1, "")
+            .with_fields(vec![("sql".into(), FieldValue::Str("select 1\r\nfrom t\twhere".into()))]);
+        assert_eq!(render(OutputFormat::Syslog, &log),
+            "<14>1 1970-01-01T00:00:00.000Z - - - 1 [fields@32473 sql=\"select 1\\r\\nfrom t\\twhere\"]\n");
+    }
+
+    #[test]
+    fn live_tsv_keeps_raw_message_bytes() {
+        let msg = b"bad \xFF\xFE utf8\tand\\ctl\n";
+        let row = Row { id: None, ts_ms: 5, level_raw: 9, code: 1, msg, fields: Vec::new(), source: None };
+        let mut out = Vec::new();
+        Tsv.write_row(&mut out, &row);
+        let text = String::from_utf8(out).unwrap();
+        let back = tsv::parse_line(text.trim_end_matches('\n')).unwrap();
+        assert_eq!((back.level_raw, back.msg.as_slice()), (9, &msg[..]));
+    }
+
+    #[test]
+    fn formats_are_chosen_by_name_and_extension() {
+        for f in [OutputFormat::Tsv, OutputFormat::Jsonl, OutputFormat::Csv, OutputFormat::Syslog] {
+            assert_eq!(OutputFormat::parse(f.ext()), Some(f));
+            assert_eq!(OutputFormat::for_path(&format!("auto/backup_1.{}", f.ext())), f);
+        }
+        assert_eq!(OutputFormat::for_path("x.NDJSON"), OutputFormat::Jsonl);
+        assert_eq!(OutputFormat::for_path("logs.txt"), OutputFormat::Tsv);
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(rfc3339(951_782_400_000), "2000-02-29T00:00:00.000Z");
+    }
+}
@@ -0,0 +1,529 @@
+// src/http.rs
+// 조회/운영용 최소 HTTP/1.1 리스너 (net.http_bind, 기본 꺼짐)
+//
+//   GET  /logs?q=<SelectLog 쿼리>        → {"count": n, "rows": [...]}
+//                                          (limit 없으면 100건, limit은 1000 / offset은 100000까지)
+//   GET  /stats                          → stats::Snapshot JSON
+//   GET  /metrics                        → Prometheus 텍스트 형식 (metrics.rs)
+//   POST /backup?q=<쿼리>[&name=<파일>][&format=tsv|jsonl|csv|syslog]
+//                                        → <auto_backup.dir>/<파일> 로 BackupLog (형식은 format, 없으면 확장자)
+//                                          {"path": .., "rows": 쓴 행 수}
+//
+//   curl 'http://127.0.0.1:9580/logs?q=level>=Warn+latest+limit=100'
+//
+// 외부 크레이트 없이 요청 줄과 헤더만 읽고, 응답마다 연결을 닫습니다(Connection: close).
+// 인자는 모두 쿼리 문자열로 받고 요청 본문은 읽어서 버립니다. 스크립트/대시보드의
+// 폴링 용도라 연결마다 스레드 하나면 충분합니다. 동시 연결은 net.max_http_connections까지,
+// 넘으면 바로 503.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::console_select::ConsoleSelect;
+use crate::diag::diag;
+use crate::format::OutputFormat;
+use crate::log_domain::{FieldValue, Log};
+use crate::tcp::OpenConn;
+use crate::{metrics, stats};
+
+// 요청 줄 + 헤더 합계 상한. 넘으면 431
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+// 버리고 읽을 요청 본문 상한. 넘으면 413
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+// 상한에 걸린 연결에 503을 쓰고 닫을 때 (accept 스레드를 오래 막지 않도록)
+const REFUSE_TIMEOUT: Duration = Duration::from_millis(100);
+// /logs 응답 행 수: 기본값 / 상한 (응답 본문을 한 번에 만들므로). offset도 그만큼 골라야 해서 상한
+const LOGS_DEFAULT_LIMIT: usize = 100;
+const LOGS_MAX_LIMIT: usize = 1000;
+const LOGS_MAX_OFFSET: usize = 100_000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// 퍼센트 디코딩한 쿼리 파라미터 (순서 유지, 중복 허용)
+    pub query: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(body: String) -> Self {
+        Self { status: 200, content_type: "application/json", body: body.into_bytes() }
+    }
+
+    fn value(v: Value) -> Self { Self::json(v.to_string()) }
+
+    pub fn text(content_type: &'static str, body: String) -> Self {
+        Self { status: 200, content_type, body: body.into_bytes() }
+    }
+
+    pub fn error(status: u16, msg: &str) -> Self {
+        let body = serde_json::json!({ "error": msg }).to_string();
+        Self { status, content_type: "application/json", body: body.into_bytes() }
+    }
+
+    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        write!(
+            w,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status, reason(self.status), self.content_type, self.body.len()
+        )?;
+        w.write_all(&self.body)?;
+        w.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        431 => "Request Header Fields Too Large",
+        _ => "Error",
+    }
+}
+
+/// 라우팅. 조회는 LogStore, 백업은 ConsoleSelect의 BackupLog 경로를 그대로 씁니다.
+pub struct Api {
+    pub console: Arc<ConsoleSelect>,
+    /// `/backup` 파일을 쓸 디렉터리 (auto_backup.dir). 파일 이름만 받고 경로는 받지 않음
+    pub backup_dir: String,
+}
+
+impl Api {
+    pub fn handle(&self, req: &Request) -> Response {
+        match (req.method.as_str(), req.path.as_str()) {
+            ("GET", "/logs") => self.logs(req),
+            ("GET", "/stats") => Response::json(stats::snapshot(self.console.store()).to_json()),
+            ("GET", "/metrics") => Response::text(metrics::CONTENT_TYPE, metrics::render(self.console.store())),
+            ("POST", "/backup") => self.backup(req),
+            (_, "/logs" | "/stats" | "/metrics" | "/backup") => Response::error(405, "method not allowed"),
+            _ => Response::error(404, "no such endpoint"),
+        }
+    }
+
+    fn logs(&self, req: &Request) -> Response {
+        let mut q = match ConsoleSelect::parse(req.param("q").unwrap_or("")) {
+            Ok(q) => q,
+            Err(e) => return Response::error(400, &format!("bad query: {e}")),
+        };
+        match q.limit {
+            None => q.limit = Some(LOGS_DEFAULT_LIMIT),
+            Some(l) if l > LOGS_MAX_LIMIT => return Response::error(400, &format!("limit must be at most {LOGS_MAX_LIMIT}")),
+            Some(_) => {}
+        }
+        if q.offset > LOGS_MAX_OFFSET {
+            return Response::error(400, &format!("offset must be at most {LOGS_MAX_OFFSET}"));
+        }
+        let rows: Vec<Value> = self.console.store().select(&q).iter().map(|l| log_json(l)).collect();
+        Response::value(json!({ "count": rows.len(), "rows": rows }))
+    }
+
+    fn backup(&self, req: &Request) -> Response {
+        let q = match ConsoleSelect::parse(req.param("q").unwrap_or("")) {
+            Ok(q) => q,
+            Err(e) => return Response::error(400, &format!("bad query: {e}")),
+        };
+        let format = match req.param("format").map(OutputFormat::parse) {
+            Some(None) => return Response::error(400, "format must be tsv, jsonl, csv or syslog"),
+            Some(f) => f,
+            None => None,
+        };
+        let name = match req.param("name") {
+            Some(n) if !is_plain_file_name(n) => return Response::error(400, "name must be a plain file name"),
+            Some(n) => n.to_string(),
+            None => {
+                let epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                format!("backup_{epoch}.{}", format.unwrap_or_default().ext())
+            }
+        };
+        let format = format.unwrap_or_else(|| OutputFormat::for_path(&name));
+        let path = format!("{}/{name}", self.backup_dir);
+        match self.console.backup_query(&path, &q, format) {
+            Ok(rows) => Response::value(json!({ "path": path, "rows": rows })),
+            Err(e) => Response::error(500, &format!("backup failed: {e}")),
+        }
+    }
+}
+
+// 디렉터리 이동/숨김 파일 금지: 백업은 backup_dir 바로 아래에만
+fn is_plain_file_name(n: &str) -> bool {
+    !n.is_empty() && !n.starts_with('.') && !n.contains(['/', '\\', '\0'])
+}
+
+/// 조회 결과 한 행. 필드 값은 JSON 타입 그대로 (문자열/정수/실수/불리언)
+pub fn log_json(log: &Log) -> Value {
+    let fields: Map<String, Value> = log.fields.iter().map(|(k, v)| {
+        let v = match v {
+            FieldValue::Str(s) => json!(s),
+            FieldValue::I64(i) => json!(i),
+            FieldValue::F64(f) => json!(f), // NaN/inf → null
+            FieldValue::Bool(b) => json!(b),
+        };
+        (k.clone(), v)
+    }).collect();
+    let source = log.source.as_deref().map(|s| {
+        let mut o = json!({ "peer": s.peer.to_string() });
+        if let Some(id) = &s.identity {
+            o["service"] = json!(id.service);
+            o["host"] = json!(id.host);
+            o["pid"] = json!(id.pid);
+        }
+        o
+    });
+    json!({
+        "id": log.id,
+        "ts_ms": log.ts_ms,
+        "level": log.level.as_str(),
+        "code": log.code,
+        "msg": log.msg,
+        "fields": fields,
+        "source": source,
+    })
+}
+
+pub struct HttpServer {
+    listener: TcpListener,
+    max_connections: usize,
+}
+
+impl HttpServer {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr)?, max_connections: usize::MAX })
+    }
+
+    /// 동시 연결 상한 (넘는 연결은 503으로 바로 닫음)
+    pub fn with_max_connections(mut self, n: usize) -> Self {
+        self.max_connections = n;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> { self.listener.local_addr() }
+
+    /// accept 스레드 시작. 연결마다 스레드 하나가 요청 하나를 처리하고 닫습니다.
+    pub fn spawn(self, api: Arc<Api>) -> io::Result<JoinHandle<()>> {
+        let open = Arc::new(AtomicUsize::new(0));
+        thread::Builder::new().name("swiftlog-http".into()).spawn(move || {
+            for incoming in self.listener.incoming() {
+                let mut stream = match incoming {
+                    Ok(s) => s,
+                    Err(e) => {
+                        diag!(Warn, "http", "accept failed: {e}");
+                        continue;
+                    }
+                };
+                let Some(guard) = OpenConn::try_new(&open, self.max_connections, &stats::HTTP_CONNECTIONS_OPEN) else {
+                    diag!(Warn, "http", "refusing connection: {} open", self.max_connections);
+                    stats::add(&stats::HTTP_CONNECTIONS_REFUSED, 1);
+                    refuse(&mut stream);
+                    continue;
+                };
+                let api = api.clone();
+                let spawned = thread::Builder::new().name("swiftlog-http-conn".into()).spawn(move || {
+                    if let Err(e) = serve(stream, &api, guard) {
+                        diag!(Debug, "http", "connection error: {e}");
+                    }
+                });
+                if let Err(e) = spawned {
+                    diag!(Warn, "http", "cannot serve connection: {e}");
+                }
+            }
+        })
+    }
+}
+
+// 503을 쓰고 받은 요청은 잠깐만 읽어 버림 (안 읽고 닫으면 RST로 응답이 사라질 수 있음).
+// accept 스레드에서 돌므로 읽기/쓰기 모두 짧은 시한
+fn refuse(stream: &mut TcpStream) {
+    let _ = stream.set_write_timeout(Some(REFUSE_TIMEOUT));
+    let _ = stream.set_read_timeout(Some(REFUSE_TIMEOUT));
+    if Response::error(503, "too many connections").write_to(stream).is_ok() {
+        let _ = stream.shutdown(std::net::Shutdown::Write);
+        let _ = io::copy(&mut stream.take(MAX_HEAD_BYTES as u64 + MAX_BODY_BYTES), &mut io::sink());
+    }
+}
+
+fn serve(stream: TcpStream, api: &Api, slot: OpenConn) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut out = stream;
+
+    let resp = match read_head(&mut reader)? {
+        Ok((_, len)) if len > MAX_BODY_BYTES => Response::error(413, "request body too large"),
+        Ok((head, len)) => match parse_request_line(&head) {
+            Some(req) => {
+                // 본문은 쓰지 않지만 안 읽고 닫으면 클라이언트가 RST를 받을 수 있음
+                io::copy(&mut (&mut reader).take(len), &mut io::sink())?;
+                diag!(Debug, "http", "{} {}", req.method, req.path);
+                api.handle(&req)
+            }
+            None => Response::error(400, "malformed request line"),
+        },
+        Err(resp) => resp,
+    };
+    drop(slot); // 응답을 쓰기 전에 자리를 비움 (받은 쪽이 바로 다음 요청을 보낼 수 있게)
+    resp.write_to(&mut out)
+}
+
+// 빈 줄까지 읽어 (요청 줄, Content-Length)를 돌려줌. 나머지 헤더는 버림
+fn read_head(r: &mut impl BufRead) -> io::Result<Result<(String, u64), Response>> {
+    let mut first = String::new();
+    let mut content_length = 0u64;
+    let mut total = 0usize;
+    loop {
+        // 상한을 읽는 동안 적용 (줄바꿈 없이 계속 보내도 남은 한도 + 1바이트까지만 버퍼링)
+        let mut buf = Vec::new();
+        let n = r.by_ref().take((MAX_HEAD_BYTES - total + 1) as u64).read_until(b'\n', &mut buf)?;
+        total += n;
+        if total > MAX_HEAD_BYTES {
+            return Ok(Err(Response::error(431, "request head too large")));
+        }
+        let line = String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if n == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+        if first.is_empty() {
+            first = line;
+        } else if let Some((k, v)) = line.split_once(':') {
+            if k.trim().eq_ignore_ascii_case("content-length") {
+                match v.trim().parse() {
+                    Ok(n) => content_length = n,
+                    Err(_) => return Ok(Err(Response::error(400, "bad content-length"))),
+                }
+            }
+        }
+    }
+    Ok(Ok((first, content_length)))
+}
+
+/// "GET /path?a=1&b=x%20y HTTP/1.1" → Request
+pub fn parse_request_line(line: &str) -> Option<Request> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let (path, qs) = target.split_once('?').unwrap_or((target, ""));
+    let query = qs
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            Some((percent_decode(k)?, percent_decode(v)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Request { method, path: percent_decode(path)?, query })
+}
+
+// application/x-www-form-urlencoded 규칙: %XX, '+'는 공백. 결과가 UTF-8이 아니면 None
+fn percent_decode(s: &str) -> Option<String> {
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(b.get(i + 1..i + 3)?).ok()?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup_quota::QuotaConfig;
+    use crate::log_domain::LogLevel;
+    use crate::log_store::LogStore;
+
+    fn request(addr: SocketAddr, method: &str, target: &str) -> (String, String) {
+        let mut s = TcpStream::connect(addr).unwrap();
+        write!(s, "{method} {target} HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\n{{}}").unwrap();
+        let mut text = String::new();
+        s.read_to_string(&mut text).unwrap();
+        let (head, body) = text.split_once("\r\n\r\n").unwrap();
+        (head.to_string(), body.to_string())
+    }
+
+    fn serve_api(store: Arc<LogStore>, backup_dir: &str) -> SocketAddr {
+        let quota = QuotaConfig { max_dir_bytes: u64::MAX, min_fs_free_bytes: 0 };
+        let console = Arc::new(ConsoleSelect::new(store, quota));
+        let server = HttpServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        server.spawn(Arc::new(Api { console, backup_dir: backup_dir.into() })).unwrap();
+        addr
+    }
+
+    #[test]
+    fn request_line_and_query_are_decoded() {
+        let r = parse_request_line("GET /logs?q=level%3E%3DError+latest&x HTTP/1.1\r\n").unwrap();
+        assert_eq!(r.method, "GET");
+        assert_eq!(r.path, "/logs");
+        assert_eq!(r.param("q"), Some("level>=Error latest"));
+        assert_eq!(r.param("x"), Some(""));
+        assert!(parse_request_line("GET /a?q=%zz HTTP/1.1").is_none());
+        assert!(parse_request_line("GET /a").is_none());
+    }
+
+    #[test]
+    fn head_limit_applies_while_reading() {
+        // 줄바꿈 없는 끝없는 입력도 8 KiB 남짓에서 끊김
+        let mut endless = BufReader::new(io::repeat(b'a'));
+        let resp = read_head(&mut endless).unwrap().err().unwrap();
+        assert_eq!(resp.status, 431);
+        let mut ok = BufReader::new(&b"GET / HTTP/1.1\r\nContent-Length: 3\r\n\r\n"[..]);
+        assert_eq!(read_head(&mut ok).unwrap().ok().unwrap(), ("GET / HTTP/1.1\r\n".to_string(), 3));
+    }
+
+    #[test]
+    fn connections_over_the_limit_get_503() {
+        let quota = QuotaConfig { max_dir_bytes: u64::MAX, min_fs_free_bytes: 0 };
+        let console = Arc::new(ConsoleSelect::new(Arc::new(LogStore::with_capacity(10, 1)), quota));
+        let server = HttpServer::bind("127.0.0.1:0").unwrap().with_max_connections(1);
+        let addr = server.local_addr().unwrap();
+        server.spawn(Arc::new(Api { console, backup_dir: String::new() })).unwrap();
+
+        // 요청을 보내지 않고 자리만 차지
+        let idle = TcpStream::connect(addr).unwrap();
+        let (head, body) = request(addr, "GET", "/stats");
+        assert!(head.starts_with("HTTP/1.1 503 Service Unavailable"), "{head}");
+        assert!(body.contains("too many connections"), "{body}");
+
+        // 닫으면 자리가 비어 다시 받음
+        drop(idle);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            let (head, _) = request(addr, "GET", "/stats");
+            if head.starts_with("HTTP/1.1 200") { break; }
+            assert!(std::time::Instant::now() < deadline, "{head}");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn stats_and_metrics_endpoints() {
+        let addr = serve_api(Arc::new(LogStore::with_capacity(10, 1)), "unused");
+
+        let (head, body) = request(addr, "GET", "/stats");
+        assert!(head.starts_with("HTTP/1.1 200 OK"), "{head}");
+        assert!(head.contains("Content-Type: application/json"));
+        let v: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(v["store_capacity"], 10);
+        assert!(v["records_parsed"].is_u64());
+
+        let (head, body) = request(addr, "GET", "/metrics");
+        assert!(head.contains("Content-Type: text/plain; version=0.0.4"), "{head}");
+        assert!(body.contains("swiftlog_store_capacity 10\n"), "{body}");
+        assert!(body.contains("swiftlog_store_shard_records{shard=\"0\"} 0\n"), "{body}");
+
+        let (head, _) = request(addr, "GET", "/nope");
+        assert!(head.starts_with("HTTP/1.1 404"), "{head}");
+        let (head, _) = request(addr, "POST", "/stats");
+        assert!(head.starts_with("HTTP/1.1 405"), "{head}");
+    }
+
+    #[test]
+    fn logs_limit_has_a_default_and_a_maximum() {
+        let store = Arc::new(LogStore::with_capacity(2000, 2));
+        for i in 0..1500 {
+            store.append(Log::new_unassigned(i, LogLevel::Info, 1, "m"));
+        }
+        let addr = serve_api(store, "");
+        let count = |target: &str| {
+            let (head, body) = request(addr, "GET", target);
+            assert!(head.starts_with("HTTP/1.1 200"), "{head} {body}");
+            serde_json::from_str::<Value>(&body).unwrap()["count"].clone()
+        };
+        assert_eq!(count("/logs"), LOGS_DEFAULT_LIMIT);
+        assert_eq!(count("/logs?q=latest"), LOGS_DEFAULT_LIMIT);
+        assert_eq!(count("/logs?q=limit%3D1000"), 1000);
+        for target in ["/logs?q=limit%3D1001", "/logs?q=limit%3D10+offset%3D100001"] {
+            let (head, body) = request(addr, "GET", target);
+            assert!(head.starts_with("HTTP/1.1 400"), "{target}: {head}");
+            assert!(body.contains("at most"), "{body}");
+        }
+    }
+
+    #[test]
+    fn logs_query_and_backup() {
+        let store = Arc::new(LogStore::with_capacity(100, 2));
+        store.append(Log::new_unassigned(1, LogLevel::Info, 10, "started"));
+        store.append(Log::new_unassigned(2, LogLevel::Warn, 20, "slow \"disk\"")
+            .with_fields(vec![("ms".into(), FieldValue::I64(830)), ("dev".into(), FieldValue::Str("sda".into()))]));
+        store.append(Log::new_unassigned(3, LogLevel::Error, 30, "disk failed"));
+        let dir = std::env::temp_dir().join(format!("swiftlog_http_{}", std::process::id()));
+        let addr = serve_api(store, dir.to_str().unwrap());
+
+        let (head, body) = request(addr, "GET", "/logs?q=level%3E%3DWarn+latest+limit%3D10");
+        assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+        let v: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(v["count"], 2);
+        assert_eq!(v["rows"][0]["msg"], "disk failed");
+        assert_eq!(v["rows"][1]["level"], "Warn");
+        assert_eq!(v["rows"][1]["msg"], "slow \"disk\"");
+        assert_eq!(v["rows"][1]["fields"]["ms"], 830);
+        assert_eq!(v["rows"][1]["fields"]["dev"], "sda");
+        assert!(v["rows"][1]["source"].is_null());
+
+        // 따옴표 쿼리 + 필드 조건
+        let (_, body) = request(addr, "GET", "/logs?q=contains%3D%22disk%22+field.dev%3Dsda");
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["count"], 1);
+
+        let (head, body) = request(addr, "GET", "/logs?q=limit%3Dmany");
+        assert!(head.starts_with("HTTP/1.1 400"), "{head}");
+        assert!(body.contains("bad query"), "{body}");
+
+        let (head, body) = request(addr, "POST", "/backup?name=err.tsv&q=level%3E%3DError");
+        assert!(head.starts_with("HTTP/1.1 200"), "{head} {body}");
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["rows"], 1);
+        assert_eq!(std::fs::read_to_string(dir.join("err.tsv")).unwrap(), "3\t4\t30\tdisk failed\n");
+
+        let (head, _) = request(addr, "POST", "/backup?name=err.csv&q=level%3E%3DError");
+        assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+        assert_eq!(std::fs::read_to_string(dir.join("err.csv")).unwrap(),
+            "ts_ms,level,code,msg,fields\r\n3,Error,30,disk failed,\r\n");
+        let (head, _) = request(addr, "POST", "/backup?format=xml");
+        assert!(head.starts_with("HTTP/1.1 400"), "{head}");
+
+        let (head, _) = request(addr, "POST", "/backup?name=..%2Fescape.tsv");
+        assert!(head.starts_with("HTTP/1.1 400"), "{head}");
+        let (head, _) = request(addr, "GET", "/backup");
+        assert!(head.starts_with("HTTP/1.1 405"), "{head}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
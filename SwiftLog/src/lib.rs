@@ -1,62 +1,76 @@
 // swiftlog/src/lib.rs
-use std::io::{self, Write};
+#![allow(non_snake_case)] // 크레이트 이름이 "SwiftLog"
+use std::io::{self, Read, Write};
 use std::net::{UdpSocket, TcpStream, SocketAddr};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use swiftlog_proto::{BatchEncoder, Hello, HelloAck, MAX_VERSION, MIN_VERSION};
 
-pub const MAGIC: u32 = 0x31474C53; // 'SLG1' LE
-pub const VERSION: u16 = 1;
+pub use swiftlog_proto::{FieldValueRef, Identity, LogLevel, MAGIC};
 
-#[repr(u8)]
-#[derive(Copy, Clone)]
-pub enum LogLevel { Trace=0, Debug=1, Info=2, Warn=3, Error=4 }
+// TCP 핸드셰이크 응답 대기 시간
+const HELLO_TIMEOUT: Duration = Duration::from_secs(2);
+// TCP 배치 하나의 최대 바이트 (이만큼 쌓이면 flush)
+const TCP_MAX_BATCH: usize = 64 * 1024;
 
 enum Transport {
     Udp { sock: UdpSocket, dst: SocketAddr, max_datagram: usize },
-    Tcp { stream: TcpStream, scratch: Vec<u8> },
+    // scratch[sent..] = 아직 다 못 보낸 프레임 (프레임 경계가 깨지지 않도록 다음 flush에서 이어서 보냄)
+    Tcp { stream: TcpStream, scratch: Vec<u8>, sent: usize },
 }
 
 pub struct Logger {
     t: Transport,
-    buf: Vec<u8>,        // Batch 누적 버퍼 (BatchHeader 포함)
-    count_in_batch: u16,
+    enc: BatchEncoder,   // Batch 누적 버퍼 (BatchHeader 포함)
+    seq: u64,            // v2 배치 순번 (flush마다 +1)
     dropped: u64,
 }
 
 impl Logger {
+    /// UDP는 협상 단계가 없으므로 이 빌드의 최신 버전으로 보냅니다.
+    /// 구버전 서버로 보낼 때는 `set_version`으로 낮추세요.
     pub fn new_udp(dst: SocketAddr) -> io::Result<Self> {
         let sock = UdpSocket::bind("0.0.0.0:0")?;
         sock.set_nonblocking(true)?;
-        let mut s = Self {
+        Ok(Self {
             t: Transport::Udp { sock, dst, max_datagram: 1300 },
-            buf: Vec::with_capacity(1400),
-            count_in_batch: 0,
+            enc: BatchEncoder::with_capacity(MAX_VERSION, 1400),
+            seq: 0,
             dropped: 0,
-        };
-        s.begin_batch();
-        Ok(s)
+        })
     }
 
+    /// 연결 직후 Hello로 버전을 협상한 뒤 논블로킹으로 전환합니다.
+    /// 송신자 정보는 `default_identity()`(실행 파일 이름, 호스트 이름, pid)를 보냅니다.
     pub fn new_tcp(dst: SocketAddr) -> io::Result<Self> {
-        let stream = TcpStream::connect(dst)?;
-        stream.set_nonblocking(true)?;
+        Self::new_tcp_with_identity(dst, default_identity())
+    }
+
+    /// 서비스 이름 등을 직접 지정해 연결합니다.
+    pub fn new_tcp_with_identity(dst: SocketAddr, identity: Identity) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(dst)?;
         stream.set_nodelay(true)?; // Nagle off: 지연 감소
-        let mut s = Self {
-            t: Transport::Tcp { stream, scratch: Vec::with_capacity(1400) },
-            buf: Vec::with_capacity(1400),
-            count_in_batch: 0,
+        let version = handshake(&mut stream, identity)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            t: Transport::Tcp { stream, scratch: Vec::with_capacity(TCP_MAX_BATCH + 64), sent: 0 },
+            enc: BatchEncoder::with_capacity(version, 1400),
+            seq: 0,
             dropped: 0,
-        };
-        s.begin_batch();
-        Ok(s)
+        })
     }
 
-    fn begin_batch(&mut self) {
-        self.buf.clear();
-        self.count_in_batch = 0;
-        self.buf.extend_from_slice(&MAGIC.to_le_bytes());
-        self.buf.extend_from_slice(&VERSION.to_le_bytes());
-        self.buf.extend_from_slice(&0u16.to_le_bytes()); // count placeholder
+    /// 현재 배치에 쓰는 프로토콜 버전
+    pub fn version(&self) -> u16 { self.enc.version() }
+
+    /// 버전 변경. 아직 보내지 않은 레코드가 있으면 먼저 flush합니다.
+    pub fn set_version(&mut self, version: u16) -> io::Result<()> {
+        if !swiftlog_proto::is_supported_version(version) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "unsupported protocol version"));
+        }
+        let res = self.flush();
+        self.enc.set_version(version);
+        res
     }
 
     #[inline] fn now_ms() -> u64 {
@@ -64,72 +78,123 @@ impl Logger {
     }
 
     pub fn log(&mut self, level: LogLevel, code: u16, msg: &str) {
-        let ts = Self::now_ms();
-        let m = msg.as_bytes();
-        let len = m.len().min(u16::MAX as usize) as u16;
+        self.log_with(level, code, msg, &[]);
+    }
 
-        let need = 8 + 1 + 2 + 2 + (len as usize);
+    /// 구조화 필드를 붙여 기록합니다. v1으로 보내는 중이면 필드는 버려집니다.
+    /// `logger.log_with(LogLevel::Info, 1, "login", &[("user_id", 42.into()), ("region", "eu".into())])`
+    pub fn log_with(&mut self, level: LogLevel, code: u16, msg: &str, fields: &[(&str, FieldValueRef<'_>)]) {
+        let ts = Self::now_ms();
+        let need = BatchEncoder::record_len(msg.len()) + BatchEncoder::fields_len(fields);
         let headroom = 2;
 
-        let max_datagram = match self.t {
+        let max_batch = match self.t {
             Transport::Udp { max_datagram, .. } => max_datagram,
-            Transport::Tcp { .. } => usize::MAX, // TCP는 배치 크기 자유(단, 너무 크지 않게 운용)
+            Transport::Tcp { .. } => TCP_MAX_BATCH,
         };
 
-        if self.buf.len() + need + headroom > max_datagram {
+        if self.enc.len() + need + headroom > max_batch || self.enc.is_full() {
             let _ = self.flush(); // 실패 시 드롭
         }
 
-        self.buf.extend_from_slice(&ts.to_le_bytes());
-        self.buf.push(level as u8);
-        self.buf.extend_from_slice(&code.to_le_bytes());
-        self.buf.extend_from_slice(&len.to_le_bytes());
-        self.buf.extend_from_slice(&m[..len as usize]);
-
-        self.count_in_batch = self.count_in_batch.saturating_add(1);
+        self.enc.push_with_fields(ts, level, code, msg.as_bytes(), fields);
 
-        if let Transport::Udp { max_datagram, .. } = self.t {
-            if self.buf.len() >= max_datagram - 64 {
-                let _ = self.flush();
-            }
+        if self.enc.len() >= max_batch - 64 {
+            let _ = self.flush();
         }
     }
 
-    // flush() 전체를 교체하거나, UDP 분기만 이렇게 수정
-pub fn flush(&mut self) -> io::Result<()> {
-    if self.count_in_batch == 0 { return Ok(()); }
-    // count 패치
-    let c = self.count_in_batch.to_le_bytes();
-    self.buf[6] = c[0]; self.buf[7] = c[1];
-
-    let res = match &mut self.t {
-        Transport::Udp { sock, dst, .. } => {
-            // dst: &mut SocketAddr 이므로 값 복사로 넘김
-            sock.send_to(&self.buf, *dst).map(|_| ())
-            //              ▲ 여기!
+    /// 쌓인 배치를 보냅니다. TCP에서 이전 프레임이 아직 전송 중이면(소켓 버퍼 가득)
+    /// 이번 배치는 드롭하고 `WouldBlock`을 돌려줍니다.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Transport::Tcp { stream, scratch, sent } = &mut self.t {
+            if let Err(e) = write_pending(stream, scratch, sent) {
+                self.dropped += self.enc.count() as u64;
+                self.enc.clear();
+                return Err(e);
+            }
         }
-        Transport::Tcp { stream, scratch } => {
-            scratch.clear();
-            let len = self.buf.len() as u32;
-            scratch.extend_from_slice(&len.to_le_bytes());
-            scratch.extend_from_slice(&self.buf);
-
-            match stream.write(&scratch) {
-                Ok(w) if w == scratch.len() => Ok(()),
-                Ok(_partial) => Err(io::Error::new(io::ErrorKind::WouldBlock, "partial write")),
-                Err(e) if e.kind() == io::ErrorKind::WouldBlock => Err(e),
-                Err(e) => Err(e),
+        if self.enc.is_empty() { return Ok(()); }
+        let count = self.enc.count();
+        self.enc.set_seq(self.seq);
+        self.seq = self.seq.wrapping_add(1);
+        let batch = self.enc.finish();
+
+        let res = match &mut self.t {
+            Transport::Udp { sock, dst, .. } => {
+                sock.send_to(batch, *dst).map(|_| ())
+            }
+            Transport::Tcp { stream, scratch, sent } => {
+                if *sent < scratch.len() {
+                    Err(io::Error::new(io::ErrorKind::WouldBlock, "previous batch still in flight"))
+                } else {
+                    scratch.clear();
+                    *sent = 0;
+                    swiftlog_proto::write_frame(scratch, batch);
+                    write_pending(stream, scratch, sent)
+                }
             }
+        };
+
+        if res.is_err() {
+            self.dropped += count as u64;
         }
-    };
+        self.enc.clear();
+        res
+    }
 
-    if res.is_err() {
-        self.dropped += self.count_in_batch as u64;
+    pub fn dropped_count(&self) -> u64 { self.dropped }
+}
+
+// buf[*sent..]를 논블로킹으로 가능한 만큼 씀. 소켓 버퍼가 차면 나머지는 남겨둠
+fn write_pending(stream: &mut TcpStream, buf: &[u8], sent: &mut usize) -> io::Result<()> {
+    while *sent < buf.len() {
+        match stream.write(&buf[*sent..]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => *sent += n,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
     }
-    self.begin_batch();
-    res
+    Ok(())
 }
 
+/// 현재 프로세스 기준 송신자 정보 (service = 실행 파일 이름)
+pub fn default_identity() -> Identity {
+    let service = std::env::current_exe().ok()
+        .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_string())
+        .unwrap_or_default();
+    Identity { service, host, pid: std::process::id() }
+}
 
-    pub fn dropped_count(&self) -> u64 { self.dropped }
+// Hello → HelloAck (블로킹, 타임아웃 적용)
+fn handshake(stream: &mut TcpStream, identity: Identity) -> io::Result<u16> {
+    let hello = Hello { min_version: MIN_VERSION, max_version: MAX_VERSION, identity: Some(identity) };
+    let mut frame = Vec::with_capacity(16);
+    swiftlog_proto::write_frame(&mut frame, &hello.encode());
+    stream.write_all(&frame)?;
+
+    stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > 64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected hello ack size"));
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload)?;
+    stream.set_read_timeout(None)?;
+
+    let ack = HelloAck::decode(&payload)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if ack.version == 0 {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "no common protocol version"));
+    }
+    Ok(ack.version)
 }
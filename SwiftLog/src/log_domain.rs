@@ -1,7 +1,39 @@
 // src/log_domain.rs
-#[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum LogLevel { Trace=0, Debug=1, Info=2, Warn=3, Error=4 }
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+pub use swiftlog_proto::{FieldValue, Identity, LogLevel};
+use swiftlog_proto::{MAX_FIELDS, MAX_FIELD_KEY_LEN, MAX_FIELD_STR_LEN};
+
+/// 배치를 보낸 쪽. TCP는 연결 단위(Hello의 Identity 포함), UDP는 데이터그램 단위(peer만).
+/// 같은 연결의 로그들은 `Arc<Source>` 하나를 공유합니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub peer: SocketAddr,
+    pub identity: Option<Identity>,
+}
+
+impl Source {
+    pub fn peer(peer: SocketAddr) -> Self { Self { peer, identity: None } }
+
+    pub fn service(&self) -> Option<&str> {
+        self.identity.as_ref().map(|i| i.service.as_str()).filter(|s| !s.is_empty())
+    }
+
+    /// 표시용: Identity가 있으면 service@host, 없으면 peer
+    pub fn label(&self) -> String {
+        match &self.identity {
+            Some(i) => format!("{}@{}", i.service, i.host),
+            None => self.peer.to_string(),
+        }
+    }
+
+    /// `source=` 필터: peer("ip:port"), peer ip, 호스트 이름 중 하나와 일치
+    pub fn matches(&self, text: &str) -> bool {
+        if self.peer.to_string() == text || self.peer.ip().to_string() == text { return true; }
+        self.identity.as_ref().is_some_and(|i| !i.host.is_empty() && i.host == text)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Log {
@@ -12,14 +44,53 @@ pub struct Log {
     pub level: LogLevel,
     pub code: u16,
     pub msg: String,
+    /// 구조화 필드 (user_id=42 region="eu" ...), 송신 순서 유지
+    pub fields: Vec<(String, FieldValue)>,
+    /// 송신자 (네트워크 밖에서 만든 로그는 None)
+    pub source: Option<Arc<Source>>,
 }
 
 impl Log {
     pub fn new_unassigned(ts_ms: u64, level: LogLevel, code: u16, msg: impl Into<String>) -> Self {
-        Self { id: 0, ts_ms, level, code, msg: msg.into() }
+        Self { id: 0, ts_ms, level, code, msg: msg.into(), fields: Vec::new(), source: None }
+    }
+    pub fn with_fields(mut self, fields: Vec<(String, FieldValue)>) -> Self {
+        self.fields = fields;
+        self
+    }
+    pub fn with_source(mut self, source: Option<Arc<Source>>) -> Self {
+        self.source = source;
+        self
+    }
+    pub fn field(&self, key: &str) -> Option<&FieldValue> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
     }
     pub fn validate(&self) -> Result<(), &'static str> {
         if self.msg.len() > u16::MAX as usize { return Err("message too long"); }
+        if self.fields.len() > MAX_FIELDS { return Err("too many fields"); }
+        for (k, v) in &self.fields {
+            if k.is_empty() || k.len() > MAX_FIELD_KEY_LEN { return Err("invalid field key"); }
+            if let FieldValue::Str(s) = v {
+                if s.len() > MAX_FIELD_STR_LEN { return Err("field value too long"); }
+            }
+        }
         Ok(())
     }
 }
+
+/// SelectLog 조건식 평가 (swiftlog-query)
+impl swiftlog_query::Record for Log {
+    fn ts_ms(&self) -> u64 { self.ts_ms }
+    fn level(&self) -> LogLevel { self.level }
+    fn code(&self) -> u16 { self.code }
+    fn msg(&self) -> &str { &self.msg }
+    fn field_eq(&self, key: &str, want: &str) -> bool {
+        self.field(key).is_some_and(|v| v.eq_text(want))
+    }
+    fn source_matches(&self, want: &str) -> bool {
+        self.source.as_ref().is_some_and(|s| s.matches(want))
+    }
+    fn service(&self) -> Option<&str> {
+        self.source.as_ref().and_then(|s| s.service())
+    }
+}
@@ -0,0 +1,204 @@
+// src/log_index.rs
+// LogStore 샤드별 보조 인덱스: 레벨 / 코드 / 시간 구간 / 메시지 토큰 → ID 목록 (목록마다 ID 오름차순)
+//
+// 샤드 deque와 같은 잠금 아래에서 append/restore와 링 eviction 때 함께 갱신합니다.
+// 밀려나는 레코드는 그 샤드에서 가장 작은 ID라 자기가 들어 있는 목록마다 맨 앞에 있으므로 pop_front로 지웁니다.
+// select는 쿼리 조건 중 후보가 가장 적은 인덱스를 골라 그 ID만 deque에서 찾아 봅니다
+// (나머지 조건은 SelectQuery::matches로 확인하므로 인덱스는 후보를 줄이기만 함).
+//
+// 토큰 인덱스(켜져 있을 때)는 `word:` / `word:<접두사>*` / `phrase:` 조건에 씁니다.
+// contains= / regex= 는 토큰 경계와 무관하므로 계속 훑습니다.
+// 인덱스는 최상위 AND 조건(SelectQuery의 전용 필드)만 보며, or / not 쪽(SelectQuery::expr)은 후보 안에서 확인합니다.
+
+use std::borrow::{Borrow, Cow};
+use std::collections::{BTreeMap, VecDeque};
+use std::mem::size_of;
+use std::ops::Bound;
+
+use crate::log_domain::{Log, LogLevel};
+use crate::log_store::{SelectQuery, TextTerm};
+use swiftlog_query::tokenize;
+
+// 한 메시지에 나오는 토큰 (중복 없이)
+fn unique_tokens(msg: &str) -> Vec<Cow<'_, str>> {
+    let mut t: Vec<_> = tokenize(msg).collect();
+    t.sort_unstable();
+    t.dedup();
+    t
+}
+
+pub struct ShardIndex {
+    bucket_ms: u64,
+    by_level: [VecDeque<u64>; LogLevel::ALL.len()],
+    by_code: BTreeMap<u16, VecDeque<u64>>,
+    /// ts_ms / bucket_ms → ID
+    by_time: BTreeMap<u64, VecDeque<u64>>,
+    /// 메시지 토큰 → ID (토큰 인덱스를 끄면 None). 접두사 검색 때문에 정렬된 맵
+    by_token: Option<BTreeMap<String, VecDeque<u64>>>,
+}
+
+/// 인덱스 메모리 사용량 (bytes는 목록 용량 + 맵 항목 기준의 대략값)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IndexUsage {
+    pub entries: u64,
+    pub bytes: u64,
+    /// 토큰 인덱스의 서로 다른 토큰 수
+    pub tokens: u64,
+}
+
+impl ShardIndex {
+    pub fn new(bucket_ms: u64, text: bool) -> Self {
+        Self {
+            bucket_ms: bucket_ms.max(1),
+            by_level: Default::default(),
+            by_code: BTreeMap::new(),
+            by_time: BTreeMap::new(),
+            by_token: text.then(BTreeMap::new),
+        }
+    }
+
+    pub fn insert(&mut self, log: &Log) {
+        self.by_level[log.level as usize].push_back(log.id);
+        self.by_code.entry(log.code).or_default().push_back(log.id);
+        self.by_time.entry(log.ts_ms / self.bucket_ms).or_default().push_back(log.id);
+        if let Some(by_token) = &mut self.by_token {
+            for t in unique_tokens(&log.msg) {
+                match by_token.get_mut(t.as_ref()) {
+                    Some(ids) => ids.push_back(log.id),
+                    None => { by_token.insert(t.into_owned(), VecDeque::from([log.id])); }
+                }
+            }
+        }
+    }
+
+    /// 샤드 맨 앞(가장 오래된) 레코드가 밀려날 때
+    pub fn evict(&mut self, log: &Log) {
+        let front = self.by_level[log.level as usize].pop_front();
+        debug_assert_eq!(front, Some(log.id));
+        pop_front(&mut self.by_code, &log.code);
+        pop_front(&mut self.by_time, &(log.ts_ms / self.bucket_ms));
+        if let Some(by_token) = &mut self.by_token {
+            for t in unique_tokens(&log.msg) {
+                pop_front(by_token, t.as_ref());
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.bucket_ms, self.by_token.is_some());
+    }
+
+    /// 쿼리 조건으로 고를 수 있는 후보 ID 목록들 (합집합이 후보, 접두사 검색이면 서로 겹칠 수 있음).
+    /// 조건끼리는 AND이므로 그중 후보가 가장 적은 것 하나만 씀.
+    /// 쓸 인덱스가 없거나 가장 적은 후보도 `max`건 이상이면 None (그냥 훑는 편이 빠름)
+    pub fn candidates(&self, q: &SelectQuery, max: usize) -> Option<Vec<&VecDeque<u64>>> {
+        let mut options: Vec<Vec<&VecDeque<u64>>> = Vec::new();
+        if q.level_min.is_some() || q.level_max.is_some() {
+            let (lo, hi) = (q.level_min.unwrap_or(LogLevel::Trace), q.level_max.unwrap_or(LogLevel::Error));
+            options.push(LogLevel::ALL.iter().filter(|l| lo <= **l && **l <= hi).map(|l| &self.by_level[*l as usize]).collect());
+        }
+        if let Some(codes) = &q.code_in {
+            let mut codes = codes.clone();
+            codes.sort_unstable();
+            codes.dedup();
+            options.push(codes.iter().filter_map(|c| self.by_code.get(c)).collect());
+        } else if let Some((lo, hi)) = q.code_range.filter(|(lo, hi)| lo <= hi) {
+            options.push(self.by_code.range(lo..=hi).map(|(_, ids)| ids).collect());
+        }
+        if q.since_ms.is_some() || q.until_ms.is_some() {
+            let lo = q.since_ms.unwrap_or(0) / self.bucket_ms;
+            let hi = q.until_ms.unwrap_or(u64::MAX) / self.bucket_ms;
+            if lo <= hi {
+                options.push(self.by_time.range(lo..=hi).map(|(_, ids)| ids).collect());
+            }
+        }
+        if let Some(by_token) = &self.by_token {
+            for term in &q.text {
+                options.push(match term {
+                    TextTerm::Word(w) => by_token.get(w).into_iter().collect(),
+                    TextTerm::Prefix(p) => by_token.range::<str, _>((Bound::Included(p.as_str()), Bound::Unbounded))
+                        .take_while(|(t, _)| t.starts_with(p.as_str()))
+                        .map(|(_, ids)| ids)
+                        .collect(),
+                    // 구절: 가장 드문 단어의 목록 (없는 단어가 있으면 후보 없음)
+                    TextTerm::Phrase(words) => words.iter().map(|w| by_token.get(w))
+                        .collect::<Option<Vec<_>>>()
+                        .and_then(|lists| lists.into_iter().min_by_key(|l| l.len()))
+                        .into_iter().collect(),
+                });
+            }
+        }
+        let count = |lists: &Vec<&VecDeque<u64>>| lists.iter().map(|l| l.len()).sum::<usize>();
+        options.into_iter().min_by_key(count).filter(|lists| count(lists) < max)
+    }
+
+    pub fn usage(&self) -> IndexUsage {
+        let list = |l: &VecDeque<u64>| (l.len() as u64, (l.capacity() * size_of::<u64>()) as u64);
+        let node = |key: usize| (key + size_of::<VecDeque<u64>>()) as u64;
+        let mut u = IndexUsage::default();
+        let lists = self.by_level.iter().map(|l| (l, 0))
+            .chain(self.by_code.values().map(|l| (l, node(size_of::<u16>()))))
+            .chain(self.by_time.values().map(|l| (l, node(size_of::<u64>()))))
+            .chain(self.by_token.iter().flatten().map(|(t, l)| (l, node(size_of::<String>() + t.capacity()))));
+        u.tokens = self.by_token.as_ref().map_or(0, |m| m.len() as u64);
+        for (l, overhead) in lists {
+            let (entries, bytes) = list(l);
+            u.entries += entries;
+            u.bytes += bytes + overhead;
+        }
+        u
+    }
+}
+
+// 목록 맨 앞을 지우고, 비면 키째 지움 (코드/시간 구간/토큰이 계속 늘어나지 않도록)
+fn pop_front<K: Ord + Borrow<Q>, Q: Ord + ?Sized>(map: &mut BTreeMap<K, VecDeque<u64>>, key: &Q) {
+    if let Some(ids) = map.get_mut(key) {
+        ids.pop_front();
+        if ids.is_empty() {
+            map.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_smallest_candidate_set_and_drops_evicted_ids() {
+        let mut ix = ShardIndex::new(1000, false);
+        let logs: Vec<Log> = (1..=10u64).map(|i| {
+            let level = if i % 5 == 0 { LogLevel::Error } else { LogLevel::Info };
+            let mut log = Log::new_unassigned(i * 500, level, (i % 3) as u16, "m");
+            log.id = i;
+            log
+        }).collect();
+        logs.iter().for_each(|l| ix.insert(l));
+        let ids = |lists: Vec<&VecDeque<u64>>| {
+            let mut v: Vec<u64> = lists.into_iter().flatten().copied().collect();
+            v.sort_unstable();
+            v
+        };
+
+        let q = SelectQuery { level_min: Some(LogLevel::Warn), code_range: Some((0, 1)), ..Default::default() };
+        assert_eq!(ids(ix.candidates(&q, 100).unwrap()), [5, 10]);
+        let q = SelectQuery { code_in: Some(vec![2, 2]), since_ms: Some(4000), ..Default::default() };
+        assert_eq!(ids(ix.candidates(&q, 100).unwrap()), [2, 5, 8]);
+        let q = SelectQuery { since_ms: Some(4000), until_ms: Some(4999), ..Default::default() };
+        assert_eq!(ids(ix.candidates(&q, 100).unwrap()), [8, 9]);
+        // 인덱스로 못 좁히는 쿼리 / 후보가 너무 많은 경우
+        assert!(ix.candidates(&SelectQuery { contains: Some("m".into()), ..Default::default() }, 100).is_none());
+        assert!(ix.candidates(&SelectQuery { level_min: Some(LogLevel::Trace), ..Default::default() }, 10).is_none());
+
+        let before = ix.usage();
+        assert_eq!(before.entries, 30);
+        for l in &logs[..6] {
+            ix.evict(l);
+        }
+        let q = SelectQuery { level_min: Some(LogLevel::Error), ..Default::default() };
+        assert_eq!(ids(ix.candidates(&q, 100).unwrap()), [10]);
+        assert_eq!(ix.by_code.values().map(|l| l.len()).sum::<usize>(), 4);
+        assert_eq!(ix.by_time.keys().copied().collect::<Vec<_>>(), [3, 4, 5]);
+        assert_eq!(ix.usage().entries, 12);
+    }
+}
@@ -1,7 +1,12 @@
 // src/log_store.rs
-use std::collections::VecDeque;
-use std::sync::{Arc, RwLock, atomic::{AtomicU64, Ordering}};
+use std::collections::{BinaryHeap, VecDeque};
+use std::sync::{Arc, RwLock, RwLockReadGuard, atomic::{AtomicU64, Ordering}};
 use crate::log_domain::{Log, LogLevel};
+use crate::log_index::{IndexUsage, ShardIndex};
+use crate::segment::SegmentStore;
+use crate::subscribe::{Subscribers, Subscription};
+use swiftlog_query::{tokenize, Cmp, Expr, Pred, Query};
+pub use swiftlog_query::TextTerm;
 
 #[derive(Clone, Debug, Default)]
 pub struct SelectQuery {
@@ -13,16 +18,127 @@ pub struct SelectQuery {
     pub until_ms: Option<u64>,
     pub contains: Option<String>,            // 부분 문자열
     pub regex: Option<regex::Regex>,         // 고급 패턴 (선택)
+    pub text: Vec<TextTerm>,                 // word: / phrase: (모두 AND, 토큰 인덱스 사용)
+    pub fields: Vec<(String, String)>,       // field.<key>=<value> (모두 AND, 값은 필드 타입에 맞춰 비교)
+    pub source: Option<String>,              // peer "ip:port" / ip / 호스트 이름
+    pub service: Option<String>,             // Hello로 알린 서비스 이름
+    pub expr: Option<Expr>,                  // 위 필드로 못 옮긴 조건 (or / not / != / 비교 등, 인덱스로 못 좁힘)
     pub limit: Option<usize>,
     pub offset: usize,
     pub latest: bool,                        // true면 ID/시간 내림차순 반환
+    pub cursor: Option<u64>,                 // 이 ID를 지난 것만 (오름차순이면 큰 ID, latest면 작은 ID). 페이지 넘김용
+}
+
+impl SelectQuery {
+    /// 필터 조건만 검사 (limit/offset/latest는 select에서 적용, 구독에서는 무시)
+    pub fn matches(&self, e: &Log) -> bool {
+        if let Some(c) = self.cursor { if (self.latest && e.id >= c) || (!self.latest && e.id <= c) { return false; } }
+        if let Some(min) = self.level_min { if e.level < min { return false; } }
+        if let Some(max) = self.level_max { if e.level > max { return false; } }
+        if let Some((lo,hi)) = self.code_range { if e.code < lo || e.code > hi { return false; } }
+        if let Some(ref codes) = self.code_in { if !codes.contains(&e.code) { return false; } }
+        if let Some(since) = self.since_ms { if e.ts_ms < since { return false; } }
+        if let Some(until) = self.until_ms { if e.ts_ms > until { return false; } }
+        if let Some(ref sub) = self.contains { if !e.msg.contains(sub) { return false; } }
+        if let Some(ref re) = self.regex { if !re.is_match(&e.msg) { return false; } }
+        if !self.text.is_empty() {
+            let tokens: Vec<_> = tokenize(&e.msg).collect();
+            if !self.text.iter().all(|t| t.matches(&tokens)) { return false; }
+        }
+        if let Some(ref want) = self.source {
+            if !e.source.as_ref().is_some_and(|s| s.matches(want)) { return false; }
+        }
+        if let Some(ref want) = self.service {
+            if e.source.as_ref().and_then(|s| s.service()) != Some(want.as_str()) { return false; }
+        }
+        for (k, want) in &self.fields {
+            if !e.field(k).is_some_and(|v| v.eq_text(want)) { return false; }
+        }
+        self.expr.as_ref().is_none_or(|x| x.matches(e))
+    }
+
+    // 최상위 AND 조건 하나를 전용 필드로 옮김 (인덱스 / 세그먼트 요약으로 좁힐 수 있게).
+    // 못 옮기면(이미 같은 필드가 찼거나 or/not 등) 그대로 돌려줌
+    fn lift(&mut self, e: Expr) -> Option<Expr> {
+        let Expr::Pred(p) = e else { return Some(e) };
+        match p {
+            Pred::Level(Cmp::Ge, l) if self.level_min.is_none() => self.level_min = Some(l),
+            Pred::Level(Cmp::Le, l) if self.level_max.is_none() => self.level_max = Some(l),
+            Pred::Level(Cmp::Eq, l) if self.level_min.is_none() && self.level_max.is_none() => {
+                (self.level_min, self.level_max) = (Some(l), Some(l));
+            }
+            Pred::Code(Cmp::Eq, c) if self.code_range.is_none() => self.code_range = Some((c, c)),
+            Pred::CodeRange(lo, hi) if self.code_range.is_none() => self.code_range = Some((lo, hi)),
+            Pred::CodeIn(codes) if self.code_in.is_none() => self.code_in = Some(codes),
+            Pred::Ts(Cmp::Ge, t) if self.since_ms.is_none() => self.since_ms = Some(t),
+            Pred::Ts(Cmp::Le, t) if self.until_ms.is_none() => self.until_ms = Some(t),
+            Pred::Contains(s) if self.contains.is_none() => self.contains = Some(s),
+            Pred::Regex(re) if self.regex.is_none() => self.regex = Some(re),
+            Pred::Source(s) if self.source.is_none() => self.source = Some(s),
+            Pred::Service(s) if self.service.is_none() => self.service = Some(s),
+            Pred::Text(t) => self.text.push(t),
+            Pred::Field(k, v) => self.fields.push((k, v)),
+            p => return Some(Expr::Pred(p)),
+        }
+        None
+    }
+}
+
+/// 파싱된 쿼리 → SelectQuery (최상위 AND 조건은 전용 필드로, 나머지는 expr로)
+impl From<Query> for SelectQuery {
+    fn from(q: Query) -> Self {
+        let mut s = SelectQuery { limit: q.limit, offset: q.offset, latest: q.latest, ..Default::default() };
+        let mut rest: Vec<Expr> = q.filter.map(Expr::into_conjuncts).unwrap_or_default()
+            .into_iter()
+            .filter_map(|e| s.lift(e))
+            .collect();
+        s.expr = match rest.len() {
+            0 => None,
+            1 => rest.pop(),
+            _ => Some(Expr::And(rest)),
+        };
+        s
+    }
+}
+
+// 샤드 하나: ID 순 링 버퍼 + (켜져 있으면) 보조 인덱스. 같은 잠금 아래에서 함께 바뀜
+#[derive(Default)]
+struct Shard {
+    logs: VecDeque<Arc<Log>>,
+    index: Option<ShardIndex>,
+}
+
+impl Shard {
+    // 가득 차 있으면 맨 앞을 밀어내고 넣음 (밀려났으면 true)
+    fn push(&mut self, log: Arc<Log>, cap: usize) -> bool {
+        let evicted = if self.logs.len() >= cap { self.logs.pop_front() } else { None };
+        if let Some(ix) = &mut self.index {
+            if let Some(old) = &evicted { ix.evict(old); }
+            ix.insert(&log);
+        }
+        self.logs.push_back(log);
+        evicted.is_some()
+    }
+
+    // 샤드 안 ID는 보통 샤드 수 간격으로 이어지므로 위치를 바로 계산하고, 어긋나면(복구로 빈 ID 등) 이진 탐색
+    fn get(&self, id: u64, stride: usize) -> Option<&Arc<Log>> {
+        let front = self.logs.front()?.id;
+        let guess = (id.checked_sub(front)? / stride as u64) as usize;
+        match self.logs.get(guess) {
+            Some(e) if e.id == id => Some(e),
+            _ => self.logs.binary_search_by_key(&id, |e| e.id).ok().map(|i| &self.logs[i]),
+        }
+    }
 }
 
 pub struct LogStore {
     // 샤드 수는 코어 수에 맞춰 조절 가능
-    shards: Vec<RwLock<VecDeque<Arc<Log>>>>,
+    shards: Vec<RwLock<Shard>>,
     cap_per_shard: usize,
     seq: AtomicU64,
+    evicted: AtomicU64, // 용량 초과로 밀려난 로그 수
+    subs: Subscribers,  // 실시간 구독자 (append마다 전달)
+    segments: Option<Arc<SegmentStore>>, // 디스크 세그먼트 (select가 메모리와 합쳐 조회)
 }
 
 impl LogStore {
@@ -30,9 +146,46 @@ impl LogStore {
         let cap = (total_cap.max(shards)) / shards;
         let mut v = Vec::with_capacity(shards);
         for _ in 0..shards {
-            v.push(RwLock::new(VecDeque::with_capacity(cap)));
+            v.push(RwLock::new(Shard { logs: VecDeque::with_capacity(cap), index: None }));
         }
-        Self { shards: v, cap_per_shard: cap, seq: AtomicU64::new(1) }
+        Self {
+            shards: v,
+            cap_per_shard: cap,
+            seq: AtomicU64::new(1),
+            evicted: AtomicU64::new(0),
+            subs: Subscribers::default(),
+            segments: None,
+        }
+    }
+
+    /// 디스크 세그먼트를 붙임. ID는 디스크 마지막 ID 다음부터 이어 매깁니다.
+    pub fn with_segments(mut self, segments: Arc<SegmentStore>) -> Self {
+        self.seq = AtomicU64::new(segments.next_id().max(1));
+        self.segments = Some(segments);
+        self
+    }
+
+    pub fn segments(&self) -> Option<&Arc<SegmentStore>> { self.segments.as_ref() }
+
+    /// 레벨/코드/시간 구간(`bucket_ms` 단위) 보조 인덱스를 켬. `text`면 메시지 토큰 인덱스도. select가 알아서 씀
+    pub fn with_index(mut self, bucket_ms: u64, text: bool) -> Self {
+        for s in &mut self.shards {
+            let s = s.get_mut().unwrap();
+            let mut ix = ShardIndex::new(bucket_ms, text);
+            s.logs.iter().for_each(|l| ix.insert(l));
+            s.index = Some(ix);
+        }
+        self
+    }
+
+    /// 보조 인덱스 메모리 (꺼져 있으면 0)
+    pub fn index_usage(&self) -> IndexUsage {
+        self.shards.iter().filter_map(|s| s.read().unwrap().index.as_ref().map(|ix| ix.usage()))
+            .fold(IndexUsage::default(), |a, b| IndexUsage {
+                entries: a.entries + b.entries,
+                bytes: a.bytes + b.bytes,
+                tokens: a.tokens + b.tokens,
+            })
     }
 
     #[inline]
@@ -44,50 +197,351 @@ impl LogStore {
         let shard_idx = self.pick_shard(id);
         let arc = Arc::new(log);
         let mut q = self.shards[shard_idx].write().unwrap();
-        if q.len() >= self.cap_per_shard { q.pop_front(); } // ring
-        q.push_back(arc.clone());
+        if q.push(arc.clone(), self.cap_per_shard) { // ring
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+        }
+        drop(q);
+        self.subs.publish(&arc);
         arc
     }
 
+    /// 복구용: 이미 ID가 매겨진 로그를 그대로 넣음 (ID 순으로, 구독자에게는 알리지 않음)
+    pub fn restore(&self, log: Arc<Log>) {
+        let mut q = self.shards[self.pick_shard(log.id)].write().unwrap();
+        if q.push(log, self.cap_per_shard) {
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 앞으로 append되는 로그 중 `query`에 맞는 것을 받는 구독. 버퍼는 최대 `buffer`건
+    pub fn subscribe(&self, query: SelectQuery, buffer: usize) -> Subscription {
+        self.subs.subscribe(query, buffer)
+    }
+
+    pub fn subscriber_count(&self) -> usize { self.subs.len() }
+
+    #[allow(dead_code)]
     pub fn clear(&self) {
         for s in &self.shards {
-            s.write().unwrap().clear();
+            let mut s = s.write().unwrap();
+            s.logs.clear();
+            if let Some(ix) = &mut s.index { ix.clear(); }
         }
     }
 
+    /// 전체 용량 (샤드 용량 × 샤드 수)
+    pub fn capacity(&self) -> usize { self.cap_per_shard * self.shards.len() }
+
+    /// 링 버퍼가 가득 차서 밀려난 로그 수 (누적)
+    pub fn evictions(&self) -> u64 { self.evicted.load(Ordering::Relaxed) }
+
+    /// 샤드별 적재 건수 (샤드 용량은 capacity() / 샤드 수)
+    pub fn shard_lens(&self) -> Vec<usize> {
+        self.shards.iter().map(|s| s.read().unwrap().logs.len()).collect()
+    }
+
     pub fn len(&self) -> usize {
-        self.shards.iter().map(|s| s.read().unwrap().len()).sum()
+        self.shards.iter().map(|s| s.read().unwrap().logs.len()).sum()
     }
 
+    /// 샤드 deque는 각자 ID 순(append는 파서 스레드 하나)이므로 전체 정렬 없이 k-way 병합.
+    /// 필터는 병합 전에 샤드 안에서 걸고, offset+limit 건을 채우면 멈춤 (`latest limit=50`은 50건만 봄).
+    /// 보조 인덱스가 있으면 샤드마다 후보 ID만 훑음
     pub fn select(&self, q: &SelectQuery) -> Vec<Arc<Log>> {
-        let mut out = Vec::new();
-        // 샤드 병합 스캔 (간단/안전: 먼저 모으고 정렬 → 필터)
-        for s in &self.shards {
-            let guard = s.read().unwrap();
-            out.extend(guard.iter().cloned());
-        }
-        // 정렬: 최신 우선이면 id 내림차순, 아니면 오름차순
-        if q.latest {
-            out.sort_by(|a,b| b.id.cmp(&a.id));
-        } else {
-            out.sort_by(|a,b| a.id.cmp(&b.id));
-        }
-        // 필터
-        out.retain(|e| {
-            if let Some(min) = q.level_min { if e.level < min { return false; } }
-            if let Some(max) = q.level_max { if e.level > max { return false; } }
-            if let Some((lo,hi)) = q.code_range { if e.code < lo || e.code > hi { return false; } }
-            if let Some(ref codes) = q.code_in { if !codes.contains(&e.code) { return false; } }
-            if let Some(since) = q.since_ms { if e.ts_ms < since { return false; } }
-            if let Some(until) = q.until_ms { if e.ts_ms > until { return false; } }
-            if let Some(ref sub) = q.contains { if !e.msg.contains(sub) { return false; } }
-            if let Some(ref re) = q.regex { if !re.is_match(&e.msg) { return false; } }
-            true
-        });
+        let need = q.limit.map_or(usize::MAX, |l| l.saturating_add(q.offset));
+        let guards: Vec<_> = self.shards.iter().map(|s| s.read().unwrap()).collect();
+        // 샤드마다 들고 있는 ID 구간 (디스크 중복 제거용)
+        let held: Vec<_> = guards.iter().map(|g| g.logs.front().zip(g.logs.back()).map(|(a, b)| (a.id, b.id))).collect();
+        let mut out = merge_shards(&guards, q, need);
+        drop(guards);
+
+        // 디스크: 메모리에 없는 ID만. 메모리만으로 한 페이지가 차면 그 경계 너머는 볼 필요 없음
+        if let Some(seg) = self.segments.as_ref().filter(|_| need > 0) {
+            let ids = match out.get(need - 1) {
+                Some(edge) if q.latest => edge.id + 1..u64::MAX,
+                Some(edge) => 0..edge.id,
+                None => 0..u64::MAX,
+            };
+            let ids = match q.cursor {
+                Some(c) if q.latest => ids.start..ids.end.min(c),
+                Some(c) => ids.start.max(c.saturating_add(1))..ids.end,
+                None => ids,
+            };
+            let n = self.shards.len();
+            let in_memory = |id: u64| held[(id as usize) % n].is_some_and(|(lo, hi)| lo <= id && id <= hi);
+            let disk = seg.select(q, ids, in_memory, need);
+            if !disk.is_empty() {
+                out.extend(disk);
+                sort_by_id(&mut out, q.latest);
+                out.truncate(need);
+            }
+        }
+
         // 페이징
         let start = q.offset.min(out.len());
         let mut out = out.split_off(start);
         if let Some(limit) = q.limit { out.truncate(limit); }
         out
     }
+
+    /// select 결과를 `page`건씩 ID 순으로 `f`에 넘김 (백업처럼 결과 전체를 한 번에 만들지 않을 때).
+    /// 마지막 행 ID를 커서로 다음 페이지를 고르고, 시작 시점 이후에 들어온 로그는 넣지 않음.
+    /// `f`가 Err면 멈추고 그 오류를 돌려줌. 넘긴 행 수를 돌려줌
+    pub fn select_pages<E>(
+        &self,
+        q: &SelectQuery,
+        page: usize,
+        mut f: impl FnMut(&[Arc<Log>]) -> Result<(), E>,
+    ) -> Result<usize, E> {
+        let next_id = self.seq.load(Ordering::Relaxed);
+        let mut q = q.clone();
+        let mut left = q.limit.unwrap_or(usize::MAX);
+        let mut sent = 0;
+        while left > 0 {
+            q.limit = Some(page.max(1).min(left));
+            let mut rows = self.select(&q);
+            let full = Some(rows.len()) == q.limit;
+            let before = rows.len();
+            rows.retain(|l| l.id < next_id);
+            let Some(tail) = rows.last() else { break };
+            (q.cursor, q.offset) = (Some(tail.id), 0);
+            f(&rows)?;
+            sent += rows.len();
+            left -= rows.len();
+            if !full || rows.len() < before { break; }
+        }
+        Ok(sent)
+    }
+}
+
+// 샤드별로 필터를 통과한 것만 ID 순(latest면 역순)으로 꺼내 병합, need건에서 멈춤
+fn merge_shards(shards: &[RwLockReadGuard<'_, Shard>], q: &SelectQuery, need: usize) -> Vec<Arc<Log>> {
+    type Iter<'a, T> = Box<dyn Iterator<Item = &'a T> + 'a>;
+    // 최대 힙 하나로 두 방향 모두: 오름차순이면 키를 비트 반전
+    let latest = q.latest;
+    let key = move |id: u64| if latest { id } else { !id };
+    let iters: Vec<Iter<'_, Arc<Log>>> = shards.iter().map(|g| -> Iter<'_, Arc<Log>> {
+        // 후보가 샤드의 1/8 이상이면 그냥 훑는 편이 빠름 (후보마다 deque를 따로 찾아가므로)
+        match g.index.as_ref().and_then(|ix| ix.candidates(q, g.logs.len() / 8)) {
+            // 후보 ID 목록들도 각자 ID 순이라 같은 방식으로 병합 (접두사 검색은 목록끼리 겹치므로 연속 중복 제거)
+            Some(lists) => {
+                let mut last = None;
+                let lists = lists.into_iter().map(|l| ordered(l, latest, q.cursor, |id| *id)).collect();
+                Box::new(KMerge::new(lists, |id: &&u64| key(**id))
+                    .filter(move |id| last.replace(**id) != Some(**id))
+                    .filter_map(|id| g.get(*id, shards.len()))
+                    .filter(|e| q.matches(e)))
+            }
+            None => Box::new(ordered(&g.logs, latest, q.cursor, |e| e.id).filter(|e| q.matches(e))),
+        }
+    }).collect();
+    KMerge::new(iters, |e: &&Arc<Log>| key(e.id)).take(need).cloned().collect()
+}
+
+// ID 순 (latest면 역순). cursor가 있으면 그 ID를 지난 곳부터 (이진 탐색)
+fn ordered<T>(v: &VecDeque<T>, latest: bool, cursor: Option<u64>, id: impl Fn(&T) -> u64) -> Box<dyn Iterator<Item = &T> + '_> {
+    match (latest, cursor) {
+        (false, None) => Box::new(v.iter()),
+        (true, None) => Box::new(v.iter().rev()),
+        (false, Some(c)) => Box::new(v.range(v.partition_point(|e| id(e) <= c)..)),
+        (true, Some(c)) => Box::new(v.range(..v.partition_point(|e| id(e) < c)).rev()),
+    }
+}
+
+// 각자 키 내림차순인 반복자들을 키 내림차순 하나로 (필요한 만큼만 꺼냄)
+struct KMerge<I: Iterator, K> {
+    iters: Vec<I>,
+    heads: Vec<Option<I::Item>>,
+    heap: BinaryHeap<(u64, usize)>,
+    key: K,
+}
+
+impl<I: Iterator, K: Fn(&I::Item) -> u64> KMerge<I, K> {
+    fn new(mut iters: Vec<I>, key: K) -> Self {
+        let heads: Vec<_> = iters.iter_mut().map(|it| it.next()).collect();
+        let heap = heads.iter().enumerate().filter_map(|(i, h)| h.as_ref().map(|e| (key(e), i))).collect();
+        Self { iters, heads, heap, key }
+    }
+}
+
+impl<I: Iterator, K: Fn(&I::Item) -> u64> Iterator for KMerge<I, K> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let (_, i) = self.heap.pop()?;
+        let next = self.iters[i].next();
+        if let Some(e) = &next {
+            self.heap.push(((self.key)(e), i));
+        }
+        std::mem::replace(&mut self.heads[i], next)
+    }
+}
+
+// 최신 우선이면 id 내림차순, 아니면 오름차순
+fn sort_by_id(v: &mut [Arc<Log>], latest: bool) {
+    if latest {
+        v.sort_by_key(|e| std::cmp::Reverse(e.id));
+    } else {
+        v.sort_by_key(|e| e.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segment::{self, SegmentConfig};
+
+    #[test]
+    fn select_merges_evicted_disk_records_without_duplicates() {
+        let dir = std::env::temp_dir().join(format!("swiftlog_store_segments_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cfg = SegmentConfig { dir: dir.to_str().unwrap().into(), max_segment_bytes: 1024, ..Default::default() };
+        let seg = Arc::new(SegmentStore::open(&cfg).unwrap());
+        let store = LogStore::with_capacity(10, 2).with_segments(seg.clone());
+        // writer 스레드처럼 저장 직후 디스크에도 (메모리에는 마지막 10건만 남음)
+        for i in 1..=50u64 {
+            let log = store.append(Log::new_unassigned(i * 10, LogLevel::Info, 1, format!("m{i}")));
+            for b in segment::blocks(None, &[log]) { seg.append(&b).unwrap(); }
+        }
+        assert_eq!(store.len(), 10);
+
+        let all = store.select(&SelectQuery::default());
+        assert_eq!(all.iter().map(|l| l.id).collect::<Vec<_>>(), (1..=50).collect::<Vec<_>>());
+        let q = SelectQuery { latest: true, limit: Some(3), offset: 9, ..Default::default() };
+        assert_eq!(store.select(&q).iter().map(|l| l.id).collect::<Vec<_>>(), vec![41, 40, 39]);
+        let q = SelectQuery { since_ms: Some(100), until_ms: Some(120), ..Default::default() };
+        assert_eq!(store.select(&q).iter().map(|l| l.msg.as_str()).collect::<Vec<_>>(), ["m10", "m11", "m12"]);
+        // 디스크와 메모리에 걸친 페이지 넘김 (넘기는 중에 들어온 로그는 빠짐)
+        for (latest, last) in [(false, 50), (true, 51)] {
+            let mut got = Vec::new();
+            let q = SelectQuery { latest, ..Default::default() };
+            store.select_pages(&q, 4, |rows| {
+                if got.is_empty() { store.append(Log::new_unassigned(0, LogLevel::Info, 1, "late")); }
+                got.extend(rows.iter().map(|l| l.id));
+                Ok::<_, ()>(())
+            }).unwrap();
+            let mut want: Vec<u64> = (1..=last).collect();
+            if latest { want.reverse(); }
+            assert_eq!(got, want);
+        }
+
+        // 재시작: ID는 디스크 다음부터
+        drop(store);
+        drop(seg);
+        let store = LogStore::with_capacity(10, 2).with_segments(Arc::new(SegmentStore::open(&cfg).unwrap()));
+        assert_eq!(store.append(Log::new_unassigned(0, LogLevel::Info, 1, "next")).id, 51);
+        assert_eq!(store.select(&SelectQuery::default()).len(), 51);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merged_select_matches_full_sort_and_paging() {
+        // 인덱스 유무와 관계없이 같은 결과 (링이 넘쳐 인덱스에서도 밀려난 상태)
+        for store in [LogStore::with_capacity(300, 4), LogStore::with_capacity(300, 4).with_index(1000, true)] {
+            let levels = [LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+            for i in 0..500u64 {
+                store.append(Log::new_unassigned(i * 100, levels[(i % 5) as usize], (i % 50) as u16, format!("m{i}")));
+            }
+            // 예전 구현: 전부 모아 정렬 후 페이징
+            let naive = |q: &SelectQuery| {
+                let mut all: Vec<Arc<Log>> = store.shards.iter()
+                    .flat_map(|s| s.read().unwrap().logs.iter().filter(|e| q.matches(e)).cloned().collect::<Vec<_>>())
+                    .collect();
+                sort_by_id(&mut all, q.latest);
+                let mut all = all.split_off(q.offset.min(all.len()));
+                if let Some(l) = q.limit { all.truncate(l); }
+                all.iter().map(|l| l.id).collect::<Vec<_>>()
+            };
+            let filters = [
+                SelectQuery::default(),
+                SelectQuery { level_min: Some(LogLevel::Error), ..Default::default() },
+                SelectQuery { code_in: Some(vec![1, 3, 3]), level_max: Some(LogLevel::Info), ..Default::default() },
+                SelectQuery { code_range: Some((5, 6)), ..Default::default() },
+                SelectQuery { since_ms: Some(30_000), until_ms: Some(32_050), ..Default::default() },
+            ];
+            for latest in [false, true] {
+                for (limit, offset) in [(None, 0), (Some(50), 0), (Some(10), 25), (Some(0), 0), (Some(1000), 290), (None, 400)] {
+                    for f in &filters {
+                        let q = SelectQuery { latest, limit, offset, ..f.clone() };
+                        let got: Vec<u64> = store.select(&q).iter().map(|l| l.id).collect();
+                        assert_eq!(got, naive(&q), "{q:?}");
+                    }
+                }
+            }
+            let q = SelectQuery { latest: true, limit: Some(3), ..Default::default() };
+            assert_eq!(store.select(&q).iter().map(|l| l.id).collect::<Vec<_>>(), [500, 499, 498]);
+            // 페이지로 나눠도 같은 결과
+            for latest in [false, true] {
+                for f in &filters {
+                    for (limit, offset) in [(None, 0), (Some(20), 5)] {
+                        let q = SelectQuery { latest, limit, offset, ..f.clone() };
+                        let mut got = Vec::new();
+                        let n = store.select_pages(&q, 7, |rows| {
+                            assert!(rows.len() <= 7);
+                            got.extend(rows.iter().map(|l| l.id));
+                            Ok::<_, ()>(())
+                        }).unwrap();
+                        assert_eq!((n, got), (naive(&q).len(), naive(&q)), "{q:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn index_usage_follows_the_ring() {
+        let store = LogStore::with_capacity(8, 2).with_index(1000, false);
+        assert_eq!(LogStore::with_capacity(8, 2).index_usage(), IndexUsage::default());
+        for i in 0..20u64 {
+            store.append(Log::new_unassigned(i, LogLevel::Info, i as u16, "m"));
+        }
+        // 레코드마다 레벨/코드/시간 하나씩, 밀려난 것은 빠짐
+        assert_eq!(store.index_usage().entries, 8 * 3);
+        assert!(store.index_usage().bytes >= 8 * 3 * 8);
+        store.clear();
+        assert_eq!(store.index_usage().entries, 0);
+    }
+
+    #[test]
+    fn text_search_by_word_prefix_and_phrase() {
+        let msgs = ["Connection reset by peer", "request req-7f3a timeout", "disk full on /dev/sda",
+            "Timeout waiting for disk", "peer reset connection", "diskette ok"];
+        for store in [LogStore::with_capacity(4, 2), LogStore::with_capacity(4, 2).with_index(1000, true)] {
+            for (i, m) in msgs.iter().enumerate() {
+                store.append(Log::new_unassigned(i as u64, LogLevel::Info, 1, *m));
+            }
+            // 링(4건)에 남은 것: 3..=6
+            let find = |text: Vec<TextTerm>| {
+                store.select(&SelectQuery { text, ..Default::default() }).iter().map(|l| l.id).collect::<Vec<_>>()
+            };
+            let w = |s: &str| TextTerm::Word(s.into());
+            assert_eq!(find(vec![w("timeout")]), [4]);
+            assert_eq!(find(vec![TextTerm::Prefix("disk".into())]), [3, 4, 6]);
+            assert_eq!(find(vec![TextTerm::Phrase(vec!["reset".into(), "connection".into()])]), [5]);
+            assert_eq!(find(vec![w("disk"), w("timeout")]), [4]);
+            // 밀려난 메시지의 단어는 찾지 않음
+            assert_eq!(find(vec![w("7f3a")]), Vec::<u64>::new());
+            assert_eq!(find(vec![w("nothing")]), Vec::<u64>::new());
+        }
+        // 후보가 적어 인덱스를 쓰는 경우: 접두사에 걸리는 목록끼리 겹쳐도 한 번씩
+        for store in [LogStore::with_capacity(400, 2), LogStore::with_capacity(400, 2).with_index(1000, true)] {
+            for i in 0..400u64 {
+                let msg = if i % 100 == 7 { "alpha alps".to_string() } else { format!("m{i}") };
+                store.append(Log::new_unassigned(i, LogLevel::Info, 1, msg));
+            }
+            for latest in [false, true] {
+                let q = SelectQuery { text: vec![TextTerm::Prefix("alp".into())], latest, ..Default::default() };
+                let mut want = vec![8, 108, 208, 308];
+                if latest { want.reverse(); }
+                assert_eq!(store.select(&q).iter().map(|l| l.id).collect::<Vec<_>>(), want);
+            }
+        }
+
+        let store = LogStore::with_capacity(2, 1).with_index(1000, true);
+        store.append(Log::new_unassigned(0, LogLevel::Info, 1, "alpha beta alpha"));
+        assert_eq!(store.index_usage().tokens, 2);
+        store.append(Log::new_unassigned(0, LogLevel::Info, 1, "gamma"));
+        store.append(Log::new_unassigned(0, LogLevel::Info, 1, "gamma"));
+        assert_eq!(store.index_usage().tokens, 1);
+    }
 }
@@ -1,8 +1,10 @@
+#![allow(non_snake_case)] // 크레이트 이름이 "SwiftLog"
 use std::io;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use std::sync::mpsc;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
 
 mod proto;
@@ -10,129 +12,219 @@ mod parser;
 mod writer;
 mod udp;
 mod tcp;
+#[allow(dead_code, clippy::empty_loop)]
 mod console;            // 기존
+#[allow(dead_code)]
 mod console_command;    // 기존
 
 mod log_domain;         // 새 모듈
 mod log_store;          // 새 모듈
+mod log_index;
 mod console_select;     // 새 모듈
 mod console_degsign;
 mod backup_quota;       // 새 모듈
-mod logger;
+mod config;
+mod pipeline;
+mod diag;
+mod stats;
+mod http;
+mod metrics;
+mod subscribe;
+mod segment;
+mod replay;
+mod rotation;
+mod routing;
+mod format;
 
 use crate::proto::UDP_BUF_SIZE;
-use crate::writer::LogWriter;
+use crate::routing::Sinks;
 use crate::udp::UdpRx;
 use crate::tcp::TcpRx;
 
 use crate::log_store::LogStore;
 use crate::console_select::ConsoleSelect;
 use crate::console_degsign::render_home;
+use crate::config::{Cli, Config};
+use crate::pipeline::Ingest;
+use crate::diag::diag;
+use crate::http::{Api, HttpServer};
+use crate::subscribe::SubscribeRx;
+use crate::segment::SegmentStore;
 
-// ───────────────────────────────────────────────────────────────────────────────
-// 자동 백업 설정 (필요시 값만 조정)
-// - AUTO_BACKUP_ENABLED : 자동백업 ON/OFF
-// - AUTO_BACKUP_INTERVAL_SECS : 자동백업 간격(초)
-// - AUTO_BACKUP_QUERY_STR : 자동백업에 사용할 선택 쿼리 문자열(명령 형식과 동일)
-//   예) "latest limit=10000", ""(전체), "level>=Warn latest limit=5000" 등
-// 파일명은 외부 크레이트 없이 epoch 초 기반으로 생성합니다.
-// ───────────────────────────────────────────────────────────────────────────────
-const AUTO_BACKUP_ENABLED: bool = true;
-const AUTO_BACKUP_INTERVAL_SECS: u64 = 60;
-const AUTO_BACKUP_QUERY_STR: &str = "latest limit=10000";
-
+// 로테이션 / 거부 배치 보고 / 자동 백업 점검 주기
+const HOUSEKEEP_INTERVAL: Duration = Duration::from_millis(200);
 
 fn main() -> io::Result<()> {
-    // 인메모리 로그 저장소 & 콘솔 셀렉터
-    let store = Arc::new(LogStore::with_capacity(200_000, 8)); // 총 20만 건, 8 샤드
-    let console_select = Arc::new(ConsoleSelect::new(store.clone()));
+    // 설정: 기본값 → --config 파일 → CLI 플래그
+    let cfg = match Config::from_args(std::env::args().skip(1)) {
+        Ok(Cli::Run(cfg)) => *cfg,
+        Ok(Cli::Help) => {
+            println!("{}", config::usage());
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("config error: {e}\n\n{}", config::usage());
+            std::process::exit(2);
+        }
+    };
+    println!("── effective configuration ──\n{cfg}\n");
+    stats::mark_start();
+    if let Err(e) = diag::init(&cfg.diag) {
+        eprintln!("diag init failed ({}): {e}", cfg.diag.file);
+        std::process::exit(2);
+    }
+
+    // 인메모리 로그 저장소(+ 디스크 세그먼트) & 콘솔 셀렉터
+    let mut store = LogStore::with_capacity(cfg.store.capacity, cfg.store.shards);
+    if cfg.store.index {
+        store = store.with_index(cfg.store.index_bucket_ms, cfg.store.text_index);
+    }
+    if cfg.segments.enabled {
+        let seg = SegmentStore::open(&cfg.segments)
+            .inspect_err(|e| diag!(Error, "segment", "cannot open {}: {e}", cfg.segments.dir))?;
+        let u = seg.usage();
+        diag!(Info, "segment", "{}: {} segment(s), {} records, {} bytes", cfg.segments.dir, u.segments, u.records, u.bytes);
+        store = store.with_segments(Arc::new(seg));
+    }
+    if cfg.store.replay {
+        match replay::replay(&store, &cfg.writer) {
+            Ok(r) if r.records > 0 => diag!(Info, "replay", "restored {} record(s) from {} ({} file(s), {} line(s) skipped)",
+                r.records, r.from, r.files, r.skipped_lines),
+            Ok(_) => {}
+            Err(e) => diag!(Warn, "replay", "startup replay failed: {e}"),
+        }
+    }
+    let store = Arc::new(store);
+    let console_select = Arc::new(ConsoleSelect::new(store.clone(), cfg.backup.quota()));
 
     render_home("SwiftLog", env!("CARGO_PKG_VERSION"), store.len(), true);
 
     // 콘솔 입력용 채널 & 스레드
     let (tx_cmd, rx_cmd) = mpsc::channel::<String>();
-    {
-        let tx_cmd = tx_cmd.clone();
-        thread::spawn(move || {
-            let stdin = io::stdin();
-            loop {
-                let mut line = String::new();
-                if stdin.read_line(&mut line).is_ok() {
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        loop {
+            let mut line = String::new();
+            match stdin.read_line(&mut line) {
+                // EOF(stdin 닫힘, 백그라운드 실행 등): 콘솔만 종료
+                Ok(0) => break,
+                Ok(_) => {
                     let line = line.trim().to_string();
                     if !line.is_empty() {
                         // 실패해도 무시하고 계속(콘솔 끊김 방지)
                         let _ = tx_cmd.send(line);
                     }
                 }
+                Err(_) => {}
             }
-        });
+        }
+    });
+
+    // 파이프라인: 수신 스레드 → parse queue → parser → write queue → writer (pipeline.rs)
+    let sinks = Sinks::open(&cfg.writer)
+        .inspect_err(|e| diag!(Error, "writer", "cannot open log files in {}: {e}", cfg.writer.dir))?;
+    let ingest = pipeline::start(&cfg.pipeline, store.clone(), sinks)?;
+
+    // 수신 스레드: UDP 1개, TCP accept 1개 + 연결마다 1개
+    UdpRx::bind(&cfg.net.udp_bind, UDP_BUF_SIZE)
+        .inspect_err(|e| diag!(Error, "net", "udp bind {} failed: {e}", cfg.net.udp_bind))?
+        .spawn(Ingest::handler(ingest.udp))?;
+    TcpRx::bind(&cfg.net.tcp_bind)
+        .inspect_err(|e| diag!(Error, "net", "tcp bind {} failed: {e}", cfg.net.tcp_bind))?
+        .with_max_connections(cfg.net.max_tcp_connections)
+        .spawn(Ingest::handler(ingest.tcp))?;
+    if !cfg.net.http_bind.is_empty() {
+        HttpServer::bind(&cfg.net.http_bind)
+            .inspect_err(|e| diag!(Error, "net", "http bind {} failed: {e}", cfg.net.http_bind))?
+            .with_max_connections(cfg.net.max_http_connections)
+            .spawn(Arc::new(Api { console: console_select.clone(), backup_dir: cfg.auto_backup.dir.clone() }))?;
     }
-
-    // 네트워크 바인딩
-    let udp_bind = "127.0.0.1:9050";
-    let tcp_bind = "127.0.0.1:9502";
-
-    let mut writer = LogWriter::open("logs", "app")?;
-
-    let mut udp = UdpRx::bind(udp_bind, UDP_BUF_SIZE)?;
-    let mut tcp = TcpRx::bind(tcp_bind)?;
+    if !cfg.subscribe.bind.is_empty() {
+        SubscribeRx::bind(&cfg.subscribe.bind)
+            .inspect_err(|e| diag!(Error, "net", "subscribe bind {} failed: {e}", cfg.subscribe.bind))?
+            .with_max_subscribers(cfg.subscribe.max_subscribers)
+            .spawn(store.clone(), cfg.subscribe.buffer)?;
+    }
+    let or_off = |s: &str| if s.is_empty() { "off".to_string() } else { s.to_string() };
+    diag!(Info, "server", "SwiftLog {} listening udp={} tcp={} http={} subscribe={}, writing {}/{}.log",
+        env!("CARGO_PKG_VERSION"), cfg.net.udp_bind, cfg.net.tcp_bind, or_off(&cfg.net.http_bind),
+        or_off(&cfg.subscribe.bind), cfg.writer.dir, cfg.writer.base);
 
     let mut last_housekeep = SystemTime::now();
+    let mut reported_rejects = 0u64;
+    let mut reported_drops = (0u64, 0u64); // (parse queue, write queue)
 
     // 자동 백업 타이머
     let mut last_auto_backup = SystemTime::now();
+    let auto_backup_busy = Arc::new(AtomicBool::new(false));
 
-    // 메인 루프
+    // 메인 루프: 콘솔 명령 + 하우스키핑 (네트워크는 수신 스레드가 처리)
     loop {
-        // ── 콘솔 명령 처리(논블로킹) ────────────────────────────────────────────────
-        // 한 틱에 누적된 명령들을 최대한 소진
-        while let Ok(cmd) = rx_cmd.try_recv() {
-            dispatch_console_command(&cmd, &console_select);
+        // ── 콘솔 명령 처리 (없으면 하우스키핑 주기까지 대기) ─────────────────────────
+        match rx_cmd.recv_timeout(HOUSEKEEP_INTERVAL) {
+            Ok(cmd) => dispatch_console_command(&cmd, &console_select),
+            Err(RecvTimeoutError::Timeout) => {}
+            // 콘솔 스레드 종료(stdin EOF): 하우스키핑만 계속
+            Err(RecvTimeoutError::Disconnected) => thread::sleep(HOUSEKEEP_INTERVAL),
         }
 
-        udp.recv_once(|datagram| {
-            parser::parse_and_store_and_write(datagram, Some(&store), |line| writer.write_line(line))?;
-            Ok(())
-        });
-
-        // TCP
-        tcp.accept_once();
-        tcp.poll_once(|batch| {
-            parser::parse_and_store_and_write(batch, Some(&store), |line| writer.write_line(line))?;
-            Ok(())
-        });
-
         // ── 주기적 하우스키핑 ──────────────────────────────────────────────────────
-        if last_housekeep.elapsed().unwrap_or(Duration::from_secs(0)) > Duration::from_millis(200) {
-            let _ = writer.rotate_if_needed();
+        if last_housekeep.elapsed().unwrap_or(Duration::from_secs(0)) >= HOUSEKEEP_INTERVAL {
             last_housekeep = SystemTime::now();
 
-            // ── 자동 백업 (용량 쿼터는 handle_backup 내부 ensure_backup_quota로 수행) ──
-            if AUTO_BACKUP_ENABLED {
-                if last_auto_backup
+            // ── 손상 배치 보고 (새로 늘었을 때만) ───────────────────────────────────
+            let s = stats::snapshot(&store);
+            if s.batches_rejected != reported_rejects {
+                diag!(Warn, "ingest", "rejected {} corrupt batch(es) (total {}: magic {}, version {}, truncated {}, checksum {}, malformed {})",
+                    s.batches_rejected - reported_rejects, s.batches_rejected, s.rejected_bad_magic,
+                    s.rejected_bad_version, s.rejected_truncated, s.rejected_checksum, s.rejected_malformed);
+                reported_rejects = s.batches_rejected;
+            }
+
+            // ── 큐 가득 참으로 버린 배치 보고 (drop 정책) ─────────────────────────────
+            let drops = (pipeline::PARSE_QUEUE.stats().dropped, pipeline::WRITE_QUEUE.stats().dropped);
+            if drops != reported_drops {
+                diag!(Warn, "pipeline", "queue full, dropped batches: parse +{} write +{} (see `Queues`)",
+                    drops.0 - reported_drops.0, drops.1 - reported_drops.1);
+                reported_drops = drops;
+            }
+
+            // ── 자동 백업 (용량 쿼터는 쓰는 동안 handle_backup 안에서 확인) ──
+            if cfg.auto_backup.enabled
+                && last_auto_backup
                     .elapsed()
                     .unwrap_or(Duration::from_secs(0))
-                    > Duration::from_secs(AUTO_BACKUP_INTERVAL_SECS)
-                {
-                    // 파일명: auto/backup_<EPOCH>.tsv  (외부 크레이트 없이 간단히)
-                    let epoch_secs = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs();
-                    let path = format!("auto/backup_{}.tsv", epoch_secs);
-
-                    // ConsoleSelect 메서드 래퍼를 이용 → 내부에서 SelectQuery 구성 & ensure_backup_quota 적용
-                    if let Err(e) = console_select.handle_backup(&path, AUTO_BACKUP_QUERY_STR) {
-                        eprintln!("[auto-backup] skipped or failed: {e}");
+                    > Duration::from_secs(cfg.auto_backup.interval_secs)
+            {
+                // 파일명: <auto_backup.dir>/backup_<EPOCH>.<형식 확장자>  (형식은 확장자로 정해짐)
+                let epoch_secs = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let path = format!("{}/backup_{}.{}", cfg.auto_backup.dir, epoch_secs, cfg.auto_backup.format.ext());
+
+                // 백그라운드 스레드에서 씀. 이전 자동 백업이 아직 돌고 있으면 이번 회차는 건너뜀
+                if auto_backup_busy.swap(true, Ordering::AcqRel) {
+                    diag!(Warn, "auto-backup", "previous backup still running, skipped {path}");
+                } else {
+                    let busy = auto_backup_busy.clone();
+                    let done_path = path.clone();
+                    let started = console_select.start_backup(&path, &cfg.auto_backup.query, move |res| {
+                        match res {
+                            Ok(rows) => diag!(Debug, "auto-backup", "wrote {rows} row(s) to {done_path}"),
+                            Err(e) => diag!(Warn, "auto-backup", "skipped or failed: {e}"),
+                        }
+                        busy.store(false, Ordering::Release);
+                    });
+                    if let Err(e) = started {
+                        diag!(Warn, "auto-backup", "skipped or failed: {e}");
+                        auto_backup_busy.store(false, Ordering::Release);
                     }
-
-                    last_auto_backup = SystemTime::now();
                 }
+
+                last_auto_backup = SystemTime::now();
             }
         }
-
-        // 폴링 슬립
-        std::thread::sleep(Duration::from_millis(5));
     }
 }
 
@@ -175,7 +267,13 @@ fn dispatch_console_command(cmd_line: &str, console: &Arc<ConsoleSelect>) {
         let args = parts.next().map(str::trim).filter(|s| !s.is_empty());
 
         let args_str = args.unwrap_or("");           // Option<&str> → &str
-        if let Err(e) = console.handle_backup(path, args_str) {
+        // 쓰는 동안에도 콘솔/하우스키핑은 계속 (끝나면 결과를 출력)
+        let done_path = path.to_string();
+        let started = console.start_backup(path, args_str, move |res| match res {
+            Ok(rows) => println!("Backup: {rows} row(s) written to {done_path}"),
+            Err(e) => eprintln!("Backup failed: {e}"),
+        });
+        if let Err(e) = started {
             eprintln!("Backup failed: {e}");
         }
         return;
@@ -186,6 +284,21 @@ fn dispatch_console_command(cmd_line: &str, console: &Arc<ConsoleSelect>) {
         return;
     }
 
+    if lower == "queues" {
+        print_queues();
+        return;
+    }
+
+    if lower == "stats" {
+        stats::print(&stats::snapshot(console.store()));
+        return;
+    }
+
+    if lower == "stats json" {
+        println!("{}", stats::snapshot(console.store()).to_json());
+        return;
+    }
+
     if lower == "home" {
         render_home("SwiftLog", env!("CARGO_PKG_VERSION"), console.store_len(), false);
         return;
@@ -197,6 +310,8 @@ fn dispatch_console_command(cmd_line: &str, console: &Arc<ConsoleSelect>) {
              - ShowLogList\n\
              - SelectLog <query>\n\
              - BackupLog <path> [\"query\"]\n\
+             - Queues\n\
+             - Stats [json]\n\
              - ClearScreen (clear/cls)\n\
              - Home\n\
              - Help"
@@ -207,3 +322,12 @@ fn dispatch_console_command(cmd_line: &str, console: &Arc<ConsoleSelect>) {
     // 알 수 없는 명령
     eprintln!("Unknown command: {cmd_line}");
 }
+
+// 파이프라인 큐 상태 (깊이 / 최고 수위 / 용량 / 드롭)
+fn print_queues() {
+    println!("{:<8} | {:>8} | {:>10} | {:>8} | {:>10}", "QUEUE", "DEPTH", "HIGH-WATER", "CAPACITY", "DROPPED");
+    for (name, g) in [("parse", &pipeline::PARSE_QUEUE), ("write", &pipeline::WRITE_QUEUE)] {
+        let s = g.stats();
+        println!("{:<8} | {:>8} | {:>10} | {:>8} | {:>10}", name, s.depth, s.high_water, s.capacity, s.dropped);
+    }
+}
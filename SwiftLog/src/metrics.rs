@@ -0,0 +1,145 @@
+// src/metrics.rs
+// Prometheus 텍스트 노출 형식(0.0.4) 렌더링 — HTTP `GET /metrics`
+//
+// 값은 stats 스냅샷 + LogStore 샤드별 적재량 + 큐 게이지에서 그대로 가져옵니다.
+// 처리율은 누적 카운터(_total)로만 내보내고 rate()는 Prometheus 쪽에서 계산합니다.
+
+use std::fmt::Write;
+
+use crate::log_domain::LogLevel;
+use crate::log_store::LogStore;
+use crate::pipeline;
+use crate::stats::{self, Snapshot};
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// 라벨 값은 관례대로 소문자
+const LEVEL_LABELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+struct Out(String);
+
+impl Out {
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {name} {help}");
+        let _ = writeln!(self.0, "# TYPE {name} {kind}");
+    }
+
+    fn metric(&mut self, name: &str, kind: &str, help: &str, v: u64) {
+        self.header(name, kind, help);
+        let _ = writeln!(self.0, "{name} {v}");
+    }
+
+    // 라벨 값은 코드에서 정한 식별자뿐이라 이스케이프 불필요
+    fn labeled(&mut self, name: &str, kind: &str, help: &str, label: &str, rows: &[(&str, u64)]) {
+        self.header(name, kind, help);
+        for (l, v) in rows {
+            let _ = writeln!(self.0, "{name}{{{label}=\"{l}\"}} {v}");
+        }
+    }
+}
+
+pub fn render(store: &LogStore) -> String {
+    render_snapshot(&stats::snapshot(store), &store.shard_lens(), store.capacity())
+}
+
+fn render_snapshot(s: &Snapshot, shard_lens: &[usize], capacity: usize) -> String {
+    let mut o = Out(String::with_capacity(4096));
+    o.metric("swiftlog_uptime_seconds", "gauge", "Seconds since server start.", s.uptime_secs);
+
+    // ── 수신 ──
+    o.metric("swiftlog_udp_datagrams_total", "counter", "UDP datagrams received.", s.udp_datagrams);
+    o.metric("swiftlog_tcp_connections_total", "counter", "TCP connections accepted.", s.tcp_connections);
+    o.metric("swiftlog_tcp_frames_total", "counter", "TCP batch frames received.", s.tcp_frames);
+    o.metric("swiftlog_tcp_connections_open", "gauge", "TCP connections open right now.", s.tcp_connections_open);
+    o.metric("swiftlog_tcp_connections_refused_total", "counter",
+        "TCP connections closed at accept because net.max_tcp_connections was reached.", s.tcp_connections_refused);
+    o.metric("swiftlog_tcp_frames_oversized_total", "counter",
+        "TCP frames whose length prefix exceeded the limit (connection closed).", s.tcp_frames_oversized);
+    o.metric("swiftlog_received_bytes_total", "counter", "Bytes read from UDP and TCP sockets.", s.bytes_received);
+    o.metric("swiftlog_http_connections_open", "gauge", "HTTP API connections open right now.", s.http_connections_open);
+    o.metric("swiftlog_http_connections_refused_total", "counter",
+        "HTTP API connections answered 503 because net.max_http_connections was reached.", s.http_connections_refused);
+
+    // ── 디코드 ──
+    o.metric("swiftlog_batches_accepted_total", "counter", "Batches that passed validation.", s.batches_accepted);
+    o.labeled("swiftlog_batches_rejected_total", "counter", "Batches rejected by the decoder.", "reason", &[
+        ("bad_magic", s.rejected_bad_magic),
+        ("bad_version", s.rejected_bad_version),
+        ("truncated", s.rejected_truncated),
+        ("checksum", s.rejected_checksum),
+        ("malformed", s.rejected_malformed),
+    ]);
+    let levels: Vec<(&str, u64)> = LogLevel::ALL.iter()
+        .map(|l| (LEVEL_LABELS[*l as usize], s.records_by_level[*l as usize]))
+        .collect();
+    o.labeled("swiftlog_records_total", "counter", "Records parsed, by level.", "level", &levels);
+    o.metric("swiftlog_records_invalid_total", "counter", "Records over validate() limits, not stored.", s.records_invalid);
+
+    // ── 파이프라인 큐 ──
+    let queues = [("parse", pipeline::PARSE_QUEUE.stats()), ("write", pipeline::WRITE_QUEUE.stats())];
+    let rows = |f: fn(&pipeline::QueueStats) -> u64| queues.iter().map(|(n, q)| (*n, f(q))).collect::<Vec<_>>();
+    o.labeled("swiftlog_queue_depth", "gauge", "Items waiting in a pipeline queue.", "queue", &rows(|q| q.depth));
+    o.labeled("swiftlog_queue_capacity", "gauge", "Pipeline queue capacity.", "queue", &rows(|q| q.capacity));
+    o.labeled("swiftlog_queue_dropped_total", "counter", "Items dropped because a queue was full.", "queue",
+        &rows(|q| q.dropped));
+
+    // ── 저장소 ──
+    let names: Vec<String> = (0..shard_lens.len()).map(|i| i.to_string()).collect();
+    let shards: Vec<(&str, u64)> = names.iter().zip(shard_lens).map(|(n, l)| (n.as_str(), *l as u64)).collect();
+    o.labeled("swiftlog_store_shard_records", "gauge", "Records held per LogStore shard.", "shard", &shards);
+    o.metric("swiftlog_store_shard_capacity", "gauge", "Capacity of each LogStore shard.",
+        (capacity / shard_lens.len().max(1)) as u64);
+    o.metric("swiftlog_store_records", "gauge", "Records held in the LogStore.", s.store_len);
+    o.metric("swiftlog_store_capacity", "gauge", "Total LogStore capacity.", s.store_capacity);
+    o.metric("swiftlog_store_evictions_total", "counter", "Records evicted from the LogStore ring.", s.store_evictions);
+    o.metric("swiftlog_store_index_entries", "gauge", "IDs held by LogStore secondary indexes.", s.store_index_entries);
+    o.metric("swiftlog_store_index_bytes", "gauge", "Approximate memory used by LogStore secondary indexes.", s.store_index_bytes);
+    o.metric("swiftlog_store_index_tokens", "gauge", "Distinct message tokens in the LogStore text index.", s.store_index_tokens);
+    o.metric("swiftlog_segments", "gauge", "On-disk segments.", s.segments);
+    o.metric("swiftlog_segment_bytes", "gauge", "Bytes held in on-disk segments.", s.segment_bytes);
+    o.metric("swiftlog_segment_records", "gauge", "Records held in on-disk segments.", s.segment_records);
+    o.metric("swiftlog_segment_blocks_read_total", "counter", "Segment blocks decoded by queries.", s.segment_blocks_read);
+
+    // ── 파일 기록 / 백업 / 구독 ──
+    o.metric("swiftlog_written_bytes_total", "counter", "Bytes written to the log file.", s.bytes_written);
+    o.metric("swiftlog_writer_file_bytes", "gauge", "Total size of the open log files (default and routed sinks).", s.writer_file_bytes);
+    o.metric("swiftlog_writer_rotations_total", "counter", "Log file rotations.", s.writer_rotations);
+    o.metric("swiftlog_writer_fsyncs_total", "counter", "Log file syncs made by the fsync policy.", s.writer_fsyncs);
+    o.metric("swiftlog_writer_files_compressed_total", "counter", "Rotated log files compressed.", s.writer_files_compressed);
+    o.metric("swiftlog_writer_files_pruned_total", "counter", "Rotated log files removed by retention.", s.writer_files_pruned);
+    o.labeled("swiftlog_backups_total", "counter", "Backup runs, by result.", "result", &[
+        ("ok", s.backups_ok),
+        ("failed", s.backups_failed),
+    ]);
+    o.metric("swiftlog_backups_running", "gauge", "Backups being written right now.", s.backups_running);
+    o.metric("swiftlog_subscribers", "gauge", "Live subscribers.", s.subscribers);
+    o.metric("swiftlog_subscribe_connections_open", "gauge",
+        "Subscribe connections open right now, including ones still in the handshake.", s.subscribe_connections_open);
+    o.metric("swiftlog_subscribers_refused_total", "counter",
+        "Subscribe connections closed because subscribe.max_subscribers was reached.", s.subscribers_refused);
+    o.metric("swiftlog_subscriber_dropped_total", "counter", "Records skipped because a subscriber buffer was full.",
+        s.subscriber_dropped);
+    o.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposition_has_types_and_labels() {
+        let s = Snapshot { records_by_level: [0, 0, 5, 2, 1], backups_failed: 3, ..Default::default() };
+        let text = render_snapshot(&s, &[3, 4], 10);
+        assert!(text.contains("# TYPE swiftlog_records_total counter\n"));
+        assert!(text.contains("swiftlog_records_total{level=\"info\"} 5\n"));
+        assert!(text.contains("swiftlog_records_total{level=\"error\"} 1\n"));
+        assert!(text.contains("swiftlog_store_shard_records{shard=\"1\"} 4\n"));
+        assert!(text.contains("swiftlog_store_shard_capacity 5\n"));
+        assert!(text.contains("swiftlog_backups_total{result=\"failed\"} 3\n"));
+        // 모든 샘플 줄은 "이름[{라벨}] 정수"
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let (_, v) = line.rsplit_once(' ').unwrap();
+            assert!(v.parse::<u64>().is_ok(), "{line}");
+        }
+    }
+}
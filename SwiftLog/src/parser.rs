@@ -1,107 +1,212 @@
-use crate::proto::{MAGIC, VERSION};
-use std::convert::TryInto;
 use std::io;
 
 use std::sync::Arc;
+use swiftlog_proto::{Batch, DecodeError, RecordRef};
 use crate::log_store::LogStore;
-use crate::log_domain::{Log, LogLevel};
-
-use crate::logger::{self, *};
-
-pub fn parse_and_write(batch: &[u8], mut sink: impl FnMut(&[u8]) -> io::Result<()>) {
-    let _ = parse_and_store_and_write(batch, None, &mut sink);
+use crate::diag::diag;
+use crate::stats;
+use crate::format::Row;
+use crate::log_domain::{Log, Source};
+
+// 손상/거부된 배치 집계는 stats의 사유별 카운터 (배치 단위로 버리므로 부분 반영 없음)
+fn note_rejected(e: DecodeError) {
+    diag!(Debug, "parser", "rejected batch: {e}");
+    stats::note_reject(&e);
 }
 
+/// 저장소에 들어간 로그(ID 포함)를 배치 순서대로 돌려줍니다 (디스크 세그먼트 블록용).
+/// sink는 출력할 행(형식은 sink 쪽 Formatter가 정함)과 저장된 로그를 받습니다
+/// (저장소가 없거나 검증에 실패하면 None, 출력 라우팅용).
 pub fn parse_and_store_and_write(
     batch: &[u8],
+    source: Option<&Arc<Source>>,
     store: Option<&Arc<LogStore>>,
-    mut sink: impl FnMut(&[u8]) -> io::Result<()>,
-) -> io::Result<()> {
-    // 헤더 검사: C++는 magic(4) + version(u32, 4) 를 씁니다.
-    logger::log_to_file("parse_and_store_and_write Step 1");
-    if batch.len() < 8 { return Ok(()); }
-    logger::log_to_file("parse_and_store_and_write Step 2");
-    if &batch[0..4] != &MAGIC.to_le_bytes() { return Ok(()); }
-    logger::log_to_file("parse_and_store_and_write Step 3");
-    if &batch[4..8] != &VERSION.to_le_bytes() { return Ok(()); }
-
-    logger::log_to_file("parse_and_store_and_write Step 4");
-
-
-    let mut p = 8usize;
-    loop {
-        logger::log_to_file("parse_and_store_and_write Step 5");
-        // 각 레코드의 최소 고정 영역: id(8) + ts_ms(8) + level(1) + code(2) + len(2)
-        const MIN_RECORD_FIXED: usize = 8 + 8 + 1 + 2 + 2;
-        if p + MIN_RECORD_FIXED > batch.len() { break; }
-
-        logger::log_to_file("parse_and_store_and_write Step 6");
-
-        // id (u64) -- 현재는 사용하지 않지만 포맷상 먼저 옴
-        let _id = u64::from_le_bytes(batch[p..p+8].try_into().unwrap());
-        p += 8;
-
-        // ts_ms (u64)
-        let ts_ms = u64::from_le_bytes(batch[p..p+8].try_into().unwrap());
-        p += 8;
-
-        // level (u8)
-        let level_u8 = batch[p];
-        p += 1;
-
-        // code (u16, LE)
-        let code = u16::from_le_bytes(batch[p..p+2].try_into().unwrap());
-        p += 2;
-
-        // msg_len (u16, LE)
-        let len = u16::from_le_bytes(batch[p..p+2].try_into().unwrap()) as usize;
-        p += 2;
-
-        // 메시지 바이트가 충분히 남았는지 체크
-        if p + len > batch.len() { break; }
-        logger::log_to_file("parse_and_store_and_write Step 7");        
-        let msg_bytes = &batch[p..p+len];
-        p += len;
-
-        // --- (1) 파일용 TSV 라인 구성 후 sink에 기록 ------------------------------
-        let mut line = Vec::with_capacity(64 + len);
-        line.extend_from_slice(ts_ms.to_string().as_bytes());
-        line.push(b'\t');
-        line.extend_from_slice(level_u8.to_string().as_bytes());
-        line.push(b'\t');
-        line.extend_from_slice(code.to_string().as_bytes());
-        line.push(b'\t');
-        line.extend_from_slice(msg_bytes);
-
-        sink(&line)?; // 파일/파이프 등에 기록
-
-        // --- (2) 인메모리 저장소에 append (옵션) --------------------------------
-        if let Some(store) = store {
-            on_parsed_entry(ts_ms, level_u8, code, msg_bytes, store);
-        }
+    mut sink: impl FnMut(&Row<'_>, Option<&Log>) -> io::Result<()>,
+) -> io::Result<Vec<Arc<Log>>> {
+    // 배치 전체 검증 (magic / version / crc / 레코드 경계 / count) — 실패 시 통째로 버림
+    let batch = Batch::parse(batch).map_err(|e| {
+        note_rejected(e);
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    })?;
+
+    diag!(Trace, "parser", "batch v{} count={} from {:?}", batch.version(), batch.count(), source.map(|s| s.peer));
+    stats::add(&stats::BATCHES_ACCEPTED, 1);
+    stats::add(&stats::RECORDS_PARSED, batch.count() as u64);
+    let mut stored = Vec::new();
+    for rec in batch.records() {
+        // --- (1) 인메모리 저장소에 append (옵션) --------------------------------
+        let log = store.and_then(|store| on_parsed_entry(&rec, source, store));
+
+        // --- (2) 출력 행을 sink에 넘김 (파일/파이프 등) -------------------------
+        let mut row = Row::from_record(&rec, source.map(|s| &**s));
+        row.id = log.as_ref().map(|l| l.id);
+        sink(&row, log.as_deref())?;
+        stored.extend(log);
     }
-    logger::log_to_file("parse_and_store_and_write Step End");
-    Ok(())
+    Ok(stored)
 }
 
 // 내부 유틸: 파싱된 엔트리 1건을 LogStore에 적재
-fn on_parsed_entry(
-    ts_ms: u64,
-    level_u8: u8,
-    code: u16,
-    msg_bytes: &[u8],
-    store: &Arc<LogStore>,
-) {
-    let level = match level_u8 {
-        0 => LogLevel::Trace,
-        1 => LogLevel::Debug,
-        2 => LogLevel::Info,
-        3 => LogLevel::Warn,
-        _ => LogLevel::Error,
-    };
-    let msg = String::from_utf8_lossy(msg_bytes).into_owned();
-    let log = Log::new_unassigned(ts_ms, level, code, msg);
+fn on_parsed_entry(rec: &RecordRef<'_>, source: Option<&Arc<Source>>, store: &Arc<LogStore>) -> Option<Arc<Log>> {
+    stats::note_level(rec.level());
+    let fields = rec.fields.iter().map(|(k, v)| (k.to_string(), v.to_owned())).collect();
+    let log = Log::new_unassigned(rec.ts_ms, rec.level(), rec.code, rec.msg_lossy())
+        .with_fields(fields)
+        .with_source(source.cloned());
     if log.validate().is_ok() {
-        store.append(log);
+        Some(store.append(log))
+    } else {
+        stats::add(&stats::RECORDS_INVALID, 1);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use SwiftLog::{LogLevel, Logger};
+    use crate::tcp::TcpRx;
+    use crate::udp::UdpRx;
+    use crate::format::{Formatter, Tsv};
+    use crate::log_store::SelectQuery;
+
+    fn wait_until(mut f: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !f() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    fn tsv(row: &Row<'_>) -> Vec<u8> {
+        let mut line = Vec::new();
+        Tsv.write_row(&mut line, row);
+        line
+    }
+
+    // 수신 스레드가 쓰는 핸들러: store + 라인 수집
+    fn ingest(
+        store: &Arc<LogStore>,
+        lines: &Arc<Mutex<Vec<Vec<u8>>>>,
+    ) -> impl FnMut(&Arc<Source>, &[u8]) -> io::Result<()> + Clone + Send + 'static {
+        let (store, lines) = (store.clone(), lines.clone());
+        move |src, batch| {
+            let mut lines = lines.lock().unwrap();
+            parse_and_store_and_write(batch, Some(src), Some(&store), |row, _| { lines.push(tsv(row)); Ok(()) })
+                .map(|_| ())
+        }
+    }
+
+    #[test]
+    fn logger_udp_batches_are_accepted() {
+        let store = Arc::new(LogStore::with_capacity(1000, 4));
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let rx = UdpRx::bind("127.0.0.1:0", 2048).unwrap();
+        let addr = rx.local_addr().unwrap();
+        rx.spawn(ingest(&store, &lines)).unwrap();
+
+        let mut logger = Logger::new_udp(addr).unwrap();
+        logger.log(LogLevel::Info, 1001, "hello swiftlog");
+        logger.log(LogLevel::Error, 5001, "Database connection failed");
+        logger.flush().unwrap();
+
+        wait_until(|| store.len() == 2);
+        let lines = lines.lock().unwrap();
+
+        let rows = store.select(&SelectQuery::default());
+        assert_eq!(rows[0].level, LogLevel::Info);
+        assert_eq!(rows[0].code, 1001);
+        assert_eq!(rows[0].msg, "hello swiftlog");
+        assert_eq!(rows[1].level, LogLevel::Error);
+        assert_eq!(rows[1].msg, "Database connection failed");
+        assert!(lines[1].ends_with(b"\t4\t5001\tDatabase connection failed\n"));
+
+        // UDP는 peer만 기록
+        let local = rows[0].source.as_ref().unwrap().peer.to_string();
+        let q = SelectQuery { source: Some(local), ..Default::default() };
+        assert_eq!(store.select(&q).len(), 2);
+        assert!(rows[0].source.as_ref().unwrap().identity.is_none());
+    }
+
+    #[test]
+    fn logger_tcp_batches_are_accepted() {
+        let store = Arc::new(LogStore::with_capacity(1000, 4));
+        let rx = TcpRx::bind("127.0.0.1:0").unwrap();
+        let addr = rx.local_addr().unwrap();
+        rx.spawn(ingest(&store, &Arc::new(Mutex::new(Vec::new())))).unwrap();
+
+        // new_tcp는 HelloAck까지 기다림 (협상 결과는 최신 버전)
+        let id = SwiftLog::Identity { service: "game-server".into(), host: "gs-07".into(), pid: 4242 };
+        let mut logger = Logger::new_tcp_with_identity(addr, id).unwrap();
+        assert_eq!(logger.version(), swiftlog_proto::MAX_VERSION);
+        for i in 0..10 {
+            logger.log(LogLevel::Warn, 2000 + i, &format!("tcp #{i}"));
+        }
+        logger.flush().unwrap();
+
+        wait_until(|| store.len() == 10);
+
+        let rows = store.select(&SelectQuery::default());
+        assert_eq!(rows[9].code, 2009);
+        assert_eq!(rows[9].msg, "tcp #9");
+
+        let src = rows[9].source.as_ref().unwrap();
+        assert_eq!(src.service(), Some("game-server"));
+        assert_eq!(src.identity.as_ref().unwrap().pid, 4242);
+        for q in [
+            SelectQuery { service: Some("game-server".into()), ..Default::default() },
+            SelectQuery { source: Some("gs-07".into()), ..Default::default() },
+            SelectQuery { source: Some("127.0.0.1".into()), ..Default::default() },
+        ] {
+            assert_eq!(store.select(&q).len(), 10);
+        }
+        let q = SelectQuery { service: Some("other".into()), ..Default::default() };
+        assert!(store.select(&q).is_empty());
+    }
+
+    #[test]
+    fn fields_reach_store_tsv_and_field_filter() {
+        use swiftlog_proto::{FieldValueRef, VERSION_V2};
+        let store = Arc::new(LogStore::with_capacity(10, 1));
+        let mut enc = swiftlog_proto::BatchEncoder::new(VERSION_V2);
+        enc.push_with_fields(1, LogLevel::Info, 1, b"login",
+            &[("user_id", FieldValueRef::I64(42)), ("region", FieldValueRef::Str("eu"))]);
+        enc.push_with_fields(2, LogLevel::Info, 1, b"login", &[("user_id", FieldValueRef::I64(7))]);
+        let mut lines = Vec::new();
+        parse_and_store_and_write(enc.finish(), None, Some(&store), |row, _| { lines.push(tsv(row)); Ok(()) }).unwrap();
+
+        assert_eq!(lines[0], b"1\t2\t1\tlogin\tuser_id=42 region=\"eu\"\n");
+        let q = SelectQuery { fields: vec![("user_id".into(), "42".into())], ..Default::default() };
+        let rows = store.select(&q);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].field("region"), Some(&crate::log_domain::FieldValue::Str("eu".into())));
+    }
+
+    #[test]
+    fn corrupt_batches_are_rejected_whole_and_counted() {
+        use swiftlog_proto::{BatchEncoder, VERSION_V1, VERSION_V2};
+        let store = Arc::new(LogStore::with_capacity(10, 1));
+        let before = stats::snapshot(&store);
+
+        for version in [VERSION_V1, VERSION_V2] {
+            let mut enc = BatchEncoder::new(version);
+            enc.push(1, LogLevel::Info, 1, b"first");
+            enc.push(2, LogLevel::Info, 2, b"second");
+            let good = enc.finish().to_vec();
+
+            let mut bad_magic = good.clone();
+            bad_magic[0] = 0;
+            let truncated = &good[..good.len() - 2];
+            for bad in [&bad_magic[..], truncated] {
+                assert!(parse_and_store_and_write(bad, None, Some(&store), |_, _| Ok(())).is_err());
+            }
+        }
+        assert_eq!(store.len(), 0);
+        let after = stats::snapshot(&store);
+        assert!(after.batches_rejected >= before.batches_rejected + 4);
+        assert!(after.rejected_bad_magic >= before.rejected_bad_magic + 2);
     }
 }
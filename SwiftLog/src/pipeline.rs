@@ -0,0 +1,332 @@
+// src/pipeline.rs
+// 수신 → 파싱 → 기록 3단 파이프라인
+//
+//   UdpRx / TcpRx 스레드 ──(parse queue: RawBatch)──▶ parser 스레드 ──(write queue: WriteItem)──▶ writer 스레드
+//                                                     └─ LogStore::append               (로그 파일 + 디스크 세그먼트)
+//
+// 큐는 모두 용량 고정(sync_channel). 가득 찼을 때는 큐마다 정책을 고릅니다.
+//   block : 앞 단계가 기다림 (TCP는 커널 윈도우를 통해 클라이언트까지 역압)
+//   drop  : 새 항목을 버리고 카운트 (UDP 수신이 느린 디스크에 묶이지 않도록)
+// 큐 깊이/최고 수위/드롭 수는 정적 게이지로 노출합니다 (콘솔 `Queues`).
+
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diag::diag;
+use crate::log_domain::Source;
+use crate::log_store::LogStore;
+use crate::parser;
+use crate::routing::Sinks;
+use crate::segment::{self, Block};
+
+// 쓸 게 없어도 이 주기로 flush/fsync와 로테이션을 점검
+const WRITER_IDLE_TICK: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnFull {
+    Block,
+    Drop,
+}
+
+impl OnFull {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "block" => Some(OnFull::Block),
+            "drop" => Some(OnFull::Drop),
+            _ => None,
+        }
+    }
+}
+
+/// 큐 하나의 관측값
+pub struct QueueGauge {
+    depth: AtomicU64,
+    high_water: AtomicU64,
+    dropped: AtomicU64,
+    capacity: AtomicU64,
+}
+
+pub static PARSE_QUEUE: QueueGauge = QueueGauge::new();
+pub static WRITE_QUEUE: QueueGauge = QueueGauge::new();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    pub depth: u64,
+    pub high_water: u64,
+    pub dropped: u64,
+    pub capacity: u64,
+}
+
+impl QueueGauge {
+    pub const fn new() -> Self {
+        Self {
+            depth: AtomicU64::new(0),
+            high_water: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            capacity: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        QueueStats {
+            depth: self.depth.load(Ordering::Relaxed),
+            high_water: self.high_water.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            capacity: self.capacity.load(Ordering::Relaxed),
+        }
+    }
+
+    // 소비자의 leave가 먼저 와도 음수가 되지 않도록 넣기 전에 올리고, 실패하면 되돌림
+    fn enter(&self) -> u64 { self.depth.fetch_add(1, Ordering::Relaxed) + 1 }
+    fn leave(&self) { self.depth.fetch_sub(1, Ordering::Relaxed); }
+}
+
+/// 용량 고정 큐의 송신 측. clone해서 여러 수신 스레드가 나눠 씁니다.
+pub struct QueueTx<T> {
+    tx: SyncSender<T>,
+    gauge: &'static QueueGauge,
+    on_full: OnFull,
+}
+
+impl<T> Clone for QueueTx<T> {
+    fn clone(&self) -> Self { Self { tx: self.tx.clone(), gauge: self.gauge, on_full: self.on_full } }
+}
+
+pub struct QueueRx<T> {
+    rx: Receiver<T>,
+    gauge: &'static QueueGauge,
+}
+
+pub fn queue<T>(capacity: usize, on_full: OnFull, gauge: &'static QueueGauge) -> (QueueTx<T>, QueueRx<T>) {
+    let (tx, rx) = mpsc::sync_channel(capacity);
+    gauge.capacity.store(capacity as u64, Ordering::Relaxed);
+    (QueueTx { tx, gauge, on_full }, QueueRx { rx, gauge })
+}
+
+impl<T> QueueTx<T> {
+    /// 같은 큐에 다른 정책으로 넣는 핸들 (UDP/TCP가 parse queue를 공유)
+    pub fn with_policy(&self, on_full: OnFull) -> Self { Self { on_full, ..self.clone() } }
+
+    /// 넣었으면 true. drop 정책에서 가득 찼거나 소비자가 사라졌으면 false
+    pub fn push(&self, item: T) -> bool {
+        let depth = self.gauge.enter();
+        let ok = match self.on_full {
+            OnFull::Block => self.tx.send(item).is_ok(),
+            OnFull::Drop => match self.tx.try_send(item) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    self.gauge.dropped.fetch_add(1, Ordering::Relaxed);
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+        };
+        if ok {
+            self.gauge.high_water.fetch_max(depth, Ordering::Relaxed);
+        } else {
+            self.gauge.leave();
+        }
+        ok
+    }
+}
+
+impl<T> QueueRx<T> {
+    fn recv_timeout(&self, t: Duration) -> Result<T, RecvTimeoutError> {
+        let item = self.rx.recv_timeout(t)?;
+        self.gauge.leave();
+        Ok(item)
+    }
+
+    fn recv(&self) -> Option<T> {
+        let item = self.rx.recv().ok()?;
+        self.gauge.leave();
+        Some(item)
+    }
+}
+
+/// 수신 스레드가 넘기는 원본 배치
+pub struct RawBatch {
+    pub source: Arc<Source>,
+    pub bytes: Vec<u8>,
+}
+
+/// 배치 1개분 기록거리: sink별 줄 묶음 (routing.rs의 sink 번호 순, sink 형식으로) + 세그먼트 블록 (세그먼트를 끄면 비어 있음)
+pub struct WriteItem {
+    pub lines: Vec<Vec<u8>>,
+    pub blocks: Vec<Block>,
+}
+
+/// 수신 스레드용 입구. 전송 방식별로 가득 찼을 때의 정책이 다릅니다.
+#[derive(Clone)]
+pub struct Ingest {
+    pub udp: QueueTx<RawBatch>,
+    pub tcp: QueueTx<RawBatch>,
+}
+
+impl Ingest {
+    /// UdpRx/TcpRx::spawn에 넘길 핸들러
+    pub fn handler(tx: QueueTx<RawBatch>) -> impl FnMut(&Arc<Source>, &[u8]) -> io::Result<()> + Clone + Send + 'static {
+        move |src, bytes| {
+            if tx.push(RawBatch { source: src.clone(), bytes: bytes.to_vec() }) {
+                Ok(())
+            } else {
+                Err(io::Error::new(io::ErrorKind::WouldBlock, "parse queue full"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+    /// 파싱 대기 배치 수 / 기록 대기 항목(배치 1개분 줄 묶음 + 세그먼트 블록) 수
+    pub parse_queue: usize,
+    pub write_queue: usize,
+    /// parse queue가 가득 찼을 때 (전송 방식별)
+    pub udp_on_full: OnFull,
+    pub tcp_on_full: OnFull,
+    /// write queue가 가득 찼을 때 (drop이면 파일에서 빠진 로그도 메모리에는 남음)
+    pub write_on_full: OnFull,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            parse_queue: 4096,
+            write_queue: 4096,
+            udp_on_full: OnFull::Drop,
+            tcp_on_full: OnFull::Block,
+            write_on_full: OnFull::Block,
+        }
+    }
+}
+
+/// parser/writer 스레드를 띄우고 수신 스레드용 입구를 돌려줍니다.
+pub fn start(cfg: &PipelineConfig, store: Arc<LogStore>, sinks: Sinks) -> io::Result<Ingest> {
+    let Sinks { router, formats, mut writers } = sinks;
+    let (raw_tx, raw_rx) = queue::<RawBatch>(cfg.parse_queue, cfg.udp_on_full, &PARSE_QUEUE);
+    let (item_tx, item_rx) = queue::<WriteItem>(cfg.write_queue, cfg.write_on_full, &WRITE_QUEUE);
+    let segments = store.segments().cloned();
+    let to_disk = segments.is_some();
+
+    thread::Builder::new().name("swiftlog-parser".into()).spawn(move || {
+        while let Some(raw) = raw_rx.recv() {
+            let mut lines = vec![Vec::new(); router.sinks()];
+            let res = parser::parse_and_store_and_write(&raw.bytes, Some(&raw.source), Some(&store), |row, log| {
+                let sink = router.route(log);
+                formats[sink].write_row(&mut lines[sink], row);
+                Ok(())
+            });
+            let Ok(stored) = res else { continue };
+            let blocks = if to_disk { segment::blocks(Some(&raw.source), &stored) } else { Vec::new() };
+            if lines.iter().any(|l| !l.is_empty()) || !blocks.is_empty() {
+                item_tx.push(WriteItem { lines, blocks });
+            }
+        }
+    })?;
+
+    thread::Builder::new().name("swiftlog-writer".into()).spawn(move || loop {
+        match item_rx.recv_timeout(WRITER_IDLE_TICK) {
+            Ok(item) => {
+                for (writer, lines) in writers.iter_mut().zip(&item.lines) {
+                    if lines.is_empty() { continue; }
+                    if let Err(e) = writer.write_block(lines) {
+                        diag!(Error, "writer", "write failed: {e}");
+                    }
+                }
+                if let Some(seg) = &segments {
+                    for b in &item.blocks {
+                        if let Err(e) = seg.append(b) {
+                            diag!(Error, "segment", "append failed: {e}");
+                        }
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        for writer in &mut writers {
+            if let Err(e) = writer.tick() {
+                diag!(Error, "writer", "flush/sync failed: {e}");
+            }
+            if let Err(e) = writer.rotate_if_needed() {
+                diag!(Error, "writer", "rotation failed: {e}");
+            }
+        }
+    })?;
+
+    Ok(Ingest { udp: raw_tx.with_policy(cfg.udp_on_full), tcp: raw_tx.with_policy(cfg.tcp_on_full) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swiftlog_proto::{BatchEncoder, LogLevel, MAX_VERSION};
+    use crate::config::WriterConfig;
+    use crate::format::OutputFormat;
+    use crate::routing::RouteConfig;
+    use crate::writer::FsyncPolicy;
+
+    #[test]
+    fn drop_policy_counts_and_block_policy_waits() {
+        let gauge: &'static QueueGauge = Box::leak(Box::new(QueueGauge::new()));
+        let (tx, rx) = queue::<u32>(2, OnFull::Drop, gauge);
+        assert!(tx.push(1) && tx.push(2));
+        assert!(!tx.push(3));
+        assert_eq!(gauge.stats(), QueueStats { depth: 2, high_water: 2, dropped: 1, capacity: 2 });
+
+        // block 핸들은 소비자가 자리를 낼 때까지 기다렸다가 넣음
+        let blocking = tx.with_policy(OnFull::Block);
+        let h = thread::spawn(move || blocking.push(4));
+        assert_eq!(rx.recv(), Some(1));
+        assert!(h.join().unwrap());
+        assert_eq!(rx.recv(), Some(2));
+        assert_eq!(rx.recv(), Some(4));
+        assert_eq!(gauge.stats().depth, 0);
+        assert_eq!(gauge.stats().dropped, 1);
+    }
+
+    #[test]
+    fn batches_flow_to_store_and_file() {
+        let dir = std::env::temp_dir().join(format!("swiftlog_pipeline_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let seg_cfg = segment::SegmentConfig { dir: dir.join("segments").to_str().unwrap().into(), ..Default::default() };
+        let seg = Arc::new(segment::SegmentStore::open(&seg_cfg).unwrap());
+        let store = Arc::new(LogStore::with_capacity(100, 2).with_segments(seg.clone()));
+        // Warn 이상은 warn.log로 (CSV)
+        let wcfg = WriterConfig {
+            dir: dir.to_str().unwrap().into(),
+            fsync: FsyncPolicy::Never,
+            routes: vec![RouteConfig {
+                name: "warn".into(), query: "level>=warn".into(), format: Some(OutputFormat::Csv), ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let ingest = start(&PipelineConfig::default(), store.clone(), Sinks::open(&wcfg).unwrap()).unwrap();
+
+        let mut enc = BatchEncoder::new(MAX_VERSION);
+        enc.push(1, LogLevel::Info, 7, b"one");
+        enc.push(2, LogLevel::Warn, 8, b"two");
+        let mut handler = Ingest::handler(ingest.tcp.clone());
+        let src = Arc::new(Source::peer("127.0.0.1:1".parse().unwrap()));
+        handler(&src, enc.finish()).unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap_or_default();
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            let (app, warn) = (read("app.log"), read("warn.log"));
+            let on_disk = seg.usage().records;
+            if store.len() == 2 && on_disk == 2 && app == "1\t2\t7\tone\n" && warn == "ts_ms,level,code,msg,fields\r\n2,Warn,8,two,\r\n" { break; }
+            assert!(std::time::Instant::now() < deadline, "timed out: {app:?} {warn:?}");
+            thread::sleep(Duration::from_millis(5));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
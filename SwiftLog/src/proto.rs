@@ -1,12 +1,5 @@
-// 공통 상수/유틸
-pub const MAGIC: u32 = 0x12345678; // 'SLG1' (LE)
-pub const VERSION: u32 = 1;
-
-// 파일 롤링 사이즈(필요시 환경변수/CLI로 확장 가능)
-pub const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+// 공통 상수/유틸 (와이어 포맷 상수는 swiftlog-proto 크레이트에 있습니다)
+// 운영 설정(바인드 주소, 롤링 크기, 백업 쿼터 등)은 config.rs로 옮겼습니다.
 
 // UDP 패킷 최대 수신 크기
 pub const UDP_BUF_SIZE: usize = 2048;
-
-pub const BACKUP_MAX_DIR_BYTES: u64 = 5 * 1024 * 1024 * 1024; // 5 GiB
-pub const BACKUP_MIN_FS_FREE_BYTES: u64 = 512 * 1024 * 1024;   // 512 MiB 
@@ -0,0 +1,177 @@
+// src/replay.rs
+// 시작 시 복구: 디스크에 남은 로그로 LogStore를 다시 채웁니다 (`--no-replay`로 끔).
+//
+// 세그먼트(segment.rs)에 기록이 있으면 거기서 최신 capacity건을 ID/송신자 그대로 되살립니다.
+// 없으면(세그먼트를 껐거나 세그먼트 도입 전 파일뿐) TSV 파일에서 읽습니다:
+//   <dir>/<base>.log (가장 최신) → <base>.log.<epoch>[.gz|.zst] (epoch 내림차순) 순으로 capacity건이 찰 때까지.
+// 어느 쪽이든 넣는 순서는 원래 순서(오래된 것부터)이고 ts_ms는 기록된 값 그대로입니다.
+// 파일 복구는 writer.format이 tsv일 때만 합니다 (다른 형식은 세그먼트로만 복구).
+// TSV에서 읽은 로그는 송신자 정보가 없고(None) ID를 새로 받습니다. 읽을 수 없는 줄은 건너뛰고 셉니다.
+// route 파일(routing.rs)은 읽지 않습니다 — 모든 레코드를 가진 세그먼트를 켜 두면 빠짐이 없습니다.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use swiftlog_proto::tsv;
+
+use crate::config::WriterConfig;
+use crate::format::OutputFormat;
+use crate::log_domain::{Log, LogLevel};
+use crate::log_store::{LogStore, SelectQuery};
+use crate::rotation;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// "segments" | "files" | "" (복구할 것 없음)
+    pub from: &'static str,
+    pub records: usize,
+    /// 읽은 TSV 파일 수
+    pub files: usize,
+    /// 형식이 맞지 않아 건너뛴 줄 (기록 도중 끊긴 마지막 줄 포함)
+    pub skipped_lines: usize,
+}
+
+pub fn replay(store: &LogStore, cfg: &WriterConfig) -> io::Result<ReplayReport> {
+    if let Some(seg) = store.segments().filter(|s| s.usage().records > 0) {
+        let cap = store.capacity();
+        let q = SelectQuery { latest: true, ..Default::default() };
+        let mut logs = seg.select(&q, 0..u64::MAX, |_| false, cap);
+        logs.sort_by_key(|l| std::cmp::Reverse(l.id));
+        logs.truncate(cap);
+        let records = logs.len();
+        for log in logs.into_iter().rev() {
+            store.restore(log);
+        }
+        return Ok(ReplayReport { from: "segments", records, ..Default::default() });
+    }
+    if cfg.format != OutputFormat::Tsv { return Ok(ReplayReport::default()); }
+    from_files(store, &cfg.dir, &cfg.base)
+}
+
+fn from_files(store: &LogStore, dir: &str, base: &str) -> io::Result<ReplayReport> {
+    let cap = store.capacity();
+    let mut report = ReplayReport::default();
+    // 최신 파일부터 읽어 파일 단위로 앞에 붙임
+    let mut chunks: Vec<Vec<Log>> = Vec::new();
+    let mut total = 0;
+    for path in log_files(Path::new(dir), base)?.into_iter().rev() {
+        if total >= cap { break; }
+        let data = rotation::read_log_file(&path)?;
+        report.files += 1;
+        let mut lines: Vec<&[u8]> = data.split(|b| *b == b'\n').collect();
+        // 마지막 조각: 파일이 줄바꿈으로 끝나면 빈 조각, 아니면 끊긴 줄
+        if lines.pop().is_some_and(|tail| !tail.is_empty()) { report.skipped_lines += 1; }
+        let mut logs = Vec::new();
+        for line in lines.iter().rev() {
+            if total >= cap { break; }
+            match parse_line(&String::from_utf8_lossy(line)) {
+                Some(log) => {
+                    logs.push(log);
+                    total += 1;
+                }
+                None => report.skipped_lines += 1,
+            }
+        }
+        logs.reverse();
+        chunks.push(logs);
+    }
+    for log in chunks.into_iter().rev().flatten() {
+        store.append(log);
+        report.records += 1;
+    }
+    if report.files > 0 { report.from = "files"; }
+    Ok(report)
+}
+
+/// 롤링된 파일(오래된 것부터, 압축 포함) 뒤에 `<base>.log`. 없는 파일은 빠짐
+pub fn log_files(dir: &Path, base: &str) -> io::Result<Vec<PathBuf>> {
+    let mut out: Vec<PathBuf> = rotation::rotated_files(dir, base)?.into_iter().map(|f| f.path).collect();
+    let current = dir.join(format!("{base}.log"));
+    if current.is_file() { out.push(current); }
+    Ok(out)
+}
+
+// swiftlog_proto::tsv 규칙 (이스케이프 이전 파일 포함). Log.msg는 String이라 UTF-8이 아닌 바이트는 대체 문자로
+fn parse_line(line: &str) -> Option<Log> {
+    let r = tsv::parse_line(line).ok()?;
+    let log = Log::new_unassigned(r.ts_ms, LogLevel::from_u8_lossy(r.level_raw), r.code, String::from_utf8_lossy(&r.msg))
+        .with_fields(r.fields);
+    log.validate().is_ok().then_some(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
+    use crate::log_domain::{FieldValue, Source};
+    use crate::segment::{self, SegmentConfig, SegmentStore};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let d = std::env::temp_dir().join(format!("swiftlog_replay_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&d);
+        fs::create_dir_all(&d).unwrap();
+        d
+    }
+
+    #[test]
+    fn newest_tsv_lines_fill_store_in_original_order() {
+        let dir = temp_dir("tsv");
+        fs::write(dir.join("app.log.100"), "1\t2\t1\toldest\n2\t2\t1\told\n").unwrap();
+        fs::write(dir.join("app.log.200"), "3\t3\t2\tmiddle\tuser_id=42 region=\"eu\"\nnot a log line\n").unwrap();
+        fs::write(dir.join("app.log"), "4\t4\t3\tnew\\ner\n5\t2\t4\ta\ttab\n6\t2\t5\ttorn").unwrap();
+        fs::write(dir.join("app.log.bak"), "7\t2\t1\tignored\n").unwrap();
+        // 롤링된 파일은 압축돼 있어도 읽음
+        let gz = rotation::RotationConfig { compress: rotation::Compression::Gzip, ..Default::default() };
+        rotation::sweep(&dir, "app", &gz, 0).unwrap();
+        assert!(dir.join("app.log.200.gz").exists());
+
+        let store = LogStore::with_capacity(4, 2);
+        let mut wcfg = WriterConfig { dir: dir.to_str().unwrap().into(), ..Default::default() };
+        let r = replay(&store, &wcfg).unwrap();
+        assert_eq!(r, ReplayReport { from: "files", records: 4, files: 3, skipped_lines: 2 });
+        // TSV가 아닌 형식으로 쓰고 있으면 파일은 읽지 않음
+        wcfg.format = OutputFormat::Jsonl;
+        assert_eq!(replay(&LogStore::with_capacity(4, 2), &wcfg).unwrap(), ReplayReport::default());
+
+        let rows = store.select(&SelectQuery::default());
+        let got: Vec<(u64, &str)> = rows.iter().map(|l| (l.ts_ms, l.msg.as_str())).collect();
+        assert_eq!(got, [(2, "old"), (3, "middle"), (4, "new\ner"), (5, "a\ttab")]);
+        assert_eq!(rows[1].field("user_id"), Some(&FieldValue::I64(42)));
+        assert_eq!(rows[2].level, LogLevel::Error);
+        // 복구 후 새 로그는 뒤에 붙음
+        assert!(store.append(Log::new_unassigned(9, LogLevel::Info, 1, "live")).id > rows[3].id);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn segments_restore_ids_and_sources() {
+        let dir = temp_dir("seg");
+        let cfg = SegmentConfig { dir: dir.join("segments").to_str().unwrap().into(), ..Default::default() };
+        let seg = Arc::new(SegmentStore::open(&cfg).unwrap());
+        let store = LogStore::with_capacity(100, 4).with_segments(seg.clone());
+        let src = Arc::new(Source::peer("10.1.2.3:4000".parse().unwrap()));
+        for i in 0..30u64 {
+            let log = store.append(Log::new_unassigned(1000 + i, LogLevel::Info, 1, format!("m{i}"))
+                .with_source(Some(src.clone())));
+            for b in segment::blocks(Some(&src), &[log]) { seg.append(&b).unwrap(); }
+        }
+        drop(store);
+        drop(seg);
+        // TSV가 있어도 세그먼트가 우선
+        fs::write(dir.join("app.log"), "1\t2\t1\tfrom tsv\n").unwrap();
+
+        let seg = Arc::new(SegmentStore::open(&cfg).unwrap());
+        let store = LogStore::with_capacity(8, 4).with_segments(seg);
+        let wcfg = WriterConfig { dir: dir.to_str().unwrap().into(), ..Default::default() };
+        let r = replay(&store, &wcfg).unwrap();
+        assert_eq!((r.from, r.records), ("segments", 8));
+        assert_eq!(store.len(), 8);
+        let rows = store.select(&SelectQuery { latest: true, limit: Some(8), ..Default::default() });
+        assert_eq!(rows.iter().map(|l| l.id).collect::<Vec<_>>(), (23..=30).rev().collect::<Vec<_>>());
+        assert_eq!(rows[0].ts_ms, 1029);
+        assert_eq!(rows[0].source.as_deref(), Some(&*src));
+        assert_eq!(store.append(Log::new_unassigned(0, LogLevel::Info, 1, "live")).id, 31);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,312 @@
+// src/rotation.rs
+// 로그 파일 롤링 정책 + 롤링된 파일 정리(압축, 보존)
+//
+//   롤링      : 크기(max_file_bytes) / 시각(every = hourly | daily, UTC 경계) / 둘 다 — 먼저 닿는 쪽
+//   파일 이름 : <base>.log.<롤링 시각 epoch 초>[.gz | .zst]
+//   정리      : writer 스레드가 아니라 정리 스레드(swiftlog-rotate)가 시작 시와 롤링 직후에 sweep
+//     1) compress가 켜져 있으면 압축 안 된 롤링 파일을 압축 (임시 파일에 쓴 뒤 rename, 원본 삭제)
+//     2) keep_files / max_age_secs / max_total_bytes 중 하나라도 넘으면 오래된 파일부터 삭제 (0 = 제한 없음)
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diag::diag;
+use crate::stats;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotateEvery {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl RotateEvery {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "never" => Some(RotateEvery::Never),
+            "hourly" => Some(RotateEvery::Hourly),
+            "daily" => Some(RotateEvery::Daily),
+            _ => None,
+        }
+    }
+
+    /// epoch 초가 속한 구간 번호 (UTC). 구간이 바뀌면 롤링
+    pub fn period(self, epoch_secs: u64) -> Option<u64> {
+        match self {
+            RotateEvery::Never => None,
+            RotateEvery::Hourly => Some(epoch_secs / 3600),
+            RotateEvery::Daily => Some(epoch_secs / 86_400),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(Compression::None),
+            "gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    fn ext(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RotationConfig {
+    /// 이 크기를 넘으면 롤링 (0 = 크기로는 안 함)
+    pub max_file_bytes: u64,
+    pub every: RotateEvery,
+    /// 롤링된 파일 보존 한도: 개수 / 나이(초) / 합계 바이트 (0 = 제한 없음)
+    pub keep_files: usize,
+    pub max_age_secs: u64,
+    pub max_total_bytes: u64,
+    pub compress: Compression,
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 64 * 1024 * 1024, // 64 MiB
+            every: RotateEvery::Never,
+            keep_files: 0,
+            max_age_secs: 0,
+            max_total_bytes: 0,
+            compress: Compression::None,
+        }
+    }
+}
+
+impl RotationConfig {
+    /// 정리 스레드가 할 일이 있는지 (압축 또는 보존 한도)
+    fn needs_sweep(&self) -> bool {
+        self.compress != Compression::None || self.keep_files > 0 || self.max_age_secs > 0 || self.max_total_bytes > 0
+    }
+}
+
+pub fn epoch_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+pub struct RotatedFile {
+    pub path: PathBuf,
+    /// 롤링 시각 (파일 이름의 epoch 초)
+    pub ts: u64,
+    pub len: u64,
+    pub compression: Compression,
+}
+
+/// `<base>.log.<epoch>[.gz|.zst]` 목록 (오래된 것부터)
+pub fn rotated_files(dir: &Path, base: &str) -> io::Result<Vec<RotatedFile>> {
+    let prefix = format!("{base}.log.");
+    let rd = match fs::read_dir(dir) {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut out: Vec<RotatedFile> = rd.filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            let rest = name.strip_prefix(&prefix)?;
+            let (ts, compression) = match rest.split_once('.') {
+                None => (rest, Compression::None),
+                Some((ts, "gz")) => (ts, Compression::Gzip),
+                Some((ts, "zst")) => (ts, Compression::Zstd),
+                Some(_) => return None,
+            };
+            let len = e.metadata().ok()?.len();
+            Some(RotatedFile { path: e.path(), ts: ts.parse().ok()?, len, compression })
+        })
+        .collect();
+    out.sort_by_key(|f| f.ts);
+    Ok(out)
+}
+
+/// 롤링된 파일 내용 (확장자에 따라 압축 해제)
+pub fn read_log_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => { flate2::read::GzDecoder::new(File::open(path)?).read_to_end(&mut out)?; }
+        Some("zst") => { zstd::stream::read::Decoder::new(File::open(path)?)?.read_to_end(&mut out)?; }
+        _ => { File::open(path)?.read_to_end(&mut out)?; }
+    }
+    Ok(out)
+}
+
+fn compress_file(path: &Path, c: Compression) -> io::Result<PathBuf> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(c.ext());
+    let dst = PathBuf::from(name);
+    let mut tmp = dst.clone().into_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut src = File::open(path)?;
+    let out = File::create(&tmp)?;
+    let file = match c {
+        Compression::Gzip => {
+            let mut enc = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+            io::copy(&mut src, &mut enc)?;
+            enc.finish()?
+        }
+        Compression::Zstd => {
+            let mut enc = zstd::stream::write::Encoder::new(out, 0)?;
+            io::copy(&mut src, &mut enc)?;
+            enc.finish()?
+        }
+        Compression::None => return Ok(path.to_path_buf()),
+    };
+    file.sync_all()?;
+    fs::rename(&tmp, &dst)?;
+    fs::remove_file(path)?;
+    Ok(dst)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SweepReport {
+    pub compressed: usize,
+    pub removed: usize,
+}
+
+/// 압축 후 보존 한도 적용. 정리 스레드가 부르며, 테스트에서는 직접 부릅니다.
+pub fn sweep(dir: &Path, base: &str, cfg: &RotationConfig, now: u64) -> io::Result<SweepReport> {
+    let mut report = SweepReport::default();
+    if cfg.compress != Compression::None {
+        for f in rotated_files(dir, base)?.into_iter().filter(|f| f.compression == Compression::None) {
+            match compress_file(&f.path, cfg.compress) {
+                Ok(dst) => {
+                    diag!(Debug, "rotate", "compressed {} -> {}", f.path.display(), dst.display());
+                    report.compressed += 1;
+                }
+                Err(e) => diag!(Warn, "rotate", "compress {} failed: {e}", f.path.display()),
+            }
+        }
+    }
+
+    let files = rotated_files(dir, base)?;
+    let mut total: u64 = files.iter().map(|f| f.len).sum();
+    let n = files.len();
+    for (i, f) in files.iter().enumerate() {
+        let too_many = cfg.keep_files > 0 && n - i > cfg.keep_files;
+        let too_old = cfg.max_age_secs > 0 && now.saturating_sub(f.ts) > cfg.max_age_secs;
+        let too_big = cfg.max_total_bytes > 0 && total > cfg.max_total_bytes;
+        if !(too_many || too_old || too_big) { break; }
+        match fs::remove_file(&f.path) {
+            Ok(()) => {
+                diag!(Info, "rotate", "retention: removed {} ({} bytes)", f.path.display(), f.len);
+                report.removed += 1;
+            }
+            Err(e) => diag!(Warn, "rotate", "retention: cannot remove {}: {e}", f.path.display()),
+        }
+        total -= f.len;
+    }
+    stats::add(&stats::WRITER_FILES_COMPRESSED, report.compressed as u64);
+    stats::add(&stats::WRITER_FILES_PRUNED, report.removed as u64);
+    Ok(report)
+}
+
+/// 정리 스레드 핸들. request()는 기다리지 않고, 밀린 요청은 한 번의 sweep으로 합쳐집니다.
+pub struct Maintainer {
+    tx: Sender<()>,
+}
+
+impl Maintainer {
+    /// 할 일이 없는 설정이면 None. 띄우자마자 한 번 sweep (이전 실행이 남긴 파일)
+    pub fn spawn(dir: &Path, base: &str, cfg: &RotationConfig) -> io::Result<Option<Self>> {
+        if !cfg.needs_sweep() { return Ok(None); }
+        let (tx, rx) = mpsc::channel::<()>();
+        let (dir, base, cfg) = (dir.to_path_buf(), base.to_string(), cfg.clone());
+        thread::Builder::new().name("swiftlog-rotate".into()).spawn(move || {
+            while rx.recv().is_ok() {
+                while rx.try_recv().is_ok() {}
+                if let Err(e) = sweep(&dir, &base, &cfg, epoch_secs()) {
+                    diag!(Warn, "rotate", "sweep of {} failed: {e}", dir.display());
+                }
+            }
+        })?;
+        let m = Self { tx };
+        m.request();
+        Ok(Some(m))
+    }
+
+    pub fn request(&self) {
+        let _ = self.tx.send(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_compresses_then_applies_count_age_and_bytes() {
+        // 기본값은 롤링된 파일을 지우지도 압축하지도 않음 (보존 한도는 운영자가 켬)
+        assert!(!RotationConfig::default().needs_sweep());
+        let dir = std::env::temp_dir().join(format!("swiftlog_rotation_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let line = "1\t2\t3\thello rotation\n".repeat(200);
+        for ts in [100, 200, 300, 400] {
+            fs::write(dir.join(format!("app.log.{ts}")), &line).unwrap();
+        }
+        fs::write(dir.join("app.log"), "current\n").unwrap();
+        fs::write(dir.join("other.log.150"), "x").unwrap();
+
+        // 압축 + 개수 3개 한도
+        let cfg = RotationConfig { keep_files: 3, compress: Compression::Zstd, ..Default::default() };
+        assert_eq!(sweep(&dir, "app", &cfg, 1000).unwrap(), SweepReport { compressed: 4, removed: 1 });
+        let files = rotated_files(&dir, "app").unwrap();
+        assert_eq!(files.iter().map(|f| f.ts).collect::<Vec<_>>(), [200, 300, 400]);
+        assert!(files.iter().all(|f| f.compression == Compression::Zstd && f.len < line.len() as u64));
+        assert_eq!(read_log_file(&files[0].path).unwrap(), line.as_bytes());
+
+        // 나이: now=1000에서 650초 넘은 것(200, 300) 삭제, gzip 섞여도 됨
+        fs::write(dir.join("app.log.500"), &line).unwrap();
+        let cfg = RotationConfig { max_age_secs: 650, compress: Compression::Gzip, ..Default::default() };
+        assert_eq!(sweep(&dir, "app", &cfg, 1000).unwrap(), SweepReport { compressed: 1, removed: 2 });
+        let files = rotated_files(&dir, "app").unwrap();
+        assert_eq!(files.iter().map(|f| (f.ts, f.compression)).collect::<Vec<_>>(),
+            [(400, Compression::Zstd), (500, Compression::Gzip)]);
+        assert_eq!(read_log_file(&files[1].path).unwrap(), line.as_bytes());
+
+        // 합계 바이트: 최신 것 하나만 들어가는 크기
+        let cfg = RotationConfig { max_total_bytes: files[1].len, ..Default::default() };
+        assert_eq!(sweep(&dir, "app", &cfg, 1000).unwrap(), SweepReport { compressed: 0, removed: 1 });
+        assert_eq!(fs::read_to_string(dir.join("app.log")).unwrap(), "current\n");
+        assert!(dir.join("other.log.150").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn periods_follow_utc_boundaries() {
+        assert_eq!(RotateEvery::Never.period(7200), None);
+        assert_eq!(RotateEvery::Hourly.period(3599), Some(0));
+        assert_eq!(RotateEvery::Hourly.period(3600), Some(1));
+        assert_eq!(RotateEvery::Daily.period(86_399), Some(0));
+        assert_eq!(RotateEvery::Daily.period(86_400), Some(1));
+    }
+}
@@ -0,0 +1,141 @@
+// src/routing.rs
+// 출력 라우팅: 레코드를 조건에 따라 이름 붙은 로그 파일(sink)로 나눠 씁니다.
+//
+//   [[writer.routes]]
+//   name = "errors"                 # <writer.dir>/errors.log
+//   query = "level>=error"          # SelectLog 조건 (SelectQuery::matches와 같은 판정)
+//   format = "jsonl"                # 생략하면 writer.format
+//   [writer.routes.rotation]        # sink마다 롤링/압축/보존 설정 (생략 시 기본값)
+//   every = "daily"
+//
+// 위에서부터 처음 맞는 route 하나로만 가고, 어디에도 맞지 않으면 기본 <writer.base>.log.
+// 저장소 검증에 실패한 레코드(로그가 없음)도 기본 파일로 갑니다.
+// 인메모리 저장소/세그먼트는 라우팅과 무관하게 모든 레코드를 가집니다.
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::WriterConfig;
+use crate::console_select::ConsoleSelect;
+use crate::format::{Formatter, OutputFormat};
+use crate::log_domain::Log;
+use crate::log_store::SelectQuery;
+use crate::rotation::RotationConfig;
+use crate::writer::LogWriter;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RouteConfig {
+    /// sink 이름 = 파일 이름 (<writer.dir>/<name>.log)
+    pub name: String,
+    /// SelectLog 문법의 조건 ("" = 전부). limit/offset/latest는 쓸 수 없음
+    pub query: String,
+    /// 출력 형식 (없으면 writer.format)
+    pub format: Option<OutputFormat>,
+    pub rotation: RotationConfig,
+}
+
+/// route 조건 목록. sink 번호: 0 = 기본 파일, i = routes[i - 1]
+pub struct Router {
+    routes: Vec<SelectQuery>,
+}
+
+impl Router {
+    pub fn new(routes: &[RouteConfig]) -> Result<Self, String> {
+        let routes = routes.iter().map(|r| {
+            let q = ConsoleSelect::parse(&r.query).map_err(|e| format!("writer.routes {:?}: bad query: {e}", r.name))?;
+            if q.limit.is_some() || q.offset > 0 || q.latest {
+                return Err(format!("writer.routes {:?}: limit/offset/latest do not apply to routes", r.name));
+            }
+            Ok(q)
+        }).collect::<Result<_, String>>()?;
+        Ok(Self { routes })
+    }
+
+    pub fn sinks(&self) -> usize { self.routes.len() + 1 }
+
+    /// 처음 맞는 route의 sink 번호 (없으면 0)
+    pub fn route(&self, log: Option<&Log>) -> usize {
+        let Some(log) = log else { return 0 };
+        self.routes.iter().position(|q| q.matches(log)).map_or(0, |i| i + 1)
+    }
+}
+
+/// 이름 규칙과 조건 문법 확인 (설정 검증용)
+pub fn validate(base: &str, routes: &[RouteConfig]) -> Result<(), String> {
+    let mut names = vec![base];
+    for r in routes {
+        let plain = !r.name.is_empty()
+            && r.name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !r.name.starts_with('.');
+        if !plain {
+            return Err(format!("writer.routes name {:?} must be a plain file name ([A-Za-z0-9._-])", r.name));
+        }
+        if names.contains(&r.name.as_str()) {
+            return Err(format!("writer.routes name {:?} is used twice (or equals writer.base)", r.name));
+        }
+        names.push(&r.name);
+    }
+    Router::new(routes).map(|_| ())
+}
+
+/// 라우터 + sink별 형식/writer (같은 순서). 파이프라인이 parser(라우팅, 형식)/writer 스레드로 나눠 가짐
+pub struct Sinks {
+    pub router: Router,
+    pub formats: Vec<Box<dyn Formatter>>,
+    pub writers: Vec<LogWriter>,
+}
+
+impl Sinks {
+    /// 기본 파일과 route별 파일을 모두 엽니다 (각자 형식/롤링 설정, fsync 정책은 공통)
+    pub fn open(cfg: &WriterConfig) -> io::Result<Self> {
+        let router = Router::new(&cfg.routes).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let (mut formats, mut writers) = (Vec::new(), Vec::new());
+        let sinks = std::iter::once((&cfg.base, &cfg.rotation, cfg.format))
+            .chain(cfg.routes.iter().map(|r| (&r.name, &r.rotation, r.format.unwrap_or(cfg.format))));
+        for (name, rot, format) in sinks {
+            let f = format.formatter();
+            writers.push(LogWriter::open(&cfg.dir, name, rot.max_file_bytes)?
+                .with_rotation(rot)?
+                .with_fsync(cfg.fsync)
+                .with_header(f.header()));
+            formats.push(f);
+        }
+        Ok(Self { router, formats, writers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_domain::LogLevel;
+
+    fn route(name: &str, query: &str) -> RouteConfig {
+        RouteConfig { name: name.into(), query: query.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn first_matching_route_wins_and_the_rest_go_to_default() {
+        let r = Router::new(&[route("errors", "level>=error"), route("net", "code=5000..5999"), route("all-warn", "level>=warn")])
+            .unwrap();
+        assert_eq!(r.sinks(), 4);
+        let log = |level, code| Log::new_unassigned(1, level, code, "m");
+        assert_eq!(r.route(Some(&log(LogLevel::Error, 5001))), 1);
+        assert_eq!(r.route(Some(&log(LogLevel::Info, 5001))), 2);
+        assert_eq!(r.route(Some(&log(LogLevel::Warn, 1))), 3);
+        assert_eq!(r.route(Some(&log(LogLevel::Info, 1))), 0);
+        assert_eq!(r.route(None), 0);
+    }
+
+    #[test]
+    fn bad_names_and_queries_are_rejected() {
+        assert!(validate("app", &[route("errors", "level>=error"), route("net_5xxx", "code_in=5001,5002")]).is_ok());
+        for bad in [route("", ""), route("../x", ""), route(".hidden", ""), route("app", ""), route("a b", "")] {
+            assert!(validate("app", std::slice::from_ref(&bad)).is_err(), "{bad:?}");
+        }
+        assert!(validate("app", &[route("x", ""), route("x", "")]).is_err());
+        assert!(validate("app", &[route("x", "level>=loud")]).is_err());
+        assert!(validate("app", &[route("x", "latest limit=10")]).is_err());
+    }
+}
@@ -0,0 +1,693 @@
+// src/segment.rs
+// 디스크 세그먼트 저장소: 재시작 후에도 SelectQuery로 조회할 수 있는 바이너리 로그
+//
+//   <dir>/seg-<첫 ID 20자리>.slseg   블록을 이어 붙인 데이터 파일
+//   <dir>/seg-<첫 ID 20자리>.slidx   봉인(seal)할 때 쓰는 요약 + 희소 인덱스 (없거나 깨졌으면 열 때 재구성)
+//
+// 세그먼트 = magic u32 ('SLSG') | version u16 | 0 u16 | Block*
+// Block     = body_len u32 | crc32c(body) u32 | body
+// body      = first_id u64 | zone | source | SLG1 v2 배치 (와이어 포맷 그대로, 필드 포함)
+// zone      = min_ts u64 | max_ts u64 | 레벨별 건수 u64 × 5
+// source    = peer_len u8 | peer ("" = 없음) [| has_identity u8 [| service | host | pid u32]]
+//
+// 블록 하나 = 파서가 받은 배치 하나(같은 송신자, 연속된 ID)입니다.
+// 인덱스는 약 `index_every` 레코드마다 항목 하나(시작 오프셋 + 그 구간의 zone)이고, 세그먼트 요약도 같은 zone입니다.
+// 타임스탬프는 송신측 시계라 정렬돼 있지 않으므로 이분 탐색 대신 구간마다 범위로 걸러냅니다.
+// ID는 LogStore ID를 그대로 쓰고 재시작 후에도 이어지므로(디스크 마지막 ID + 1부터),
+// 메모리와 디스크 결과는 ID로 합치고 메모리에 있는 ID는 디스크에서 건너뜁니다 (LogStore::select).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use swiftlog_proto::{crc32c, Batch, BatchEncoder, Identity, VERSION_V2};
+
+use crate::diag::diag;
+use crate::log_domain::{Log, LogLevel, Source};
+use crate::log_store::SelectQuery;
+
+const SEG_MAGIC: u32 = 0x4753_4C53; // 'SLSG' (LE)
+const IDX_MAGIC: u32 = 0x4953_4C53; // 'SLSI' (LE)
+const FORMAT_VERSION: u16 = 1;
+const SEG_HEADER_LEN: u64 = 8;
+// body_len u32 + crc32c u32
+const BLOCK_HEADER_LEN: usize = 8;
+// 길이 필드가 깨졌을 때 거대한 할당을 막는 상한
+const MAX_BLOCK_LEN: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SegmentConfig {
+    /// 끄면 TSV 파일만 남고, 조회는 메모리에 있는 것뿐
+    pub enabled: bool,
+    pub dir: String,
+    /// 활성 세그먼트가 이 크기를 넘으면 봉인하고 새 세그먼트 시작
+    pub max_segment_bytes: u64,
+    /// 희소 인덱스 간격 (레코드 수)
+    pub index_every: u64,
+    /// 세그먼트 전체 상한. 넘으면 오래된 세그먼트부터 삭제 (0 = 무제한)
+    pub max_total_bytes: u64,
+}
+
+impl Default for SegmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: "logs/segments".into(),
+            max_segment_bytes: 64 * 1024 * 1024,   // 64 MiB
+            index_every: 1024,
+            max_total_bytes: 4 * 1024 * 1024 * 1024, // 4 GiB
+        }
+    }
+}
+
+/// 레코드 묶음 요약: ts 범위 + 레벨별 건수. 블록 / 인덱스 구간 / 세그먼트 모두 같은 꼴
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Zone {
+    min_ts: u64,
+    max_ts: u64,
+    levels: [u64; 5],
+}
+
+impl Default for Zone {
+    fn default() -> Self { Self { min_ts: u64::MAX, max_ts: 0, levels: [0; 5] } }
+}
+
+const ZONE_LEN: usize = 8 + 8 + 8 * 5;
+
+impl Zone {
+    fn records(&self) -> u64 { self.levels.iter().sum() }
+
+    fn add(&mut self, ts: u64, level: LogLevel) {
+        self.min_ts = self.min_ts.min(ts);
+        self.max_ts = self.max_ts.max(ts);
+        self.levels[level as usize] += 1;
+    }
+
+    fn merge(&mut self, o: &Zone) {
+        self.min_ts = self.min_ts.min(o.min_ts);
+        self.max_ts = self.max_ts.max(o.max_ts);
+        for (a, b) in self.levels.iter_mut().zip(o.levels) { *a += b; }
+    }
+
+    /// q의 시간/레벨 조건에 맞는 레코드가 있을 수 있으면 true
+    fn may_match(&self, q: &SelectQuery) -> bool {
+        if let Some(since) = q.since_ms { if self.max_ts < since { return false; } }
+        if let Some(until) = q.until_ms { if self.min_ts > until { return false; } }
+        let lo = q.level_min.map_or(0, |l| l as usize);
+        let hi = q.level_max.map_or(4, |l| l as usize);
+        (lo..=hi).any(|i| self.levels[i] > 0)
+    }
+
+    fn put(&self, b: &mut Vec<u8>) {
+        b.extend_from_slice(&self.min_ts.to_le_bytes());
+        b.extend_from_slice(&self.max_ts.to_le_bytes());
+        for n in self.levels { b.extend_from_slice(&n.to_le_bytes()); }
+    }
+
+    fn get(r: &mut Reader<'_>) -> Option<Self> {
+        Some(Self { min_ts: r.u64()?, max_ts: r.u64()?, levels: [r.u64()?, r.u64()?, r.u64()?, r.u64()?, r.u64()?] })
+    }
+}
+
+/// 세그먼트에 그대로 붙일 블록. 파서 스레드에서 만들어 writer 스레드로 넘깁니다.
+pub struct Block {
+    first_id: u64,
+    zone: Zone,
+    bytes: Vec<u8>,
+}
+
+impl Block {
+    fn id_range(&self) -> Range<u64> { self.first_id..self.first_id + self.zone.records() }
+}
+
+/// 저장소에 들어간(ID가 매겨진) 로그들을 블록으로. ID가 끊기는 곳에서 블록을 나눕니다.
+pub fn blocks(source: Option<&Arc<Source>>, logs: &[Arc<Log>]) -> Vec<Block> {
+    let mut out = Vec::new();
+    let mut cur: Option<BlockBuilder> = None;
+    for log in logs {
+        let b = cur.get_or_insert_with(|| BlockBuilder::new(source));
+        if !b.push(log) {
+            out.extend(cur.take().and_then(BlockBuilder::finish));
+            let mut b = BlockBuilder::new(source);
+            b.push(log);
+            cur = Some(b);
+        }
+    }
+    out.extend(cur.and_then(BlockBuilder::finish));
+    out
+}
+
+struct BlockBuilder<'a> {
+    source: Option<&'a Arc<Source>>,
+    first_id: u64,
+    zone: Zone,
+    enc: BatchEncoder,
+}
+
+impl<'a> BlockBuilder<'a> {
+    fn new(source: Option<&'a Arc<Source>>) -> Self {
+        Self { source, first_id: 0, zone: Zone::default(), enc: BatchEncoder::with_capacity(VERSION_V2, 4096) }
+    }
+
+    // ID가 앞 레코드에 이어지지 않으면 넣지 않고 false
+    fn push(&mut self, log: &Log) -> bool {
+        let n = self.zone.records();
+        if n > 0 && log.id != self.first_id + n { return false; }
+        let fields: Vec<_> = log.fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())).collect();
+        if !self.enc.push_with_fields(log.ts_ms, log.level, log.code, log.msg.as_bytes(), &fields) {
+            return false;
+        }
+        if n == 0 { self.first_id = log.id; }
+        self.zone.add(log.ts_ms, log.level);
+        true
+    }
+
+    fn finish(mut self) -> Option<Block> {
+        if self.zone.records() == 0 { return None; }
+        let batch = self.enc.finish();
+        let mut body = Vec::with_capacity(8 + ZONE_LEN + 64 + batch.len());
+        body.extend_from_slice(&self.first_id.to_le_bytes());
+        self.zone.put(&mut body);
+        put_source(&mut body, self.source.map(|s| &**s));
+        body.extend_from_slice(batch);
+
+        let mut bytes = Vec::with_capacity(BLOCK_HEADER_LEN + body.len());
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&crc32c(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Some(Block { first_id: self.first_id, zone: self.zone, bytes })
+    }
+}
+
+fn put_short(b: &mut Vec<u8>, s: &str) {
+    let mut end = s.len().min(u8::MAX as usize);
+    while !s.is_char_boundary(end) { end -= 1; }
+    b.push(end as u8);
+    b.extend_from_slice(&s.as_bytes()[..end]);
+}
+
+fn put_source(b: &mut Vec<u8>, s: Option<&Source>) {
+    let Some(s) = s else {
+        b.push(0);
+        return;
+    };
+    put_short(b, &s.peer.to_string());
+    match &s.identity {
+        None => b.push(0),
+        Some(i) => {
+            b.push(1);
+            put_short(b, &i.service);
+            put_short(b, &i.host);
+            b.extend_from_slice(&i.pid.to_le_bytes());
+        }
+    }
+}
+
+fn get_source(r: &mut Reader<'_>) -> Option<Option<Arc<Source>>> {
+    let peer = r.short_str()?;
+    if peer.is_empty() { return Some(None); }
+    let peer = peer.parse().ok()?;
+    let identity = match r.u8()? {
+        0 => None,
+        _ => Some(Identity { service: r.short_str()?.to_string(), host: r.short_str()?.to_string(), pid: r.u32()? }),
+    };
+    Some(Some(Arc::new(Source { peer, identity })))
+}
+
+struct Reader<'a> {
+    b: &'a [u8],
+    p: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(b: &'a [u8]) -> Self { Self { b, p: 0 } }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let s = self.b.get(self.p..self.p.checked_add(n)?)?;
+        self.p += n;
+        Some(s)
+    }
+
+    fn u8(&mut self) -> Option<u8> { Some(self.take(1)?[0]) }
+    fn u16(&mut self) -> Option<u16> { Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?)) }
+    fn u32(&mut self) -> Option<u32> { Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?)) }
+    fn u64(&mut self) -> Option<u64> { Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?)) }
+
+    fn short_str(&mut self) -> Option<&'a str> {
+        let n = self.u8()? as usize;
+        std::str::from_utf8(self.take(n)?).ok()
+    }
+
+    fn rest(&self) -> &'a [u8] { &self.b[self.p..] }
+}
+
+// buf 앞의 블록 하나 → (body, 헤더 포함 길이). 잘렸거나 CRC가 틀리면 None
+fn split_block(buf: &[u8]) -> Option<(&[u8], usize)> {
+    let mut r = Reader::new(buf);
+    let len = r.u32()? as usize;
+    let crc = r.u32()?;
+    if len > MAX_BLOCK_LEN { return None; }
+    let body = r.take(len)?;
+    (crc32c(body) == crc).then_some((body, BLOCK_HEADER_LEN + len))
+}
+
+struct BlockView<'a> {
+    first_id: u64,
+    zone: Zone,
+    source: Option<Arc<Source>>,
+    batch: &'a [u8],
+}
+
+impl<'a> BlockView<'a> {
+    fn parse(body: &'a [u8]) -> Option<Self> {
+        let mut r = Reader::new(body);
+        let first_id = r.u64()?;
+        let zone = Zone::get(&mut r)?;
+        let source = get_source(&mut r)?;
+        Some(Self { first_id, zone, source, batch: r.rest() })
+    }
+
+    fn id_range(&self) -> Range<u64> { self.first_id..self.first_id + self.zone.records() }
+
+    fn collect(&self, q: &SelectQuery, ids: &Range<u64>, skip: &impl Fn(u64) -> bool, out: &mut Vec<Arc<Log>>) {
+        let Ok(batch) = Batch::parse(self.batch) else { return };
+        for (id, rec) in (self.first_id..).zip(batch.records()) {
+            if !ids.contains(&id) || skip(id) { continue; }
+            // 문자열을 만들기 전에 싼 조건부터
+            if q.since_ms.is_some_and(|s| rec.ts_ms < s) || q.until_ms.is_some_and(|u| rec.ts_ms > u) { continue; }
+            let fields = rec.fields.iter().map(|(k, v)| (k.to_string(), v.to_owned())).collect();
+            let mut log = Log::new_unassigned(rec.ts_ms, rec.level(), rec.code, rec.msg_lossy())
+                .with_fields(fields)
+                .with_source(self.source.clone());
+            log.id = id;
+            if q.matches(&log) { out.push(Arc::new(log)); }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct IndexEntry {
+    offset: u64,
+    zone: Zone,
+}
+
+#[derive(Clone, Debug)]
+struct SegmentMeta {
+    path: PathBuf,
+    first_id: u64,
+    last_id: u64,
+    /// 유효 바이트 (헤더 포함). 읽기는 여기까지만
+    len: u64,
+    zone: Zone,
+    index: Vec<IndexEntry>,
+}
+
+impl SegmentMeta {
+    fn new(path: PathBuf, first_id: u64) -> Self {
+        Self { path, first_id, last_id: first_id, len: SEG_HEADER_LEN, zone: Zone::default(), index: Vec::new() }
+    }
+
+    fn add_block(&mut self, offset: u64, len: u64, zone: &Zone, ids: Range<u64>, index_every: u64) {
+        self.len = offset + len;
+        self.last_id = ids.end - 1;
+        self.zone.merge(zone);
+        match self.index.last_mut() {
+            Some(e) if e.zone.records() < index_every => e.zone.merge(zone),
+            _ => self.index.push(IndexEntry { offset, zone: *zone }),
+        }
+    }
+
+    fn index_path(&self) -> PathBuf { self.path.with_extension("slidx") }
+
+    fn write_index(&self) -> io::Result<()> {
+        let mut b = Vec::with_capacity(64 + self.index.len() * (8 + ZONE_LEN));
+        b.extend_from_slice(&IDX_MAGIC.to_le_bytes());
+        b.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        b.extend_from_slice(&0u16.to_le_bytes());
+        b.extend_from_slice(&self.len.to_le_bytes());
+        b.extend_from_slice(&self.last_id.to_le_bytes());
+        self.zone.put(&mut b);
+        b.extend_from_slice(&(self.index.len() as u32).to_le_bytes());
+        for e in &self.index {
+            b.extend_from_slice(&e.offset.to_le_bytes());
+            e.zone.put(&mut b);
+        }
+        b.extend_from_slice(&crc32c(&b).to_le_bytes());
+        let tmp = self.path.with_extension("slidx.tmp");
+        fs::write(&tmp, &b)?;
+        fs::rename(&tmp, self.index_path())
+    }
+
+    // 인덱스 파일이 온전하고 데이터 파일 길이와 맞을 때만 Some
+    fn load_index(path: &Path, first_id: u64) -> Option<Self> {
+        let mut m = Self::new(path.to_path_buf(), first_id);
+        let b = fs::read(m.index_path()).ok()?;
+        let (body, crc) = b.split_at(b.len().checked_sub(4)?);
+        if crc32c(body).to_le_bytes() != crc { return None; }
+        let mut r = Reader::new(body);
+        if r.u32()? != IDX_MAGIC || r.u16()? != FORMAT_VERSION { return None; }
+        r.u16()?;
+        m.len = r.u64()?;
+        m.last_id = r.u64()?;
+        m.zone = Zone::get(&mut r)?;
+        for _ in 0..r.u32()? {
+            m.index.push(IndexEntry { offset: r.u64()?, zone: Zone::get(&mut r)? });
+        }
+        (fs::metadata(path).ok()?.len() == m.len).then_some(m)
+    }
+
+    // 데이터 파일을 처음부터 읽어 요약/인덱스 재구성. 끝의 잘린 블록(기록 중 종료)은 잘라냄
+    fn rebuild(path: &Path, first_id: u64, index_every: u64) -> io::Result<Option<Self>> {
+        let data = fs::read(path)?;
+        let mut m = Self::new(path.to_path_buf(), first_id);
+        // 헤더도 못 쓰고 끝난 빈 세그먼트
+        if data.len() < SEG_HEADER_LEN as usize { return Ok(None); }
+        let mut r = Reader::new(&data);
+        if r.u32() != Some(SEG_MAGIC) || r.u16() != Some(FORMAT_VERSION) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a segment file"));
+        }
+        let mut offset = SEG_HEADER_LEN as usize;
+        while let Some((body, used)) = split_block(&data[offset..]) {
+            let Some(view) = BlockView::parse(body) else { break };
+            m.add_block(offset as u64, used as u64, &view.zone, view.id_range(), index_every);
+            offset += used;
+        }
+        if offset < data.len() {
+            diag!(Warn, "segment", "{}: dropping {} byte(s) of torn tail", path.display(), data.len() - offset);
+            OpenOptions::new().write(true).open(path)?.set_len(offset as u64)?;
+        }
+        Ok((m.zone.records() > 0).then_some(m))
+    }
+}
+
+struct Active {
+    file: File,
+    meta: SegmentMeta,
+}
+
+struct State {
+    sealed: Vec<Arc<SegmentMeta>>,
+    active: Option<Active>,
+}
+
+/// 세그먼트 사용량 (Stats / metrics)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SegmentUsage {
+    pub segments: u64,
+    pub bytes: u64,
+    pub records: u64,
+    /// 조회로 디코드한 블록 수 (누적). 인덱스가 거른 블록은 세지 않음
+    pub blocks_read: u64,
+}
+
+pub struct SegmentStore {
+    dir: PathBuf,
+    max_segment_bytes: u64,
+    index_every: u64,
+    max_total_bytes: u64,
+    next_id: u64,
+    state: Mutex<State>,
+    blocks_read: AtomicU64,
+}
+
+impl SegmentStore {
+    /// 디렉터리의 기존 세그먼트를 모두 봉인된 것으로 열고, 다음 append는 새 세그먼트에 씁니다.
+    pub fn open(cfg: &SegmentConfig) -> io::Result<Self> {
+        let dir = PathBuf::from(&cfg.dir);
+        fs::create_dir_all(&dir)?;
+        let mut found: Vec<(u64, PathBuf)> = fs::read_dir(&dir)?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().into_string().ok()?;
+                let id = name.strip_prefix("seg-")?.strip_suffix(".slseg")?.parse().ok()?;
+                Some((id, e.path()))
+            })
+            .collect();
+        found.sort();
+
+        let mut sealed = Vec::with_capacity(found.len());
+        for (first_id, path) in found {
+            if let Some(m) = SegmentMeta::load_index(&path, first_id) {
+                sealed.push(Arc::new(m));
+                continue;
+            }
+            match SegmentMeta::rebuild(&path, first_id, cfg.index_every.max(1)) {
+                Ok(Some(m)) => {
+                    m.write_index()?;
+                    sealed.push(Arc::new(m));
+                }
+                Ok(None) => {
+                    let _ = fs::remove_file(&path);
+                    let _ = fs::remove_file(path.with_extension("slidx"));
+                }
+                // 남의 파일이거나 헤더가 깨진 파일은 건드리지 않고 건너뜀
+                Err(e) => diag!(Warn, "segment", "skipping {}: {e}", path.display()),
+            }
+        }
+        let next_id = sealed.iter().map(|m| m.last_id + 1).max().unwrap_or(1);
+        Ok(Self {
+            dir,
+            max_segment_bytes: cfg.max_segment_bytes,
+            index_every: cfg.index_every.max(1),
+            max_total_bytes: cfg.max_total_bytes,
+            next_id,
+            state: Mutex::new(State { sealed, active: None }),
+            blocks_read: AtomicU64::new(0),
+        })
+    }
+
+    /// 디스크에 있는 마지막 ID + 1 (열 때 기준). LogStore가 ID를 여기서부터 이어 매깁니다.
+    pub fn next_id(&self) -> u64 { self.next_id }
+
+    pub fn usage(&self) -> SegmentUsage {
+        let st = self.state.lock().unwrap();
+        let metas = st.sealed.iter().map(|m| &**m).chain(st.active.as_ref().map(|a| &a.meta));
+        let mut u = SegmentUsage { blocks_read: self.blocks_read.load(Ordering::Relaxed), ..Default::default() };
+        for m in metas {
+            u.segments += 1;
+            u.bytes += m.len;
+            u.records += m.zone.records();
+        }
+        u
+    }
+
+    /// 블록 1개 추가 (writer 스레드). 세그먼트가 차면 봉인하고 보존 한도를 적용합니다.
+    pub fn append(&self, block: &Block) -> io::Result<()> {
+        let mut st = self.state.lock().unwrap();
+        let a = match &mut st.active {
+            Some(a) => a,
+            None => st.active.insert(self.create(block.first_id)?),
+        };
+        let offset = a.meta.len;
+        if let Err(e) = a.file.write_all(&block.bytes) {
+            // 반쯤 쓴 블록을 남기지 않도록 되돌림 (다음 블록이 그 뒤에 붙지 않게)
+            let _ = a.file.set_len(offset).and_then(|_| a.file.seek(SeekFrom::Start(offset)).map(|_| ()));
+            return Err(e);
+        }
+        a.meta.add_block(offset, block.bytes.len() as u64, &block.zone, block.id_range(), self.index_every);
+        if a.meta.len >= self.max_segment_bytes {
+            self.seal(&mut st)?;
+        }
+        Ok(())
+    }
+
+    fn create(&self, first_id: u64) -> io::Result<Active> {
+        let path = self.dir.join(format!("seg-{first_id:020}.slseg"));
+        let mut file = OpenOptions::new().create(true).write(true).truncate(true).open(&path)?;
+        let mut header = Vec::with_capacity(SEG_HEADER_LEN as usize);
+        header.extend_from_slice(&SEG_MAGIC.to_le_bytes());
+        header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        file.write_all(&header)?;
+        Ok(Active { file, meta: SegmentMeta::new(path, first_id) })
+    }
+
+    fn seal(&self, st: &mut State) -> io::Result<()> {
+        let Some(a) = st.active.take() else { return Ok(()) };
+        a.file.sync_data()?;
+        a.meta.write_index()?;
+        diag!(Debug, "segment", "sealed {} ({} records, {} bytes)", a.meta.path.display(), a.meta.zone.records(), a.meta.len);
+        st.sealed.push(Arc::new(a.meta));
+
+        // 보존 한도: 오래된 봉인 세그먼트부터 삭제
+        if self.max_total_bytes > 0 {
+            let mut total: u64 = st.sealed.iter().map(|m| m.len).sum();
+            while total > self.max_total_bytes && !st.sealed.is_empty() {
+                let old = st.sealed.remove(0);
+                total -= old.len;
+                let _ = fs::remove_file(old.index_path());
+                match fs::remove_file(&old.path) {
+                    Ok(()) => diag!(Info, "segment", "retention: removed {} ({} bytes)", old.path.display(), old.len),
+                    Err(e) => diag!(Warn, "segment", "retention: cannot remove {}: {e}", old.path.display()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 조회 시점의 세그먼트 목록 (ID 오름차순). 활성 세그먼트는 그때까지 쓴 길이까지만
+    fn snapshot(&self) -> Vec<Arc<SegmentMeta>> {
+        let st = self.state.lock().unwrap();
+        let mut v = st.sealed.clone();
+        if let Some(a) = &st.active { v.push(Arc::new(a.meta.clone())); }
+        v
+    }
+
+    /// `ids` 구간에서 q에 맞는 디스크 로그 (순서 보장 없음). `skip(id)`가 true인 것(메모리에 있는 것)은 뺍니다.
+    /// 세그먼트 단위로 읽다가 `enough`건이 모이면 멈춥니다 — q.latest면 최신 세그먼트부터.
+    pub fn select(&self, q: &SelectQuery, ids: Range<u64>, skip: impl Fn(u64) -> bool, enough: usize) -> Vec<Arc<Log>> {
+        let mut segs = self.snapshot();
+        if q.latest { segs.reverse(); }
+        let mut out = Vec::new();
+        for seg in segs {
+            if out.len() >= enough { break; }
+            if seg.last_id < ids.start || seg.first_id >= ids.end || !seg.zone.may_match(q) { continue; }
+            if let Err(e) = self.scan(&seg, q, &ids, &skip, &mut out) {
+                diag!(Warn, "segment", "read {} failed: {e}", seg.path.display());
+            }
+        }
+        out
+    }
+
+    fn scan(
+        &self,
+        seg: &SegmentMeta,
+        q: &SelectQuery,
+        ids: &Range<u64>,
+        skip: &impl Fn(u64) -> bool,
+        out: &mut Vec<Arc<Log>>,
+    ) -> io::Result<()> {
+        // 보존 한도로 막 지워진 세그먼트
+        let mut f = match File::open(&seg.path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            r => r?,
+        };
+        let mut buf = Vec::new();
+        for (i, e) in seg.index.iter().enumerate() {
+            if !e.zone.may_match(q) { continue; }
+            let end = seg.index.get(i + 1).map_or(seg.len, |n| n.offset);
+            buf.resize((end - e.offset) as usize, 0);
+            f.seek(SeekFrom::Start(e.offset))?;
+            f.read_exact(&mut buf)?;
+            let mut p = 0;
+            while let Some((body, used)) = split_block(&buf[p..]) {
+                p += used;
+                let Some(view) = BlockView::parse(body) else { break };
+                let r = view.id_range();
+                if r.end <= ids.start || r.start >= ids.end || !view.zone.may_match(q) { continue; }
+                self.blocks_read.fetch_add(1, Ordering::Relaxed);
+                view.collect(q, ids, skip, out);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let d = std::env::temp_dir().join(format!("swiftlog_segment_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&d);
+        d
+    }
+
+    fn cfg(dir: &Path, max_segment_bytes: u64) -> SegmentConfig {
+        SegmentConfig { dir: dir.to_str().unwrap().into(), max_segment_bytes, index_every: 8, ..Default::default() }
+    }
+
+    // ID 1..=n, ts = ID × 1000, 레벨은 순환. 10건씩 한 블록
+    fn fill(store: &SegmentStore, ids: Range<u64>) {
+        let src = Arc::new(Source {
+            peer: "10.0.0.1:5000".parse().unwrap(),
+            identity: Some(Identity { service: "api".into(), host: "web-1".into(), pid: 7 }),
+        });
+        let logs: Vec<Arc<Log>> = ids.map(|id| {
+            let mut l = Log::new_unassigned(id * 1000, LogLevel::ALL[id as usize % 5], id as u16, format!("m{id}"))
+                .with_fields(vec![("n".into(), swiftlog_proto::FieldValue::I64(id as i64))]);
+            l.id = id;
+            Arc::new(l)
+        }).collect();
+        for chunk in logs.chunks(10) {
+            for b in blocks(Some(&src), chunk) { store.append(&b).unwrap(); }
+        }
+    }
+
+    fn ids(v: &[Arc<Log>]) -> Vec<u64> {
+        let mut v: Vec<u64> = v.iter().map(|l| l.id).collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn time_range_uses_index_and_survives_reopen() {
+        let dir = temp_dir("reopen");
+        let store = SegmentStore::open(&cfg(&dir, 4096)).unwrap();
+        fill(&store, 1..301);
+        let u = store.usage();
+        assert!(u.segments >= 3, "{u:?}");
+        assert_eq!(u.records, 300);
+
+        let q = SelectQuery { since_ms: Some(100_000), until_ms: Some(119_000), ..Default::default() };
+        let rows = store.select(&q, 0..u64::MAX, |_| false, usize::MAX);
+        assert_eq!(ids(&rows), (100..120).collect::<Vec<_>>());
+        let r = rows.iter().find(|l| l.id == 105).unwrap();
+        assert_eq!((r.msg.as_str(), r.code, r.level), ("m105", 105, LogLevel::ALL[0]));
+        assert_eq!(r.source.as_ref().unwrap().service(), Some("api"));
+        assert_eq!(r.field("n"), Some(&swiftlog_proto::FieldValue::I64(105)));
+        // 30블록 중 시간 구간이 겹치는 3블록만 디코드
+        assert_eq!(store.usage().blocks_read, 3);
+
+        // 레벨 요약, ID 구간, 건너뛰기
+        let q = SelectQuery { level_min: Some(LogLevel::Error), ..Default::default() };
+        assert_eq!(store.select(&q, 0..u64::MAX, |_| false, usize::MAX).len(), 60);
+        let rows = store.select(&SelectQuery::default(), 290..u64::MAX, |id| id % 2 == 0, usize::MAX);
+        assert_eq!(ids(&rows), vec![291, 293, 295, 297, 299]);
+        drop(store);
+
+        // 다시 열면 활성 세그먼트도 봉인되고 ID는 이어서
+        let store = SegmentStore::open(&cfg(&dir, 4096)).unwrap();
+        assert_eq!(store.next_id(), 301);
+        assert_eq!(store.usage().records, 300);
+        let q = SelectQuery { since_ms: Some(250_000), ..Default::default() };
+        assert_eq!(store.select(&q, 0..u64::MAX, |_| false, usize::MAX).len(), 51);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn torn_tail_is_cut_and_retention_drops_oldest() {
+        let dir = temp_dir("torn");
+        let store = SegmentStore::open(&cfg(&dir, u64::MAX)).unwrap();
+        fill(&store, 1..21);
+        drop(store);
+
+        // 기록 도중 죽은 것처럼 블록 반쪽을 덧붙임
+        let path = dir.join(format!("seg-{:020}.slseg", 1));
+        let good_len = fs::metadata(&path).unwrap().len();
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        f.write_all(&[40, 0, 0, 0, 1, 2, 3, 4, 5]).unwrap();
+        drop(f);
+
+        let store = SegmentStore::open(&cfg(&dir, u64::MAX)).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), good_len);
+        assert_eq!(store.next_id(), 21);
+        assert_eq!(store.select(&SelectQuery::default(), 0..u64::MAX, |_| false, usize::MAX).len(), 20);
+        drop(store);
+
+        let mut c = cfg(&dir, 2048);
+        c.max_total_bytes = 6000;
+        let store = SegmentStore::open(&c).unwrap();
+        fill(&store, 21..201);
+        let u = store.usage();
+        assert!(u.bytes <= 6000 + 2048 + 1024, "{u:?}");
+        let rows = store.select(&SelectQuery::default(), 0..u64::MAX, |_| false, usize::MAX);
+        assert!(!ids(&rows).contains(&1) && ids(&rows).contains(&200));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,301 @@
+// src/stats.rs
+// 수집 통계: 수신 → 디코드 → 저장/기록 각 단계에서 몇 건이 들어오고 어디서 빠졌는지
+//
+// 카운터는 모두 프로세스 전역 AtomicU64(Relaxed)라 핫 패스 비용은 fetch_add 하나입니다.
+// 콘솔 `Stats`는 표로, HTTP `/stats`(http.rs)는 같은 스냅샷을 JSON으로 내보냅니다.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use serde::Serialize;
+use swiftlog_proto::DecodeError;
+
+use crate::log_domain::LogLevel;
+use crate::log_store::LogStore;
+use crate::pipeline;
+
+// ── 수신 ──
+pub static UDP_DATAGRAMS: AtomicU64 = AtomicU64::new(0);
+pub static TCP_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+pub static TCP_FRAMES: AtomicU64 = AtomicU64::new(0);
+/// 지금 열려 있는 TCP 연결 (게이지)
+pub static TCP_CONNECTIONS_OPEN: AtomicU64 = AtomicU64::new(0);
+/// net.max_tcp_connections에 걸려 바로 닫은 연결
+pub static TCP_CONNECTIONS_REFUSED: AtomicU64 = AtomicU64::new(0);
+/// 길이 프리픽스가 한도를 넘어 연결을 끊은 프레임
+pub static TCP_FRAMES_OVERSIZED: AtomicU64 = AtomicU64::new(0);
+pub static BYTES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+/// 지금 열려 있는 HTTP API 연결 (게이지)
+pub static HTTP_CONNECTIONS_OPEN: AtomicU64 = AtomicU64::new(0);
+/// net.max_http_connections에 걸려 503으로 닫은 연결
+pub static HTTP_CONNECTIONS_REFUSED: AtomicU64 = AtomicU64::new(0);
+// ── 디코드 ──
+pub static BATCHES_ACCEPTED: AtomicU64 = AtomicU64::new(0);
+pub static RECORDS_PARSED: AtomicU64 = AtomicU64::new(0);
+/// Log::validate() 한도(메시지 길이 등)를 넘어 저장소에 넣지 않은 레코드
+pub static RECORDS_INVALID: AtomicU64 = AtomicU64::new(0);
+/// 파싱된 레코드의 레벨별 건수 (LogLevel as usize 순)
+static RECORDS_BY_LEVEL: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+static REJECT_BAD_MAGIC: AtomicU64 = AtomicU64::new(0);
+static REJECT_BAD_VERSION: AtomicU64 = AtomicU64::new(0);
+static REJECT_TRUNCATED: AtomicU64 = AtomicU64::new(0);
+static REJECT_CHECKSUM: AtomicU64 = AtomicU64::new(0);
+static REJECT_MALFORMED: AtomicU64 = AtomicU64::new(0);
+// ── 기록 ──
+pub static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+/// 열려 있는 로그 파일(기본 + route sink) 크기 합계 (롤링된 파일은 빠짐)
+pub static WRITER_FILE_BYTES: AtomicU64 = AtomicU64::new(0);
+pub static WRITER_ROTATIONS: AtomicU64 = AtomicU64::new(0);
+/// fsync 정책에 따른 디스크 동기화 횟수
+pub static WRITER_FSYNCS: AtomicU64 = AtomicU64::new(0);
+pub static WRITER_FILES_COMPRESSED: AtomicU64 = AtomicU64::new(0);
+pub static WRITER_FILES_PRUNED: AtomicU64 = AtomicU64::new(0);
+// ── 백업 (콘솔 BackupLog + 자동 백업) ──
+pub static BACKUPS_OK: AtomicU64 = AtomicU64::new(0);
+pub static BACKUPS_FAILED: AtomicU64 = AtomicU64::new(0);
+/// 지금 쓰고 있는 백업 수 (게이지)
+pub static BACKUPS_RUNNING: AtomicU64 = AtomicU64::new(0);
+// ── 실시간 구독 ──
+/// 지금 열려 있는 구독 연결 (게이지, Subscribe를 받기 전 연결 포함)
+pub static SUBSCRIBE_CONNECTIONS_OPEN: AtomicU64 = AtomicU64::new(0);
+/// subscribe.max_subscribers에 걸려 Error 후 닫은 연결
+pub static SUBSCRIBERS_REFUSED: AtomicU64 = AtomicU64::new(0);
+/// 구독자 버퍼가 가득 차 건너뛴 레코드 (구독자별 합)
+pub static SUBSCRIBER_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+#[inline]
+pub fn add(counter: &AtomicU64, n: u64) {
+    counter.fetch_add(n, Ordering::Relaxed);
+}
+
+/// 가동 시간 기준점. main 시작 시 한 번 (안 부르면 첫 스냅샷 시점부터)
+pub fn mark_start() {
+    STARTED.get_or_init(Instant::now);
+}
+
+#[inline]
+pub fn note_level(level: LogLevel) {
+    add(&RECORDS_BY_LEVEL[level as usize], 1);
+}
+
+/// 배치 거부 사유별 집계
+pub fn note_reject(e: &DecodeError) {
+    let c = match e {
+        DecodeError::BadMagic(_) => &REJECT_BAD_MAGIC,
+        DecodeError::UnsupportedVersion(_) | DecodeError::UnsupportedFlags(_) => &REJECT_BAD_VERSION,
+        DecodeError::TooShort { .. } | DecodeError::Truncated { .. } => &REJECT_TRUNCATED,
+        DecodeError::ChecksumMismatch { .. } => &REJECT_CHECKSUM,
+        DecodeError::CountMismatch { .. } | DecodeError::InvalidField { .. } => &REJECT_MALFORMED,
+    };
+    add(c, 1);
+}
+
+/// 한 시점의 통계. 필드 이름이 그대로 JSON 키가 됩니다.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Snapshot {
+    pub uptime_secs: u64,
+    pub udp_datagrams: u64,
+    pub tcp_connections: u64,
+    pub tcp_frames: u64,
+    pub tcp_connections_open: u64,
+    pub tcp_connections_refused: u64,
+    pub tcp_frames_oversized: u64,
+    pub bytes_received: u64,
+    pub http_connections_open: u64,
+    pub http_connections_refused: u64,
+    pub batches_accepted: u64,
+    pub batches_rejected: u64,
+    pub rejected_bad_magic: u64,
+    pub rejected_bad_version: u64,
+    pub rejected_truncated: u64,
+    pub rejected_checksum: u64,
+    pub rejected_malformed: u64,
+    pub records_parsed: u64,
+    pub records_invalid: u64,
+    /// trace, debug, info, warn, error 순
+    pub records_by_level: [u64; 5],
+    pub parse_queue_dropped: u64,
+    pub write_queue_dropped: u64,
+    pub bytes_written: u64,
+    pub writer_file_bytes: u64,
+    pub writer_rotations: u64,
+    pub writer_fsyncs: u64,
+    pub writer_files_compressed: u64,
+    pub writer_files_pruned: u64,
+    pub backups_ok: u64,
+    pub backups_failed: u64,
+    pub backups_running: u64,
+    pub subscribers: u64,
+    pub subscribe_connections_open: u64,
+    pub subscribers_refused: u64,
+    pub subscriber_dropped: u64,
+    pub store_len: u64,
+    pub store_capacity: u64,
+    pub store_evictions: u64,
+    pub store_index_entries: u64,
+    pub store_index_bytes: u64,
+    pub store_index_tokens: u64,
+    pub segments: u64,
+    pub segment_bytes: u64,
+    pub segment_records: u64,
+    pub segment_blocks_read: u64,
+}
+
+pub fn snapshot(store: &LogStore) -> Snapshot {
+    let get = |c: &AtomicU64| c.load(Ordering::Relaxed);
+    let (bad_magic, bad_version, truncated, checksum, malformed) = (
+        get(&REJECT_BAD_MAGIC),
+        get(&REJECT_BAD_VERSION),
+        get(&REJECT_TRUNCATED),
+        get(&REJECT_CHECKSUM),
+        get(&REJECT_MALFORMED),
+    );
+    let seg = store.segments().map(|s| s.usage()).unwrap_or_default();
+    let index = store.index_usage();
+    Snapshot {
+        uptime_secs: STARTED.get_or_init(Instant::now).elapsed().as_secs(),
+        udp_datagrams: get(&UDP_DATAGRAMS),
+        tcp_connections: get(&TCP_CONNECTIONS),
+        tcp_frames: get(&TCP_FRAMES),
+        tcp_connections_open: get(&TCP_CONNECTIONS_OPEN),
+        tcp_connections_refused: get(&TCP_CONNECTIONS_REFUSED),
+        tcp_frames_oversized: get(&TCP_FRAMES_OVERSIZED),
+        bytes_received: get(&BYTES_RECEIVED),
+        http_connections_open: get(&HTTP_CONNECTIONS_OPEN),
+        http_connections_refused: get(&HTTP_CONNECTIONS_REFUSED),
+        batches_accepted: get(&BATCHES_ACCEPTED),
+        batches_rejected: bad_magic + bad_version + truncated + checksum + malformed,
+        rejected_bad_magic: bad_magic,
+        rejected_bad_version: bad_version,
+        rejected_truncated: truncated,
+        rejected_checksum: checksum,
+        rejected_malformed: malformed,
+        records_parsed: get(&RECORDS_PARSED),
+        records_invalid: get(&RECORDS_INVALID),
+        records_by_level: std::array::from_fn(|i| get(&RECORDS_BY_LEVEL[i])),
+        parse_queue_dropped: pipeline::PARSE_QUEUE.stats().dropped,
+        write_queue_dropped: pipeline::WRITE_QUEUE.stats().dropped,
+        bytes_written: get(&BYTES_WRITTEN),
+        writer_file_bytes: get(&WRITER_FILE_BYTES),
+        writer_rotations: get(&WRITER_ROTATIONS),
+        writer_fsyncs: get(&WRITER_FSYNCS),
+        writer_files_compressed: get(&WRITER_FILES_COMPRESSED),
+        writer_files_pruned: get(&WRITER_FILES_PRUNED),
+        backups_ok: get(&BACKUPS_OK),
+        backups_failed: get(&BACKUPS_FAILED),
+        backups_running: get(&BACKUPS_RUNNING),
+        subscribers: store.subscriber_count() as u64,
+        subscribe_connections_open: get(&SUBSCRIBE_CONNECTIONS_OPEN),
+        subscribers_refused: get(&SUBSCRIBERS_REFUSED),
+        subscriber_dropped: get(&SUBSCRIBER_DROPPED),
+        store_len: store.len() as u64,
+        store_capacity: store.capacity() as u64,
+        store_evictions: store.evictions(),
+        store_index_entries: index.entries,
+        store_index_bytes: index.bytes,
+        store_index_tokens: index.tokens,
+        segments: seg.segments,
+        segment_bytes: seg.bytes,
+        segment_records: seg.records,
+        segment_blocks_read: seg.blocks_read,
+    }
+}
+
+impl Snapshot {
+    pub fn to_json(self) -> String {
+        serde_json::to_string(&self).expect("plain integers")
+    }
+
+    /// 콘솔 `Stats` 출력용 (이름, 값) 목록. 빈 이름은 구분선
+    pub fn rows(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("uptime (s)", self.uptime_secs),
+            ("", 0),
+            ("udp datagrams", self.udp_datagrams),
+            ("tcp connections", self.tcp_connections),
+            ("tcp frames", self.tcp_frames),
+            ("tcp connections open", self.tcp_connections_open),
+            ("tcp connections refused", self.tcp_connections_refused),
+            ("tcp frames oversized", self.tcp_frames_oversized),
+            ("bytes received", self.bytes_received),
+            ("http connections open", self.http_connections_open),
+            ("http connections refused", self.http_connections_refused),
+            ("", 0),
+            ("batches accepted", self.batches_accepted),
+            ("batches rejected", self.batches_rejected),
+            ("  bad magic", self.rejected_bad_magic),
+            ("  bad version/flags", self.rejected_bad_version),
+            ("  truncated", self.rejected_truncated),
+            ("  checksum", self.rejected_checksum),
+            ("  malformed", self.rejected_malformed),
+            ("records parsed", self.records_parsed),
+            ("records over limits", self.records_invalid),
+            ("  trace", self.records_by_level[0]),
+            ("  debug", self.records_by_level[1]),
+            ("  info", self.records_by_level[2]),
+            ("  warn", self.records_by_level[3]),
+            ("  error", self.records_by_level[4]),
+            ("", 0),
+            ("parse queue dropped", self.parse_queue_dropped),
+            ("write queue dropped", self.write_queue_dropped),
+            ("bytes written", self.bytes_written),
+            ("current file bytes", self.writer_file_bytes),
+            ("rotations", self.writer_rotations),
+            ("fsyncs", self.writer_fsyncs),
+            ("files compressed", self.writer_files_compressed),
+            ("files pruned", self.writer_files_pruned),
+            ("backups ok", self.backups_ok),
+            ("backups failed", self.backups_failed),
+            ("backups running", self.backups_running),
+            ("subscribers", self.subscribers),
+            ("subscribe connections open", self.subscribe_connections_open),
+            ("subscribers refused", self.subscribers_refused),
+            ("subscriber dropped", self.subscriber_dropped),
+            ("store len", self.store_len),
+            ("store capacity", self.store_capacity),
+            ("store evictions", self.store_evictions),
+            ("index entries", self.store_index_entries),
+            ("index bytes", self.store_index_bytes),
+            ("index tokens", self.store_index_tokens),
+            ("segments", self.segments),
+            ("segment bytes", self.segment_bytes),
+            ("segment records", self.segment_records),
+            ("segment blocks read", self.segment_blocks_read),
+        ]
+    }
+}
+
+pub fn print(s: &Snapshot) {
+    for (name, v) in s.rows() {
+        if name.is_empty() {
+            println!("{:-<22}-+-{:-<14}", "", "");
+        } else {
+            println!("{name:<22} | {v:>14}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_are_split_by_reason_and_snapshot_is_json() {
+        let store = LogStore::with_capacity(2, 1);
+        let before = snapshot(&store);
+        note_reject(&DecodeError::BadMagic(0));
+        note_reject(&DecodeError::Truncated { offset: 8 });
+        note_reject(&DecodeError::TooShort { len: 1 });
+        let after = snapshot(&store);
+        assert!(after.rejected_bad_magic > before.rejected_bad_magic);
+        assert!(after.rejected_truncated >= before.rejected_truncated + 2);
+        assert!(after.batches_rejected >= before.batches_rejected + 3);
+
+        let json = after.to_json();
+        assert!(json.starts_with('{') && json.contains("\"records_parsed\":"), "{json}");
+        assert!(json.contains("\"store_capacity\":2"), "{json}");
+    }
+}
@@ -0,0 +1,408 @@
+// src/subscribe.rs
+// 실시간 구독 (follow 모드) — 와이어 포맷은 swiftlog_proto::subscribe 참고
+//
+//   client ──Subscribe("level>=Warn service=api")──▶ server
+//          ◀──Ack── Record* ── Dropped(n) ── Record* ...
+//
+// LogStore::append가 저장 직후 구독자마다 필터를 검사해 구독자 버퍼에 Arc<Log>를 넣습니다.
+// 버퍼는 구독자별로 용량 고정이고, 가득 차면 새 레코드를 버리고 건수만 셉니다.
+// 느린 구독자가 파서/저장소를 막지 않으며, 다음 전송 때 Dropped(n)로 빠진 구간을 알립니다.
+// 연결마다 스레드 2개: 전송 스레드(버퍼 → 소켓), 감시 스레드(클라이언트가 닫으면 구독 해제).
+// 동시 연결은 subscribe.max_subscribers까지, 넘으면 Error("too many subscribers") 후 닫음.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use swiftlog_proto::{next_frame, write_frame, SubEvent, SubRecord, Subscribe};
+
+use crate::console_select::ConsoleSelect;
+use crate::diag::diag;
+use crate::log_domain::Log;
+use crate::log_store::{LogStore, SelectQuery};
+use crate::stats;
+use crate::tcp::OpenConn;
+
+// Subscribe 프레임을 기다리는 시간
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// 한 번에 소켓으로 내보낼 최대 레코드 수 (버퍼 잠금 시간 제한)
+const MAX_DRAIN: usize = 1024;
+const MAX_SUBSCRIBE_FRAME: usize = 64 * 1024;
+// 상한에 걸린 연결에 Error를 쓰고 닫을 때 (accept 스레드를 오래 막지 않도록)
+const REFUSE_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubscribeConfig {
+    /// 구독 리스너 주소. 비우면 띄우지 않음
+    pub bind: String,
+    /// 구독자별 버퍼 건수. 넘치면 버리고 Dropped로 알림
+    pub buffer: usize,
+    /// 동시 구독 연결 수 (연결마다 스레드 2개 + 버퍼). 넘으면 Error 후 닫음
+    pub max_subscribers: usize,
+}
+
+impl Default for SubscribeConfig {
+    fn default() -> Self {
+        Self { bind: "127.0.0.1:9503".into(), buffer: 4096, max_subscribers: 64 }
+    }
+}
+
+struct Slot {
+    id: u64,
+    query: SelectQuery,
+    cap: usize,
+    state: Mutex<SlotState>,
+    ready: Condvar,
+}
+
+#[derive(Default)]
+struct SlotState {
+    buf: VecDeque<Arc<Log>>,
+    dropped: u64,
+    closed: bool,
+}
+
+impl Slot {
+    fn offer(&self, log: &Arc<Log>) {
+        let mut st = self.state.lock().unwrap();
+        if st.buf.len() >= self.cap {
+            st.dropped += 1;
+            stats::add(&stats::SUBSCRIBER_DROPPED, 1);
+            return;
+        }
+        st.buf.push_back(log.clone());
+        drop(st);
+        self.ready.notify_one();
+    }
+}
+
+/// LogStore가 가진 구독자 목록
+#[derive(Default)]
+pub struct Subscribers {
+    slots: Arc<Mutex<Vec<Arc<Slot>>>>,
+    // 구독자가 없을 때 append가 잠금 없이 지나가도록
+    active: Arc<AtomicUsize>,
+    next_id: AtomicU64,
+}
+
+impl Subscribers {
+    pub fn publish(&self, log: &Arc<Log>) {
+        if self.active.load(Ordering::Relaxed) == 0 { return; }
+        for slot in self.slots.lock().unwrap().iter() {
+            if slot.query.matches(log) {
+                slot.offer(log);
+            }
+        }
+    }
+
+    pub fn subscribe(&self, query: SelectQuery, buffer: usize) -> Subscription {
+        let slot = Arc::new(Slot {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            query,
+            cap: buffer.max(1),
+            state: Mutex::new(SlotState::default()),
+            ready: Condvar::new(),
+        });
+        self.slots.lock().unwrap().push(slot.clone());
+        self.active.fetch_add(1, Ordering::Relaxed);
+        Subscription { slot, slots: self.slots.clone(), active: self.active.clone() }
+    }
+
+    pub fn len(&self) -> usize { self.active.load(Ordering::Relaxed) }
+}
+
+/// 구독 핸들. drop하면 구독 해제
+pub struct Subscription {
+    slot: Arc<Slot>,
+    slots: Arc<Mutex<Vec<Arc<Slot>>>>,
+    active: Arc<AtomicUsize>,
+}
+
+/// `Subscription::next`의 결과
+#[derive(Debug, Default)]
+pub struct Delivery {
+    pub logs: Vec<Arc<Log>>,
+    /// logs 마지막 건 뒤로 버려진 레코드 수
+    pub dropped: u64,
+    pub closed: bool,
+}
+
+impl Subscription {
+    /// 레코드가 오거나 `timeout`이 지나거나 close될 때까지 기다렸다가 쌓인 것을 가져옵니다.
+    pub fn next(&self, timeout: Duration) -> Delivery {
+        let st = self.slot.state.lock().unwrap();
+        let (mut st, _) = self.slot.ready
+            .wait_timeout_while(st, timeout, |s| s.buf.is_empty() && s.dropped == 0 && !s.closed)
+            .unwrap();
+        let n = st.buf.len().min(MAX_DRAIN);
+        let logs: Vec<_> = st.buf.drain(..n).collect();
+        // 버퍼가 빈 뒤에만 Dropped를 내보내야 순서가 맞음 (버린 건 항상 버퍼 뒤쪽)
+        let dropped = if st.buf.is_empty() { std::mem::take(&mut st.dropped) } else { 0 };
+        Delivery { logs, dropped, closed: st.closed }
+    }
+
+    /// 다른 스레드에서 구독을 끝냄 (next가 closed로 깨어남)
+    pub fn closer(&self) -> impl Fn() + Send + 'static {
+        let slot = self.slot.clone();
+        move || {
+            slot.state.lock().unwrap().closed = true;
+            slot.ready.notify_all();
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.slots.lock().unwrap().retain(|s| s.id != self.slot.id);
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn to_event(log: &Log) -> SubEvent {
+    SubEvent::Record(SubRecord {
+        id: log.id,
+        ts_ms: log.ts_ms,
+        level: log.level,
+        code: log.code,
+        source: log.source.as_deref().map(|s| s.label()).unwrap_or_default(),
+        msg: log.msg.clone(),
+        fields: log.fields.clone(),
+    })
+}
+
+pub struct SubscribeRx {
+    listener: TcpListener,
+    max_subscribers: usize,
+}
+
+impl SubscribeRx {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr)?, max_subscribers: usize::MAX })
+    }
+
+    /// 동시 구독 연결 상한 (넘는 연결은 Error를 보내고 바로 닫음)
+    pub fn with_max_subscribers(mut self, n: usize) -> Self {
+        self.max_subscribers = n;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> { self.listener.local_addr() }
+
+    /// accept 스레드 시작. 연결마다 Subscribe 한 번을 받아 구독을 엽니다.
+    pub fn spawn(self, store: Arc<LogStore>, buffer: usize) -> io::Result<JoinHandle<()>> {
+        let open = Arc::new(AtomicUsize::new(0));
+        thread::Builder::new().name("swiftlog-subscribe".into()).spawn(move || {
+            for incoming in self.listener.incoming() {
+                let mut stream = match incoming {
+                    Ok(s) => s,
+                    Err(e) => {
+                        diag!(Warn, "subscribe", "accept failed: {e}");
+                        continue;
+                    }
+                };
+                let Some(guard) = OpenConn::try_new(&open, self.max_subscribers, &stats::SUBSCRIBE_CONNECTIONS_OPEN) else {
+                    diag!(Warn, "subscribe", "refusing subscriber: {} connections open", self.max_subscribers);
+                    stats::add(&stats::SUBSCRIBERS_REFUSED, 1);
+                    refuse(&mut stream);
+                    continue;
+                };
+                let store = store.clone();
+                let spawned = thread::Builder::new().name("swiftlog-subscriber".into()).spawn(move || {
+                    let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                    if let Err(e) = serve(stream, &store, buffer) {
+                        diag!(Debug, "subscribe", "{peer}: {e}");
+                    }
+                    drop(guard);
+                });
+                if let Err(e) = spawned {
+                    diag!(Warn, "subscribe", "cannot serve subscriber: {e}");
+                }
+            }
+        })
+    }
+}
+
+// Error를 쓰고 받은 Subscribe는 잠깐만 읽어 버림 (안 읽고 닫으면 RST로 Error가 사라질 수 있음)
+fn refuse(stream: &mut TcpStream) {
+    let _ = stream.set_write_timeout(Some(REFUSE_TIMEOUT));
+    let _ = stream.set_read_timeout(Some(REFUSE_TIMEOUT));
+    if send(stream, &[SubEvent::Error("too many subscribers".into())]).is_ok() {
+        let _ = stream.shutdown(Shutdown::Write);
+        let _ = io::copy(&mut stream.take(MAX_SUBSCRIBE_FRAME as u64), &mut io::sink());
+    }
+}
+
+fn send(stream: &mut TcpStream, events: &[SubEvent]) -> io::Result<()> {
+    let mut out = Vec::new();
+    for ev in events {
+        write_frame(&mut out, &ev.encode());
+    }
+    stream.write_all(&out)
+}
+
+fn read_subscribe(stream: &mut TcpStream) -> io::Result<Subscribe> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut buf = Vec::new();
+    let mut tmp = [0u8; 4096];
+    loop {
+        if let Some((frame, _)) = next_frame(&buf) {
+            return Subscribe::decode(frame).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        if buf.len() > MAX_SUBSCRIBE_FRAME {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "subscribe frame too large"));
+        }
+        match stream.read(&mut tmp)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => buf.extend_from_slice(&tmp[..n]),
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream, store: &LogStore, buffer: usize) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let req = read_subscribe(&mut stream)?;
+    let query = match ConsoleSelect::parse(&req.query) {
+        Ok(q) => q,
+        Err(e) => {
+            send(&mut stream, &[SubEvent::Error(format!("bad query: {e}"))])?;
+            return Ok(());
+        }
+    };
+    let sub = store.subscribe(query, buffer);
+    diag!(Info, "subscribe", "{} subscribed: {:?}", stream.peer_addr()?, req.query);
+    send(&mut stream, &[SubEvent::Ack])?;
+
+    // 감시 스레드: 클라이언트가 닫으면(또는 뭔가 보내면) 구독 종료
+    let mut watch = stream.try_clone()?;
+    watch.set_read_timeout(None)?;
+    let close = sub.closer();
+    thread::Builder::new().name("swiftlog-subscriber-watch".into()).spawn(move || {
+        let _ = watch.read(&mut [0u8; 64]);
+        close();
+    })?;
+
+    let result = loop {
+        let d = sub.next(Duration::from_secs(1));
+        if d.closed { break Ok(()); }
+        let mut events: Vec<SubEvent> = d.logs.iter().map(|l| to_event(l)).collect();
+        if d.dropped > 0 { events.push(SubEvent::Dropped(d.dropped)); }
+        if events.is_empty() { continue; }
+        if let Err(e) = send(&mut stream, &events) { break Err(e); }
+    };
+    let _ = stream.shutdown(Shutdown::Both); // 감시 스레드도 깨움
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_domain::LogLevel;
+
+    fn read_event(s: &mut TcpStream, buf: &mut Vec<u8>) -> SubEvent {
+        let mut tmp = [0u8; 4096];
+        loop {
+            if let Some((frame, used)) = next_frame(buf) {
+                let ev = SubEvent::decode(frame).unwrap();
+                buf.drain(..used);
+                return ev;
+            }
+            let n = s.read(&mut tmp).unwrap();
+            assert!(n > 0, "server closed");
+            buf.extend_from_slice(&tmp[..n]);
+        }
+    }
+
+    #[test]
+    fn full_buffer_drops_and_reports_count() {
+        let store = LogStore::with_capacity(100, 2);
+        let sub = store.subscribe(SelectQuery { level_min: Some(LogLevel::Warn), ..Default::default() }, 2);
+        for i in 0..5 {
+            store.append(Log::new_unassigned(i, LogLevel::Error, 1, format!("e{i}")));
+            store.append(Log::new_unassigned(i, LogLevel::Info, 1, "ignored"));
+        }
+        let d = sub.next(Duration::from_millis(10));
+        assert_eq!(d.logs.iter().map(|l| l.msg.as_str()).collect::<Vec<_>>(), ["e0", "e1"]);
+        assert_eq!(d.dropped, 3);
+
+        store.append(Log::new_unassigned(9, LogLevel::Warn, 1, "after"));
+        let d = sub.next(Duration::from_millis(10));
+        assert_eq!((d.logs.len(), d.dropped), (1, 0));
+
+        assert_eq!(store.subscriber_count(), 1);
+        drop(sub);
+        assert_eq!(store.subscriber_count(), 0);
+    }
+
+    #[test]
+    fn tcp_subscriber_receives_matching_records() {
+        let store = Arc::new(LogStore::with_capacity(100, 2));
+        let rx = SubscribeRx::bind("127.0.0.1:0").unwrap();
+        let addr = rx.local_addr().unwrap();
+        rx.spawn(store.clone(), 16).unwrap();
+
+        // 잘못된 쿼리는 Error 후 종료
+        let mut bad = TcpStream::connect(addr).unwrap();
+        let mut out = Vec::new();
+        write_frame(&mut out, &Subscribe::new("limit=many").encode());
+        bad.write_all(&out).unwrap();
+        assert!(matches!(read_event(&mut bad, &mut Vec::new()), SubEvent::Error(e) if e.contains("bad query")));
+
+        let mut s = TcpStream::connect(addr).unwrap();
+        let mut out = Vec::new();
+        write_frame(&mut out, &Subscribe::new("level>=Warn").encode());
+        s.write_all(&out).unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(read_event(&mut s, &mut buf), SubEvent::Ack);
+
+        store.append(Log::new_unassigned(1, LogLevel::Info, 1, "skip"));
+        store.append(Log::new_unassigned(2, LogLevel::Error, 500, "boom"));
+        match read_event(&mut s, &mut buf) {
+            SubEvent::Record(r) => assert_eq!((r.level, r.code, r.msg.as_str()), (LogLevel::Error, 500, "boom")),
+            other => panic!("unexpected {other:?}"),
+        }
+
+        // 연결을 닫으면 구독도 해제
+        drop(s);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while store.subscriber_count() != 0 {
+            assert!(std::time::Instant::now() < deadline, "subscription not released");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn subscribers_over_the_limit_get_an_error() {
+        let store = Arc::new(LogStore::with_capacity(10, 1));
+        let rx = SubscribeRx::bind("127.0.0.1:0").unwrap().with_max_subscribers(1);
+        let addr = rx.local_addr().unwrap();
+        rx.spawn(store.clone(), 16).unwrap();
+        let subscribe = || {
+            let mut s = TcpStream::connect(addr).unwrap();
+            let mut out = Vec::new();
+            write_frame(&mut out, &Subscribe::new("").encode());
+            s.write_all(&out).unwrap();
+            let ev = read_event(&mut s, &mut Vec::new());
+            (s, ev)
+        };
+
+        let (first, ev) = subscribe();
+        assert_eq!(ev, SubEvent::Ack);
+        let (_, ev) = subscribe();
+        assert_eq!(ev, SubEvent::Error("too many subscribers".into()));
+
+        // 닫으면 자리가 비어 다시 받음
+        drop(first);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while subscribe().1 != SubEvent::Ack {
+            assert!(std::time::Instant::now() < deadline, "slot not released");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -1,76 +1,207 @@
-use std::collections::VecDeque;
-use std::io::{self, Read};
-use std::net::{TcpListener, TcpStream};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use swiftlog_proto::{
+    negotiate_version, next_frame, peek_magic, write_frame, Hello, HelloAck, FRAME_PREFIX_LEN, HELLO_MAGIC,
+};
+
+use crate::diag::diag;
+use crate::log_domain::Source;
+use crate::stats;
+
+/// 프레임(배치) 길이 상한. 클라이언트 배치는 64KB 이하라 넉넉히 잡음.
+/// 넘는 길이 프리픽스는 끝까지 버퍼에 모으지 않고 바로 연결을 끊음
+pub const MAX_TCP_FRAME: usize = 1024 * 1024;
 
 pub struct Conn {
     stream: TcpStream,
     buf: Vec<u8>, // 누적 read 버퍼
+    source: Arc<Source>, // Hello에 Identity가 오면 교체
 }
 impl Conn {
-    fn new(s: TcpStream) -> io::Result<Self> {
-        s.set_nonblocking(true)?;
+    fn new(s: TcpStream, peer: SocketAddr) -> io::Result<Self> {
         s.set_nodelay(true)?; // 지연 줄이기
-        Ok(Self { stream: s, buf: Vec::with_capacity(4096) })
+        Ok(Self { stream: s, buf: Vec::with_capacity(4096), source: Arc::new(Source::peer(peer)) })
     }
 
-    // 이 연결에서 읽을 수 있는 만큼 읽고, [len|batch] 파싱
-    pub fn poll_read<F: FnMut(&[u8]) -> io::Result<()>>(&mut self, mut on_batch: F) -> bool {
-        let mut tmp = [0u8; 4096];
+    // 연결이 끊길 때까지 블로킹 read → [len|batch] 파싱
+    fn run<F: FnMut(&Arc<Source>, &[u8]) -> io::Result<()>>(&mut self, mut on_batch: F) {
+        let mut tmp = [0u8; 16 * 1024];
         loop {
-            match self.stream.read(&mut tmp) {
-                Ok(0) => return false, // closed
-                Ok(n) => self.buf.extend_from_slice(&tmp[..n]),
-                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
-                Err(_) => return false,
-            }
+            let n = match self.stream.read(&mut tmp) {
+                Ok(0) => 0,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return,
+            };
+            self.buf.extend_from_slice(&tmp[..n]);
+            stats::add(&stats::BYTES_RECEIVED, n as u64);
+            // closed: 이미 받은 프레임은 처리하고 끝냄 (짧게 쓰고 닫는 클라이언트)
+            if !self.process_frames(&mut on_batch) || n == 0 { return; }
         }
+    }
 
+    // 버퍼에 완성된 프레임을 모두 처리. Hello 응답 쓰기에 실패하거나 프레임이 너무 크면 false
+    fn process_frames<F: FnMut(&Arc<Source>, &[u8]) -> io::Result<()>>(&mut self, on_batch: &mut F) -> bool {
+        let mut alive = true;
         let mut offset = 0usize;
-        while self.buf.len() >= offset + 4 {
-            let len = u32::from_le_bytes(self.buf[offset..offset+4].try_into().unwrap()) as usize;
-            if self.buf.len() < offset + 4 + len { break; }
-            let start = offset + 4;
-            let end = start + len;
-            let _ = on_batch(&self.buf[start..end]);
-            offset = end;
+        loop {
+            let rest = &self.buf[offset..];
+            if let Some(len) = rest.get(..FRAME_PREFIX_LEN).map(|p| u32::from_le_bytes(p.try_into().unwrap()) as usize) {
+                if len > MAX_TCP_FRAME {
+                    diag!(Warn, "tcp", "{}: frame of {len} bytes exceeds {MAX_TCP_FRAME}, closing", self.source.peer);
+                    stats::add(&stats::TCP_FRAMES_OVERSIZED, 1);
+                    return false;
+                }
+            }
+            let Some((frame, used)) = next_frame(rest) else { break };
+            if peek_magic(frame) == Some(HELLO_MAGIC) {
+                // 버전 협상: 배치 디코더는 지원 범위 내 모든 버전을 받으므로 응답만 돌려줌
+                let reply = match Hello::decode(frame) {
+                    Ok(h) => {
+                        if h.identity.is_some() {
+                            self.source = Arc::new(Source { peer: self.source.peer, identity: h.identity });
+                        }
+                        negotiate_version(h.min_version, h.max_version).unwrap_or(0)
+                    }
+                    Err(_) => 0,
+                };
+                let mut out = Vec::with_capacity(16);
+                write_frame(&mut out, &HelloAck { version: reply }.encode());
+                if self.stream.write_all(&out).is_err() { alive = false; }
+            } else {
+                stats::add(&stats::TCP_FRAMES, 1);
+                let _ = on_batch(&self.source, frame);
+            }
+            offset += used;
         }
         if offset > 0 { self.buf.drain(0..offset); }
-        true
+        alive
     }
 }
 
 pub struct TcpRx {
     listener: TcpListener,
-    conns: Vec<Conn>,
-    dead: VecDeque<usize>,
+    max_connections: usize,
 }
 impl TcpRx {
     pub fn bind(addr: &str) -> io::Result<Self> {
-        let l = TcpListener::bind(addr)?;
-        l.set_nonblocking(true)?;
-        Ok(Self { listener: l, conns: Vec::new(), dead: VecDeque::new() })
+        Ok(Self { listener: TcpListener::bind(addr)?, max_connections: usize::MAX })
     }
 
-    pub fn accept_once(&mut self) {
-        loop {
-            match self.listener.accept() {
-                Ok((s, _addr)) => {
-                    if let Ok(c) = Conn::new(s) { self.conns.push(c); }
+    /// 동시 연결 상한 (넘는 연결은 accept 직후 닫음)
+    pub fn with_max_connections(mut self, n: usize) -> Self {
+        self.max_connections = n;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> { self.listener.local_addr() }
+
+    /// accept 스레드 시작. 연결마다 전용 스레드가 블로킹 read로 배치를 받아 `on_batch`에 넘깁니다
+    /// (핸들러는 연결마다 clone).
+    pub fn spawn<F>(self, on_batch: F) -> io::Result<JoinHandle<()>>
+    where
+        F: FnMut(&Arc<Source>, &[u8]) -> io::Result<()> + Clone + Send + 'static,
+    {
+        let open = Arc::new(AtomicUsize::new(0));
+        thread::Builder::new().name("swiftlog-tcp-accept".into()).spawn(move || {
+            for incoming in self.listener.incoming() {
+                let s = match incoming {
+                    Ok(s) => s,
+                    Err(e) => {
+                        // EMFILE 등: 다음 accept에서 재시도
+                        diag!(Warn, "tcp", "accept failed: {e}");
+                        continue;
+                    }
+                };
+                let addr = match s.peer_addr() {
+                    Ok(a) => a,
+                    Err(_) => continue,
+                };
+                let Some(guard) = OpenConn::try_new(&open, self.max_connections, &stats::TCP_CONNECTIONS_OPEN) else {
+                    diag!(Warn, "tcp", "refusing {addr}: {} connections open", self.max_connections);
+                    stats::add(&stats::TCP_CONNECTIONS_REFUSED, 1);
+                    continue; // s를 버려서 닫음
+                };
+                diag!(Debug, "tcp", "connection from {addr}");
+                stats::add(&stats::TCP_CONNECTIONS, 1);
+                let handler = on_batch.clone();
+                let spawned = thread::Builder::new()
+                    .name(format!("swiftlog-tcp-{addr}"))
+                    .spawn(move || {
+                        if let Ok(mut c) = Conn::new(s, addr) {
+                            c.run(handler);
+                            drop(guard); // 소켓이 닫히기 전에 자리를 비움 (끊긴 쪽이 바로 다시 붙을 수 있게)
+                        }
+                    });
+                if let Err(e) = spawned {
+                    diag!(Warn, "tcp", "cannot serve {addr}: {e}");
                 }
-                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
-                Err(_e) => break,
             }
-        }
+        })
     }
+}
 
-    pub fn poll_once<F: FnMut(&[u8]) -> io::Result<()>>(&mut self, mut on_batch: F) {
-        for (i, c) in self.conns.iter_mut().enumerate() {
-            if !c.poll_read(&mut on_batch) {
-                self.dead.push_back(i);
-            }
-        }
-        while let Some(i) = self.dead.pop_front() {
-            if i < self.conns.len() { self.conns.swap_remove(i); }
-        }
+/// 열린 연결 한 자리 (리스너별 상한용 카운터 + stats 게이지). 연결 스레드가 끝나면(또는 못 띄우면) 줄어듦.
+/// TCP 수신, HTTP, 구독 리스너가 같이 씁니다.
+pub struct OpenConn {
+    open: Arc<AtomicUsize>,
+    gauge: &'static AtomicU64,
+}
+
+impl OpenConn {
+    /// 자리가 남았으면 차지. `max`에 닿았으면 None
+    pub fn try_new(open: &Arc<AtomicUsize>, max: usize, gauge: &'static AtomicU64) -> Option<Self> {
+        open.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (n < max).then_some(n + 1)).ok()?;
+        stats::add(gauge, 1);
+        Some(Self { open: open.clone(), gauge })
+    }
+}
+
+impl Drop for OpenConn {
+    fn drop(&mut self) {
+        self.open.fetch_sub(1, Ordering::Relaxed);
+        self.gauge.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn closed_by_server(s: &mut TcpStream) -> bool {
+        s.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        matches!(s.read(&mut [0u8; 1]), Ok(0) | Err(_))
+    }
+
+    #[test]
+    fn oversized_frames_and_extra_connections_are_closed() {
+        let rx = TcpRx::bind("127.0.0.1:0").unwrap().with_max_connections(1);
+        let addr = rx.local_addr().unwrap();
+        let (tx, batches) = mpsc::channel();
+        rx.spawn(move |_: &Arc<Source>, b: &[u8]| { tx.send(b.to_vec()).unwrap(); Ok(()) }).unwrap();
+
+        let mut first = TcpStream::connect(addr).unwrap();
+        let mut out = Vec::new();
+        write_frame(&mut out, b"ok");
+        first.write_all(&out).unwrap();
+        assert_eq!(batches.recv_timeout(Duration::from_secs(5)).unwrap(), b"ok");
+        // 상한 1: 두 번째 연결은 바로 닫힘
+        let mut second = TcpStream::connect(addr).unwrap();
+        assert!(closed_by_server(&mut second));
+
+        // 길이 프리픽스만 보고 끊음 (본문을 기다리지 않음)
+        first.write_all(&((MAX_TCP_FRAME + 1) as u32).to_le_bytes()).unwrap();
+        assert!(closed_by_server(&mut first));
+        // 끊긴 자리가 비었으니 다시 받음
+        let mut third = TcpStream::connect(addr).unwrap();
+        third.write_all(&out).unwrap();
+        assert_eq!(batches.recv_timeout(Duration::from_secs(5)).unwrap(), b"ok");
     }
 }
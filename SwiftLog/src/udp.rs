@@ -1,46 +1,58 @@
 use std::io;
-use std::net::UdpSocket;
-use std::sync::atomic::{AtomicU64, Ordering};
-
-
-pub static RECV_NORMAL_PACKET_COUNT: AtomicU64 = AtomicU64::new(0);
-
-pub fn increase_normal_count() {
-    RECV_NORMAL_PACKET_COUNT.fetch_add(1, Ordering::Relaxed);
-}
-pub fn get_normal_count() -> u64 {
-    RECV_NORMAL_PACKET_COUNT.load(Ordering::Relaxed)
-}
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 
+use crate::diag::diag;
+use crate::log_domain::Source;
+use crate::stats;
 
 pub struct UdpRx {
     sock: UdpSocket,
     buf: Box<[u8]>,
+    last_src: Option<Arc<Source>>, // 같은 peer가 연달아 보내면 재사용
 }
 
 impl UdpRx {
     pub fn bind(addr: &str, buf_size: usize) -> io::Result<Self> {
         let sock = UdpSocket::bind(addr)?;
-        sock.set_nonblocking(true)?;
-        Ok(Self { sock, buf: vec![0u8; buf_size].into_boxed_slice() })
+        Ok(Self { sock, buf: vec![0u8; buf_size].into_boxed_slice(), last_src: None })
     }
 
-    // 논블로킹 단발 수신 한 번만 시도 (없으면 WouldBlock)
-    pub fn recv_once<F: FnMut(&[u8]) -> io::Result<()>>(&mut self, mut on_batch: F) {
-
-        // RECV_PACKET_COUNT.fetch_add(1, Ordering::Relaxed);
-
-
-        match self.sock.recv(&mut self.buf) {
-            Ok(n) => {
-                increase_normal_count();
-                 let _ = on_batch(&self.buf[..n]); 
+    #[allow(dead_code)]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> { self.sock.local_addr() }
+
+    /// 전용 수신 스레드 시작. 블로킹 recv라 데이터그램이 도착하는 즉시 깨어나고,
+    /// 소켓 큐에 쌓인 것은 다음 recv들이 대기 없이 연달아 비웁니다.
+    pub fn spawn<F>(mut self, mut on_batch: F) -> io::Result<JoinHandle<()>>
+    where
+        F: FnMut(&Arc<Source>, &[u8]) -> io::Result<()> + Send + 'static,
+    {
+        thread::Builder::new().name("swiftlog-udp".into()).spawn(move || loop {
+            match self.sock.recv_from(&mut self.buf) {
+                Ok((n, peer)) => {
+                    stats::add(&stats::UDP_DATAGRAMS, 1);
+                    stats::add(&stats::BYTES_RECEIVED, n as u64);
+                    let src = self.source_for(peer);
+                    let _ = on_batch(&src, &self.buf[..n]);
+                }
+                // Windows는 이전 send의 ICMP unreachable을 recv 에러로 돌려줌 → 무시
+                Err(e) if matches!(e.kind(), io::ErrorKind::Interrupted | io::ErrorKind::ConnectionReset) => {}
+                Err(e) => {
+                    diag!(Error, "udp", "receiver stopped: {e}");
+                    return;
                 }
-            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
-
             }
-            Err(_e) => {
+        })
+    }
 
+    fn source_for(&mut self, peer: SocketAddr) -> Arc<Source> {
+        match &self.last_src {
+            Some(s) if s.peer == peer => s.clone(),
+            _ => {
+                let s = Arc::new(Source::peer(peer));
+                self.last_src = Some(s.clone());
+                s
             }
         }
     }
@@ -1,43 +1,401 @@
-use crate::proto::MAX_FILE_BYTES;
+// src/writer.rs
+// 로그 파일 기록 (<dir>/<base>.log, 줄 형식은 format.rs)
+//
+// 쓰기는 BufWriter에 모았다가 내보내고, 디스크 동기화(fdatasync)는 `FsyncPolicy`를 따릅니다.
+//   never       : 동기화는 OS에 맡김 (버퍼는 FLUSH_INTERVAL마다 내보냄)
+//   records:<n> : n줄마다 flush + 동기화
+//   ms:<n>      : 마지막 동기화 후 n ms가 지났으면 flush + 동기화 (writer 스레드가 tick으로 점검)
+// 기록 도중 프로세스가 죽으면 마지막 줄이 잘려 있을 수 있으므로, open이 마지막 '\n' 뒤를 잘라냅니다.
+// 롤링 조건(크기/시각)과 롤링된 파일의 압축·보존은 rotation.rs.
+
+use std::fmt;
 use std::fs::{OpenOptions, File};
-use std::io::{self, Write};
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diag::diag;
+use crate::rotation::{self, Maintainer, RotateEvery, RotationConfig};
+use crate::stats;
+
+const BUF_SIZE: usize = 64 * 1024;
+// 동기화와 별개로, 버퍼에 남은 줄을 파일(OS)로 내보내는 최대 지연
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FsyncPolicy {
+    Never,
+    Records(u64),
+    Millis(u64),
+}
+
+impl FsyncPolicy {
+    /// "never" | "records:<n>" | "ms:<n>" (n > 0)
+    pub fn parse(s: &str) -> Option<Self> {
+        if s == "never" { return Some(FsyncPolicy::Never); }
+        let (kind, n) = s.split_once(':')?;
+        let n: u64 = n.parse().ok().filter(|n| *n > 0)?;
+        match kind {
+            "records" => Some(FsyncPolicy::Records(n)),
+            "ms" => Some(FsyncPolicy::Millis(n)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FsyncPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsyncPolicy::Never => f.write_str("never"),
+            FsyncPolicy::Records(n) => write!(f, "records:{n}"),
+            FsyncPolicy::Millis(n) => write!(f, "ms:{n}"),
+        }
+    }
+}
+
+impl TryFrom<String> for FsyncPolicy {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> {
+        Self::parse(&s).ok_or_else(|| format!("invalid fsync policy: {s:?} (never | records:<n> | ms:<n>)"))
+    }
+}
+
+impl From<FsyncPolicy> for String {
+    fn from(p: FsyncPolicy) -> String { p.to_string() }
+}
 
 pub struct LogWriter {
     dir: PathBuf,
     base: String,
-    file: File,
+    out: BufWriter<File>,
     written: u64,
+    max_file_bytes: u64,
+    every: RotateEvery,
+    period: Option<u64>, // 현재 파일이 속한 시각 구간 (every가 never면 None)
+    maintainer: Option<Maintainer>,
+    fsync: FsyncPolicy,
+    header: &'static [u8], // 새(빈) 파일 맨 앞에 쓰는 줄 (CSV 헤더)
+    unsynced: u64, // 마지막 동기화 이후 기록한 줄 수
+    last_sync: Instant,
+    last_flush: Instant,
 }
 
 impl LogWriter {
-    pub fn open(dir: &str, base: &str) -> io::Result<Self> {
+    /// 크기로만 롤링 (0 = 안 함). 시각 롤링/압축/보존은 with_rotation
+    pub fn open(dir: &str, base: &str, max_file_bytes: u64) -> io::Result<Self> {
         let d = PathBuf::from(dir);
         std::fs::create_dir_all(&d)?;
         let path = d.join(format!("{}.log", base));
+        let cut = repair_torn_tail(&path)?;
+        if cut > 0 {
+            diag!(Warn, "writer", "{}: dropped {cut} byte(s) of a partial last line", path.display());
+        }
         let f = OpenOptions::new().create(true).append(true).open(&path)?;
-        Ok(Self { dir: d, base: base.into(), file: f, written: 0 })
+        // 이어 쓰는 파일: 이미 있는 크기부터 셈
+        let meta = f.metadata()?;
+        stats::add(&stats::WRITER_FILE_BYTES, meta.len());
+        Ok(Self {
+            dir: d,
+            base: base.into(),
+            out: BufWriter::with_capacity(BUF_SIZE, f),
+            written: meta.len(),
+            max_file_bytes,
+            every: RotateEvery::Never,
+            period: None,
+            maintainer: None,
+            fsync: FsyncPolicy::Never,
+            header: b"",
+            unsynced: 0,
+            last_sync: Instant::now(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// 롤링 설정 전체 적용. 압축/보존 한도가 있으면 정리 스레드를 띄웁니다.
+    pub fn with_rotation(mut self, cfg: &RotationConfig) -> io::Result<Self> {
+        self.max_file_bytes = cfg.max_file_bytes;
+        self.every = cfg.every;
+        // 이어 쓰는 파일의 구간은 마지막 수정 시각 기준
+        let meta = self.out.get_ref().metadata()?;
+        let mtime = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs());
+        self.period = cfg.every.period(if meta.len() > 0 { mtime } else { rotation::epoch_secs() });
+        self.maintainer = Maintainer::spawn(&self.dir, &self.base, cfg)?;
+        Ok(self)
+    }
+
+    pub fn with_fsync(mut self, fsync: FsyncPolicy) -> Self {
+        self.fsync = fsync;
+        self
     }
 
     pub fn rotate_if_needed(&mut self) -> io::Result<()> {
-        if self.written < MAX_FILE_BYTES { return Ok(()); }
-        drop(&self.file);
-        let ts = SystemTime::now().duration_since(UNIX_EPOCH)
-            .unwrap_or_default().as_secs();
+        let now = rotation::epoch_secs();
+        let period = self.every.period(now);
+        let by_size = self.max_file_bytes > 0 && self.written >= self.max_file_bytes;
+        let by_time = period != self.period;
+        if !by_size && !by_time { return Ok(()); }
+        // 빈 파일은 구간만 넘김
+        if self.written == 0 {
+            self.period = period;
+            return Ok(());
+        }
+
+        // 닫기 전에 남은 줄을 내보내고 동기화 (롤링된 파일은 더 쓰지 않으므로)
+        self.sync()?;
         let src = self.dir.join(format!("{}.log", self.base));
-        let dst = self.dir.join(format!("{}.log.{}", self.base, ts));
-        let _ = std::fs::rename(&src, &dst);
-        self.file = OpenOptions::new().create(true).append(true)
-            .open(self.dir.join(format!("{}.log", self.base)))?;
+        // 같은 초에 두 번 롤링해도 덮어쓰지 않도록 (압축된 이름까지 확인)
+        let mut ts = now;
+        let free = |ts: u64| ["", ".gz", ".zst"].iter()
+            .all(|ext| !self.dir.join(format!("{}.log.{ts}{ext}", self.base)).exists());
+        while !free(ts) { ts += 1; }
+        let dst = self.dir.join(format!("{}.log.{ts}", self.base));
+        // 실패하면 구간/크기/통계를 그대로 두고 다음 점검에서 다시 시도
+        std::fs::rename(&src, &dst)
+            .map_err(|e| io::Error::new(e.kind(), format!("rename {} -> {}: {e}", src.display(), dst.display())))?;
+        diag!(Info, "writer", "rotated {} -> {} ({} bytes, by {})", src.display(), dst.display(),
+            self.written, if by_size { "size" } else { "time" });
+        self.period = period;
+        let f = OpenOptions::new().create(true).append(true).open(&src)?;
+        self.out = BufWriter::with_capacity(BUF_SIZE, f);
+        stats::WRITER_FILE_BYTES.fetch_sub(self.written, Ordering::Relaxed);
         self.written = 0;
+        stats::add(&stats::WRITER_ROTATIONS, 1);
+        if let Some(m) = &self.maintainer { m.request(); }
+        Ok(())
+    }
+
+    pub fn with_header(mut self, header: &'static [u8]) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// 줄바꿈까지 포함된 줄 묶음을 버퍼에 추가 (records:<n> 정책이면 여기서 동기화)
+    pub fn write_block(&mut self, block: &[u8]) -> io::Result<()> {
+        if self.written == 0 && !self.header.is_empty() {
+            self.out.write_all(self.header)?;
+            self.written += self.header.len() as u64;
+            stats::add(&stats::WRITER_FILE_BYTES, self.header.len() as u64);
+        }
+        self.out.write_all(block)?;
+        self.written += block.len() as u64;
+        self.unsynced += block.iter().filter(|b| **b == b'\n').count() as u64;
+        stats::add(&stats::BYTES_WRITTEN, block.len() as u64);
+        stats::add(&stats::WRITER_FILE_BYTES, block.len() as u64);
+        if let FsyncPolicy::Records(n) = self.fsync {
+            if self.unsynced >= n { self.sync()?; }
+        }
+        Ok(())
+    }
+
+    /// 주기 점검 (writer 스레드가 블록마다, 그리고 쉴 때도 호출): ms:<n> 동기화, 버퍼 flush
+    pub fn tick(&mut self) -> io::Result<()> {
+        if let FsyncPolicy::Millis(ms) = self.fsync {
+            if self.unsynced > 0 && self.last_sync.elapsed() >= Duration::from_millis(ms) {
+                return self.sync();
+            }
+        }
+        if !self.out.buffer().is_empty() && self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.out.flush()?;
+            self.last_flush = Instant::now();
+        }
         Ok(())
     }
 
-    pub fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
-        self.file.write_all(line)?;
-        self.file.write_all(b"\n")?;
-        self.written += (line.len() + 1) as u64;
+    /// 버퍼를 내보내고, never 정책이 아니면 디스크까지 동기화
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.last_flush = Instant::now();
+        if self.fsync != FsyncPolicy::Never && self.unsynced > 0 {
+            self.out.get_ref().sync_data()?;
+            stats::add(&stats::WRITER_FSYNCS, 1);
+            self.last_sync = Instant::now();
+        }
+        self.unsynced = 0;
         Ok(())
     }
 }
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        let _ = self.sync();
+        stats::WRITER_FILE_BYTES.fetch_sub(self.written, Ordering::Relaxed);
+    }
+}
+
+/// 마지막 '\n' 뒤(끝나지 않은 줄)를 잘라내고 잘라낸 바이트 수를 돌려줌. 파일이 없으면 0
+fn repair_torn_tail(path: &Path) -> io::Result<u64> {
+    let mut f = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let len = f.metadata()?.len();
+    // 뒤에서부터 청크 단위로 마지막 줄바꿈을 찾음
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut end = len;
+    let keep = loop {
+        if end == 0 { break 0; }
+        let start = end.saturating_sub(buf.len() as u64);
+        let chunk = &mut buf[..(end - start) as usize];
+        f.seek(SeekFrom::Start(start))?;
+        f.read_exact(chunk)?;
+        if let Some(i) = chunk.iter().rposition(|b| *b == b'\n') {
+            break start + i as u64 + 1;
+        }
+        end = start;
+    };
+    if keep < len {
+        f.set_len(keep)?;
+        f.sync_data()?;
+    }
+    Ok(len - keep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+    use std::thread;
+
+    // 자식 프로세스(kill_target_writer)가 쓸 디렉터리
+    const CHILD_DIR_ENV: &str = "SWIFTLOG_WRITER_CHILD_DIR";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let d = std::env::temp_dir().join(format!("swiftlog_writer_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&d);
+        d
+    }
+
+    fn line(i: usize) -> String { format!("{i}\t2\t{}\t{}\n", i % 65536, "x".repeat(i % 700)) }
+
+    #[test]
+    fn fsync_policy_parses_and_round_trips() {
+        for s in ["never", "records:100", "ms:250"] {
+            assert_eq!(FsyncPolicy::parse(s).unwrap().to_string(), s);
+        }
+        for s in ["always", "records:0", "ms:", "ms:x", "seconds:1"] {
+            assert!(FsyncPolicy::parse(s).is_none(), "{s}");
+        }
+    }
+
+    #[test]
+    fn buffered_lines_reach_file_on_sync_and_rotation() {
+        let dir = temp_dir("sync");
+        let mut w = LogWriter::open(dir.to_str().unwrap(), "app", 50).unwrap().with_fsync(FsyncPolicy::Records(3));
+        let path = dir.join("app.log");
+        w.write_block(b"1\t2\t1\ta\n2\t2\t1\tb\n").unwrap();
+        // 버퍼에만 있음
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+        w.write_block(b"3\t2\t1\tc\n").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap().len(), 24);
+
+        w.write_block(&b"4\t2\t1\td\n".repeat(4)).unwrap();
+        w.rotate_if_needed().unwrap();
+        let rotated: Vec<_> = std::fs::read_dir(&dir).unwrap().filter_map(|e| e.ok())
+            .filter(|e| e.file_name() != "app.log").collect();
+        assert_eq!(rotated.len(), 1);
+        assert_eq!(std::fs::read(rotated[0].path()).unwrap().len(), 56);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reopened_file_counts_existing_bytes_and_rotates_on_new_hour() {
+        let dir = temp_dir("reopen");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        std::fs::write(&path, b"1\t2\t1\told\n".repeat(4)).unwrap(); // 44바이트
+        let rotated = |dir: &Path| rotation::rotated_files(dir, "app").unwrap();
+
+        // 크기: 이어 쓴 12바이트로 50을 넘김
+        let mut w = LogWriter::open(dir.to_str().unwrap(), "app", 50).unwrap();
+        w.write_block(b"2\t2\t1\tnew\n").unwrap();
+        w.rotate_if_needed().unwrap();
+        assert_eq!(rotated(&dir).len(), 1);
+        drop(w);
+
+        // 시각: 두 시간 전에 마지막으로 쓴 파일은 열자마자 다음 점검에서 롤링
+        std::fs::write(&path, b"3\t2\t1\tstale\n").unwrap();
+        let two_hours_ago = std::time::SystemTime::now() - Duration::from_secs(7200);
+        File::options().write(true).open(&path).unwrap().set_modified(two_hours_ago).unwrap();
+        let hourly = RotationConfig { max_file_bytes: 0, every: RotateEvery::Hourly, ..Default::default() };
+        let mut w = LogWriter::open(dir.to_str().unwrap(), "app", 0).unwrap().with_rotation(&hourly).unwrap();
+        w.rotate_if_needed().unwrap();
+        let files = rotated(&dir);
+        assert_eq!(files.len(), 2);
+        assert_eq!(std::fs::read(&files[1].path).unwrap(), b"3\t2\t1\tstale\n");
+        // 같은 구간 안에서는 다시 롤링하지 않음
+        w.write_block(b"4\t2\t1\tfresh\n").unwrap();
+        w.rotate_if_needed().unwrap();
+        assert_eq!(rotated(&dir).len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_rename_keeps_counting_and_retries() {
+        let dir = temp_dir("rename_fail");
+        let mut w = LogWriter::open(dir.to_str().unwrap(), "app", 10).unwrap();
+        w.write_block(b"1\t2\t1\tabcdef\n").unwrap();
+        // 디렉터리가 사라지면 rename이 실패 (열린 파일에는 계속 쓸 수 있음)
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(w.rotate_if_needed().is_err());
+        assert_eq!(w.written, 13);
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.log"), b"").unwrap();
+        w.rotate_if_needed().unwrap();
+        assert_eq!((w.written, rotation::rotated_files(&dir, "app").unwrap().len()), (0, 1));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // 자식 프로세스 역할: 죽을 때까지 기록 (부모 테스트가 --ignored로 띄워 SIGKILL). 혼자서는 돌지 않음
+    #[test]
+    #[ignore = "child process for killed_mid_write_leaves_only_whole_lines_after_reopen"]
+    fn kill_target_writer() {
+        let Ok(dir) = std::env::var(CHILD_DIR_ENV) else { return };
+        let mut w = LogWriter::open(&dir, "app", u64::MAX).unwrap().with_fsync(FsyncPolicy::Records(16));
+        for i in 0.. {
+            w.write_block(line(i).as_bytes()).unwrap();
+            w.tick().unwrap();
+        }
+    }
+
+    #[test]
+    fn killed_mid_write_leaves_only_whole_lines_after_reopen() {
+        let dir = temp_dir("kill");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let mut child = Command::new(std::env::current_exe().unwrap())
+            .args(["--ignored", "--exact", "writer::tests::kill_target_writer", "--nocapture", "--test-threads=1"])
+            .env(CHILD_DIR_ENV, &dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0) < 256 * 1024 {
+            assert!(Instant::now() < deadline, "child wrote nothing");
+            thread::sleep(Duration::from_millis(5));
+        }
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // 쓰기 syscall 도중에 죽은 경우를 흉내: 반쪽 줄을 덧붙임
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        f.write_all(b"999999\t2\t1\thalf a li").unwrap();
+        drop(f);
+
+        drop(LogWriter::open(dir.to_str().unwrap(), "app", u64::MAX).unwrap());
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.ends_with('\n'));
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.len() > 100);
+        for (i, l) in lines.iter().enumerate() {
+            assert_eq!(format!("{l}\n"), line(i), "line {i}");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,372 @@
+// 제로카피 디코더: 입력 버퍼를 빌려서 레코드를 슬라이스로 돌려줍니다.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::fields::{TYPE_BOOL, TYPE_F64, TYPE_I64, TYPE_STR};
+use crate::{
+    crc32c, header_len, peek_magic, FieldValueRef, LogLevel, FLAG_FIELDS, KNOWN_FLAGS, MAGIC,
+    RECORD_FIXED_LEN, VERSION_V1,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// 헤더조차 다 오지 않음
+    TooShort { len: usize },
+    BadMagic(u32),
+    UnsupportedVersion(u16),
+    /// v2 헤더에 이 빌드가 모르는 flag 비트가 켜져 있음
+    UnsupportedFlags(u16),
+    /// v2 페이로드 CRC-32C 불일치
+    ChecksumMismatch { expected: u32, actual: u32 },
+    /// `offset`(배치 시작 기준)에서 레코드가 잘림
+    Truncated { offset: usize },
+    /// 헤더의 count와 실제 레코드 수가 다름
+    CountMismatch { header: u32, actual: u32 },
+    /// `offset`의 레코드에 잘못된 필드(알 수 없는 타입, UTF-8 아님)가 있음
+    InvalidField { offset: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::TooShort { len } => write!(f, "batch too short ({len} bytes)"),
+            DecodeError::BadMagic(m) => write!(f, "bad magic 0x{m:08X}"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            DecodeError::UnsupportedFlags(fl) => write!(f, "unsupported flags 0x{fl:04X}"),
+            DecodeError::ChecksumMismatch { expected, actual } =>
+                write!(f, "crc32c mismatch (header 0x{expected:08X}, payload 0x{actual:08X})"),
+            DecodeError::Truncated { offset } => write!(f, "truncated record at offset {offset}"),
+            DecodeError::CountMismatch { header, actual } =>
+                write!(f, "record count mismatch (header {header}, actual {actual})"),
+            DecodeError::InvalidField { offset } => write!(f, "invalid field in record at offset {offset}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Batch<'a> {
+    version: u16,
+    flags: u16,
+    count: u32,
+    seq: Option<u64>,
+    body: &'a [u8],
+}
+
+impl<'a> Batch<'a> {
+    /// 헤더, 체크섬(v2), 레코드 경계와 count까지 모두 검증합니다.
+    /// 성공하면 `records()`는 더 이상 실패하지 않습니다.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < 6 {
+            return Err(DecodeError::TooShort { len: bytes.len() });
+        }
+        let magic = peek_magic(bytes).unwrap_or(0);
+        if magic != MAGIC { return Err(DecodeError::BadMagic(magic)); }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        let hl = header_len(version).ok_or(DecodeError::UnsupportedVersion(version))?;
+        if bytes.len() < hl {
+            return Err(DecodeError::TooShort { len: bytes.len() });
+        }
+        let body = &bytes[hl..];
+
+        let batch = if version == VERSION_V1 {
+            let count = u16::from_le_bytes([bytes[6], bytes[7]]) as u32;
+            Self { version, flags: 0, count, seq: None, body }
+        } else {
+            let flags = u16::from_le_bytes([bytes[6], bytes[7]]);
+            if flags & !KNOWN_FLAGS != 0 { return Err(DecodeError::UnsupportedFlags(flags)); }
+            let count = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+            let seq = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
+            let expected = u32::from_le_bytes(bytes[20..24].try_into().unwrap());
+            let actual = crc32c(body);
+            if expected != actual {
+                return Err(DecodeError::ChecksumMismatch { expected, actual });
+            }
+            Self { version, flags, count, seq: Some(seq), body }
+        };
+
+        // 레코드 경계(+필드) 검증
+        let has_fields = batch.flags & FLAG_FIELDS != 0;
+        let mut pos = 0usize;
+        let mut actual = 0u32;
+        while pos < body.len() {
+            let (rec, next) = read_record(body, pos, has_fields)
+                .ok_or(DecodeError::Truncated { offset: hl + pos })?;
+            if !rec.fields.validate() {
+                return Err(DecodeError::InvalidField { offset: hl + pos });
+            }
+            pos = next;
+            actual = actual.saturating_add(1);
+        }
+        if actual != batch.count {
+            return Err(DecodeError::CountMismatch { header: batch.count, actual });
+        }
+        Ok(batch)
+    }
+
+    pub fn version(&self) -> u16 { self.version }
+    pub fn flags(&self) -> u16 { self.flags }
+    /// 헤더의 레코드 수 (검증을 통과했으므로 실제 수와 같음)
+    pub fn count(&self) -> u32 { self.count }
+    /// 송신측 배치 순번 (v2 전용)
+    pub fn seq(&self) -> Option<u64> { self.seq }
+
+    pub fn records(&self) -> Records<'a> {
+        Records { body: self.body, pos: 0, has_fields: self.flags & FLAG_FIELDS != 0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordRef<'a> {
+    pub ts_ms: u64,
+    /// 와이어에 실린 그대로의 레벨 값 (범위 밖일 수 있음)
+    pub level_raw: u8,
+    pub code: u16,
+    pub msg: &'a [u8],
+    pub fields: Fields<'a>,
+}
+
+impl<'a> RecordRef<'a> {
+    pub fn level(&self) -> LogLevel { LogLevel::from_u8_lossy(self.level_raw) }
+    pub fn msg_lossy(&self) -> Cow<'a, str> { String::from_utf8_lossy(self.msg) }
+}
+
+/// 검증된 배치의 레코드 순회자
+pub struct Records<'a> {
+    body: &'a [u8],
+    pos: usize,
+    has_fields: bool,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = RecordRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (rec, next) = read_record(self.body, self.pos, self.has_fields)?;
+        self.pos = next;
+        Some(rec)
+    }
+}
+
+// body[pos..]에서 레코드 1건을 읽고 다음 위치를 돌려줌. 끝이거나 잘렸으면 None.
+// 필드 블록은 경계만 확인하고, 내용 검증은 `Fields::validate`에서 합니다.
+fn read_record(b: &[u8], p: usize, has_fields: bool) -> Option<(RecordRef<'_>, usize)> {
+    let fixed = b.get(p..p.checked_add(RECORD_FIXED_LEN)?)?;
+    let ts_ms = u64::from_le_bytes(fixed[0..8].try_into().ok()?);
+    let level_raw = fixed[8];
+    let code = u16::from_le_bytes([fixed[9], fixed[10]]);
+    let len = u16::from_le_bytes([fixed[11], fixed[12]]) as usize;
+    let start = p + RECORD_FIXED_LEN;
+    let msg = b.get(start..start + len)?;
+    let mut end = start + len;
+    let fields = if has_fields {
+        let used = fields_block_len(b.get(end..)?)?;
+        let f = Fields { raw: &b[end..end + used] };
+        end += used;
+        f
+    } else {
+        Fields::EMPTY
+    };
+    Some((RecordRef { ts_ms, level_raw, code, msg, fields }, end))
+}
+
+// field_count 포함 필드 블록 길이 (잘렸으면 None)
+fn fields_block_len(b: &[u8]) -> Option<usize> {
+    let n = *b.first()? as usize;
+    let mut p = 1usize;
+    for _ in 0..n {
+        let (_, _, _, next) = read_field(b, p)?;
+        p = next;
+    }
+    Some(p)
+}
+
+// b[p..]의 필드 1개 → (key bytes, type, value bytes, 다음 위치)
+fn read_field(b: &[u8], p: usize) -> Option<(&[u8], u8, &[u8], usize)> {
+    let klen = *b.get(p)? as usize;
+    let key = b.get(p + 1..p + 1 + klen)?;
+    let mut q = p + 1 + klen;
+    let ty = *b.get(q)?;
+    q += 1;
+    let vlen = match ty {
+        TYPE_STR => { let l = u16::from_le_bytes(b.get(q..q + 2)?.try_into().ok()?) as usize; q += 2; l }
+        TYPE_I64 | TYPE_F64 => 8,
+        TYPE_BOOL => 1,
+        _ => 0, // 알 수 없는 타입: 경계는 여기서 끊고 validate에서 거부
+    };
+    let val = b.get(q..q + vlen)?;
+    Some((key, ty, val, q + vlen))
+}
+
+/// 레코드의 구조화 필드 (제로카피). 배치 검증을 통과했다면 순회는 실패하지 않습니다.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fields<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    const EMPTY: Fields<'static> = Fields { raw: &[] };
+
+    pub fn len(&self) -> usize { self.raw.first().copied().unwrap_or(0) as usize }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, FieldValueRef<'a>)> + 'a {
+        let raw = self.raw;
+        let mut p = 1usize;
+        (0..self.len()).map_while(move |_| {
+            let (k, v, next) = decode_field(raw, p)?;
+            p = next;
+            Some((k, v))
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<FieldValueRef<'a>> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    fn validate(&self) -> bool {
+        self.iter().count() == self.len()
+    }
+
+    /// `b` 앞쪽의 필드 블록을 검증해 (필드, 사용한 바이트 수)로. 잘렸거나 잘못됐으면 None
+    pub(crate) fn split(b: &'a [u8]) -> Option<(Fields<'a>, usize)> {
+        let used = fields_block_len(b)?;
+        let f = Fields { raw: &b[..used] };
+        f.validate().then_some((f, used))
+    }
+}
+
+fn decode_field(b: &[u8], p: usize) -> Option<(&str, FieldValueRef<'_>, usize)> {
+    let (key, ty, val, next) = read_field(b, p)?;
+    let key = std::str::from_utf8(key).ok()?;
+    let v = match ty {
+        TYPE_STR => FieldValueRef::Str(std::str::from_utf8(val).ok()?),
+        TYPE_I64 => FieldValueRef::I64(i64::from_le_bytes(val.try_into().ok()?)),
+        TYPE_F64 => FieldValueRef::F64(f64::from_le_bytes(val.try_into().ok()?)),
+        TYPE_BOOL => FieldValueRef::Bool(val[0] != 0),
+        _ => return None,
+    };
+    Some((key, v, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BatchEncoder, VERSION_V2};
+
+    fn sample(version: u16) -> Vec<u8> {
+        let mut enc = BatchEncoder::new(version);
+        enc.set_seq(7);
+        enc.push(1_000, LogLevel::Info, 1001, b"hello");
+        enc.push(2_000, LogLevel::Error, 5001, "한글 메시지".as_bytes());
+        enc.push(3_000, LogLevel::Trace, 0, b"");
+        enc.finish().to_vec()
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        for version in [VERSION_V1, VERSION_V2] {
+            let bytes = sample(version);
+            let batch = Batch::parse(&bytes).unwrap();
+            assert_eq!(batch.version(), version);
+            assert_eq!(batch.count(), 3);
+            assert_eq!(batch.seq(), (version == VERSION_V2).then_some(7));
+
+            let recs: Vec<_> = batch.records().collect();
+            assert_eq!(recs.len(), 3);
+            assert_eq!((recs[0].ts_ms, recs[0].level_raw, recs[0].code, recs[0].msg), (1_000, 2, 1001, &b"hello"[..]));
+            assert_eq!(recs[1].level(), LogLevel::Error);
+            assert_eq!(recs[1].msg_lossy(), "한글 메시지");
+            assert!(recs[2].msg.is_empty());
+        }
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        assert_eq!(Batch::parse(&[0u8; 4]).unwrap_err(), DecodeError::TooShort { len: 4 });
+
+        let mut bytes = sample(VERSION_V1);
+        bytes[0] ^= 0xFF;
+        assert!(matches!(Batch::parse(&bytes), Err(DecodeError::BadMagic(_))));
+
+        let mut bytes = sample(VERSION_V1);
+        bytes[4] = 99;
+        assert_eq!(Batch::parse(&bytes).unwrap_err(), DecodeError::UnsupportedVersion(99));
+
+        let mut bytes = sample(VERSION_V2);
+        bytes[7] = 0x80;
+        assert_eq!(Batch::parse(&bytes).unwrap_err(), DecodeError::UnsupportedFlags(0x8000 | crate::FLAG_FIELDS));
+    }
+
+    #[test]
+    fn truncated_batch_is_rejected_whole() {
+        let bytes = sample(VERSION_V1);
+        let cut = &bytes[..bytes.len() - 3];
+        assert!(matches!(Batch::parse(cut), Err(DecodeError::Truncated { .. })));
+
+        let bytes = sample(VERSION_V2);
+        let cut = &bytes[..bytes.len() - 3];
+        assert!(matches!(Batch::parse(cut), Err(DecodeError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn corrupt_payload_and_count_are_rejected() {
+        let mut bytes = sample(VERSION_V2);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        assert!(matches!(Batch::parse(&bytes), Err(DecodeError::ChecksumMismatch { .. })));
+
+        let mut bytes = sample(VERSION_V1);
+        bytes[6] = 5;
+        assert_eq!(Batch::parse(&bytes).unwrap_err(), DecodeError::CountMismatch { header: 5, actual: 3 });
+    }
+
+    #[test]
+    fn fields_roundtrip_v2_and_dropped_in_v1() {
+        let fields = [
+            ("user_id", FieldValueRef::I64(42)),
+            ("region", FieldValueRef::Str("eu")),
+            ("ratio", FieldValueRef::F64(0.25)),
+            ("ok", FieldValueRef::Bool(true)),
+        ];
+        for version in [VERSION_V1, VERSION_V2] {
+            let mut enc = BatchEncoder::new(version);
+            enc.push_with_fields(1, LogLevel::Info, 7, b"login", &fields);
+            enc.push(2, LogLevel::Info, 8, b"plain");
+            let bytes = enc.finish().to_vec();
+
+            let batch = Batch::parse(&bytes).unwrap();
+            let recs: Vec<_> = batch.records().collect();
+            if version == VERSION_V2 {
+                assert_eq!(recs[0].fields.iter().collect::<Vec<_>>(), fields);
+                assert_eq!(recs[0].fields.get("region"), Some(FieldValueRef::Str("eu")));
+            } else {
+                assert!(recs[0].fields.is_empty());
+            }
+            assert!(recs[1].fields.is_empty());
+            assert_eq!(recs[1].msg, b"plain");
+        }
+    }
+
+    #[test]
+    fn invalid_field_type_is_rejected() {
+        let mut enc = BatchEncoder::new(VERSION_V2);
+        enc.push_with_fields(1, LogLevel::Info, 7, b"m", &[("k", FieldValueRef::Bool(true))]);
+        let mut bytes = enc.finish().to_vec();
+        // 필드 타입 바이트: header(24) + fixed(13) + msg(1) + count(1) + klen(1) + key(1)
+        bytes[24 + 13 + 1 + 3] = 9;
+        let crc = crc32c(&bytes[24..]);
+        bytes[20..24].copy_from_slice(&crc.to_le_bytes());
+        assert!(matches!(Batch::parse(&bytes), Err(DecodeError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn version_negotiation() {
+        use crate::{negotiate_version, MAX_VERSION};
+        assert_eq!(negotiate_version(1, 9), Some(MAX_VERSION));
+        assert_eq!(negotiate_version(1, 1), Some(VERSION_V1));
+        assert_eq!(negotiate_version(MAX_VERSION + 1, MAX_VERSION + 5), None);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::fields::{TYPE_BOOL, TYPE_F64, TYPE_I64, TYPE_STR};
+use crate::{
+    crc32c, header_len, FieldValueRef, LogLevel, FLAG_FIELDS, MAGIC, MAX_FIELDS, MAX_FIELD_KEY_LEN,
+    MAX_FIELD_STR_LEN, MAX_MSG_LEN, RECORD_FIXED_LEN, VERSION_V1,
+};
+
+/// 배치 누적 버퍼. 헤더를 먼저 써두고 `finish()`에서 count(v2는 seq/crc까지)를 패치합니다.
+/// v2 배치는 항상 `FLAG_FIELDS`로 인코딩합니다 (필드 없는 레코드는 field_count=0 1바이트).
+pub struct BatchEncoder {
+    buf: Vec<u8>,
+    version: u16,
+    flags: u16,
+    count: u32,
+    seq: u64,
+}
+
+impl BatchEncoder {
+    pub fn new(version: u16) -> Self {
+        Self::with_capacity(version, 1400)
+    }
+
+    /// 지원하지 않는 버전을 주면 패닉합니다.
+    pub fn with_capacity(version: u16, cap: usize) -> Self {
+        assert!(header_len(version).is_some(), "unsupported batch version {version}");
+        let mut e = Self { buf: Vec::with_capacity(cap), version, flags: 0, count: 0, seq: 0 };
+        e.set_flags();
+        e.clear();
+        e
+    }
+
+    pub fn version(&self) -> u16 { self.version }
+
+    /// 다음 배치부터 적용 (현재 버퍼는 비웁니다)
+    pub fn set_version(&mut self, version: u16) {
+        assert!(header_len(version).is_some(), "unsupported batch version {version}");
+        self.version = version;
+        self.set_flags();
+        self.clear();
+    }
+
+    fn set_flags(&mut self) {
+        self.flags = if self.version == VERSION_V1 { 0 } else { FLAG_FIELDS };
+    }
+
+    fn has_fields(&self) -> bool { self.flags & FLAG_FIELDS != 0 }
+
+    /// v2 배치 순번. `finish()` 시점의 값이 헤더에 기록됩니다 (v1에서는 무시).
+    pub fn set_seq(&mut self, seq: u64) { self.seq = seq; }
+    pub fn seq(&self) -> u64 { self.seq }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.count = 0;
+        self.buf.extend_from_slice(&MAGIC.to_le_bytes());
+        self.buf.extend_from_slice(&self.version.to_le_bytes());
+        if self.version == VERSION_V1 {
+            self.buf.extend_from_slice(&0u16.to_le_bytes()); // count placeholder
+        } else {
+            self.buf.extend_from_slice(&self.flags.to_le_bytes());
+            self.buf.extend_from_slice(&0u32.to_le_bytes()); // count placeholder
+            self.buf.extend_from_slice(&0u64.to_le_bytes()); // seq placeholder
+            self.buf.extend_from_slice(&0u32.to_le_bytes()); // crc placeholder
+        }
+    }
+
+    pub fn count(&self) -> u32 { self.count }
+    pub fn is_empty(&self) -> bool { self.count == 0 }
+    /// 헤더 포함 현재 바이트 수
+    pub fn len(&self) -> usize { self.buf.len() }
+
+    /// 배치 하나에 담을 수 있는 최대 레코드 수 (v1은 u16 count)
+    pub fn max_records(&self) -> u32 {
+        if self.version == VERSION_V1 { u16::MAX as u32 } else { u32::MAX }
+    }
+
+    pub fn is_full(&self) -> bool { self.count >= self.max_records() }
+
+    /// 필드 없는 레코드(메시지 길이 `msg_len`)가 차지할 최대 바이트 수
+    #[inline]
+    pub fn record_len(msg_len: usize) -> usize {
+        RECORD_FIXED_LEN + msg_len.min(MAX_MSG_LEN) + 1
+    }
+
+    /// 필드 블록이 차지할 바이트 수 (field_count 1바이트 포함, 한도 초과분 제외)
+    pub fn fields_len(fields: &[(&str, FieldValueRef<'_>)]) -> usize {
+        1 + fields.iter().filter(|(k, _)| k.len() <= MAX_FIELD_KEY_LEN).take(MAX_FIELDS)
+            .map(|(k, v)| 1 + k.len() + 1 + match v {
+                FieldValueRef::Str(s) => 2 + s.len().min(MAX_FIELD_STR_LEN),
+                FieldValueRef::I64(_) | FieldValueRef::F64(_) => 8,
+                FieldValueRef::Bool(_) => 1,
+            })
+            .sum::<usize>()
+    }
+
+    /// 레코드 1건 추가. 메시지는 `MAX_MSG_LEN`에서 잘립니다.
+    /// 배치가 이미 가득 찼으면 추가하지 않고 false.
+    pub fn push(&mut self, ts_ms: u64, level: LogLevel, code: u16, msg: &[u8]) -> bool {
+        self.push_with_fields(ts_ms, level, code, msg, &[])
+    }
+
+    /// 필드 포함 레코드 추가. v1 배치는 필드를 실을 수 없어 버립니다.
+    /// 키가 255바이트를 넘는 필드와 255개를 넘는 필드는 빠지고, 문자열 값은 65535바이트에서 잘립니다.
+    pub fn push_with_fields(
+        &mut self,
+        ts_ms: u64,
+        level: LogLevel,
+        code: u16,
+        msg: &[u8],
+        fields: &[(&str, FieldValueRef<'_>)],
+    ) -> bool {
+        if self.is_full() { return false; }
+        let len = msg.len().min(MAX_MSG_LEN);
+        self.buf.extend_from_slice(&ts_ms.to_le_bytes());
+        self.buf.push(level as u8);
+        self.buf.extend_from_slice(&code.to_le_bytes());
+        self.buf.extend_from_slice(&(len as u16).to_le_bytes());
+        self.buf.extend_from_slice(&msg[..len]);
+        if self.has_fields() {
+            self.write_fields(fields);
+        }
+        self.count += 1;
+        true
+    }
+
+    fn write_fields(&mut self, fields: &[(&str, FieldValueRef<'_>)]) {
+        put_fields(&mut self.buf, fields);
+    }
+
+    /// 헤더를 완성하고 배치 바이트를 돌려줍니다.
+    pub fn finish(&mut self) -> &[u8] {
+        if self.version == VERSION_V1 {
+            self.buf[6..8].copy_from_slice(&(self.count as u16).to_le_bytes());
+        } else {
+            let hl = header_len(self.version).unwrap_or(self.buf.len());
+            let crc = crc32c(&self.buf[hl..]);
+            self.buf[8..12].copy_from_slice(&self.count.to_le_bytes());
+            self.buf[12..20].copy_from_slice(&self.seq.to_le_bytes());
+            self.buf[20..24].copy_from_slice(&crc.to_le_bytes());
+        }
+        &self.buf
+    }
+}
+
+/// 필드 블록(count u8 + 필드들)을 `buf` 뒤에 씁니다. 255개 / 긴 키는 버리고 긴 문자열은 자릅니다.
+pub(crate) fn put_fields(buf: &mut Vec<u8>, fields: &[(&str, FieldValueRef<'_>)]) {
+    let kept: Vec<_> = fields.iter().filter(|(k, _)| k.len() <= MAX_FIELD_KEY_LEN).take(MAX_FIELDS).collect();
+    buf.push(kept.len() as u8);
+    for (k, v) in kept {
+        buf.push(k.len() as u8);
+        buf.extend_from_slice(k.as_bytes());
+        match *v {
+            FieldValueRef::Str(s) => {
+                let s = truncate_utf8(s, MAX_FIELD_STR_LEN);
+                buf.push(TYPE_STR);
+                buf.extend_from_slice(&(s.len() as u16).to_le_bytes());
+                buf.extend_from_slice(s.as_bytes());
+            }
+            FieldValueRef::I64(x) => {
+                buf.push(TYPE_I64);
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            FieldValueRef::F64(x) => {
+                buf.push(TYPE_F64);
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            FieldValueRef::Bool(x) => {
+                buf.push(TYPE_BOOL);
+                buf.push(x as u8);
+            }
+        }
+    }
+}
+
+pub(crate) fn truncate_utf8(s: &str, max: usize) -> &str {
+    if s.len() <= max { return s; }
+    let mut end = max;
+    while !s.is_char_boundary(end) { end -= 1; }
+    &s[..end]
+}
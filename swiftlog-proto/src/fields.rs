@@ -0,0 +1,246 @@
+// 구조화 필드 (key=value) — 레코드 메시지와 별도로 타입을 유지합니다.
+//
+// 와이어 (v2 + FLAG_FIELDS, msg 바로 뒤)
+//   field_count u8
+//   Field * N : key_len u8 | key utf8 | type u8 | value
+//     type 0 = str  : len u16 | utf8
+//     type 1 = i64  : 8 bytes
+//     type 2 = f64  : 8 bytes (IEEE754 LE)
+//     type 3 = bool : 1 byte
+//
+// 텍스트 (TSV 필드 컬럼, 콘솔 출력)
+//   user_id=42 region="eu" ratio=0.5 ok=true   (문자열만 따옴표 + 백슬래시 이스케이프)
+//   키도 비었거나 공백/제어문자/`=`/`"`/`\`가 있으면 같은 방식으로 따옴표: "a b"=1
+
+use std::fmt;
+
+pub const MAX_FIELDS: usize = u8::MAX as usize;
+pub const MAX_FIELD_KEY_LEN: usize = u8::MAX as usize;
+pub const MAX_FIELD_STR_LEN: usize = u16::MAX as usize;
+
+pub(crate) const TYPE_STR: u8 = 0;
+pub(crate) const TYPE_I64: u8 = 1;
+pub(crate) const TYPE_F64: u8 = 2;
+pub(crate) const TYPE_BOOL: u8 = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Str(String),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+}
+
+/// 디코더가 내주는 빌린 값, 인코더 입력
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldValueRef<'a> {
+    Str(&'a str),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+}
+
+impl FieldValue {
+    pub fn as_ref(&self) -> FieldValueRef<'_> {
+        match self {
+            FieldValue::Str(s) => FieldValueRef::Str(s),
+            FieldValue::I64(v) => FieldValueRef::I64(*v),
+            FieldValue::F64(v) => FieldValueRef::F64(*v),
+            FieldValue::Bool(v) => FieldValueRef::Bool(*v),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str { self.as_ref().type_name() }
+
+    /// 쿼리 문자열과 비교: 필드 타입에 맞춰 `text`를 해석합니다.
+    /// (`field.user_id=42`는 I64(42), F64(42.0), Str("42") 모두와 일치)
+    pub fn eq_text(&self, text: &str) -> bool {
+        match self {
+            FieldValue::Str(s) => s == text.trim_matches('"'),
+            FieldValue::I64(v) => text.parse::<i64>().map(|t| t == *v).unwrap_or(false),
+            FieldValue::F64(v) => text.parse::<f64>().map(|t| t == *v).unwrap_or(false),
+            FieldValue::Bool(v) => text.parse::<bool>().map(|t| t == *v).unwrap_or(false),
+        }
+    }
+}
+
+impl<'a> FieldValueRef<'a> {
+    pub fn to_owned(self) -> FieldValue {
+        match self {
+            FieldValueRef::Str(s) => FieldValue::Str(s.to_string()),
+            FieldValueRef::I64(v) => FieldValue::I64(v),
+            FieldValueRef::F64(v) => FieldValue::F64(v),
+            FieldValueRef::Bool(v) => FieldValue::Bool(v),
+        }
+    }
+
+    pub fn type_name(self) -> &'static str {
+        match self {
+            FieldValueRef::Str(_) => "str",
+            FieldValueRef::I64(_) => "i64",
+            FieldValueRef::F64(_) => "f64",
+            FieldValueRef::Bool(_) => "bool",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for FieldValueRef<'a> { fn from(v: &'a str) -> Self { FieldValueRef::Str(v) } }
+impl From<i64> for FieldValueRef<'_> { fn from(v: i64) -> Self { FieldValueRef::I64(v) } }
+impl From<i32> for FieldValueRef<'_> { fn from(v: i32) -> Self { FieldValueRef::I64(v as i64) } }
+impl From<u32> for FieldValueRef<'_> { fn from(v: u32) -> Self { FieldValueRef::I64(v as i64) } }
+impl From<f64> for FieldValueRef<'_> { fn from(v: f64) -> Self { FieldValueRef::F64(v) } }
+impl From<bool> for FieldValueRef<'_> { fn from(v: bool) -> Self { FieldValueRef::Bool(v) } }
+
+impl fmt::Display for FieldValueRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValueRef::Str(s) => {
+                f.write_str("\"")?;
+                for ch in s.chars() {
+                    match ch {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        _ => write!(f, "{ch}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            FieldValueRef::I64(v) => write!(f, "{v}"),
+            // {:?}는 항상 소수점/지수를 남겨 i64와 구분됨 (1.0, 1e300, NaN, inf)
+            FieldValueRef::F64(v) => write!(f, "{v:?}"),
+            FieldValueRef::Bool(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.as_ref().fmt(f) }
+}
+
+/// `k=v k=v` 텍스트로 직렬화
+pub fn format_fields<'a, I>(fields: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, FieldValueRef<'a>)>,
+{
+    let mut out = String::new();
+    for (k, v) in fields {
+        if !out.is_empty() { out.push(' '); }
+        if key_needs_quotes(k) {
+            out.push_str(&FieldValueRef::Str(k).to_string());
+        } else {
+            out.push_str(k);
+        }
+        out.push('=');
+        out.push_str(&v.to_string());
+    }
+    out
+}
+
+fn key_needs_quotes(k: &str) -> bool {
+    k.is_empty() || k.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '=' | '"' | '\\'))
+}
+
+/// `format_fields`의 역. 타입은 값 모양으로 복원합니다
+/// (따옴표 → str, true/false → bool, 소수점·지수·inf·NaN → f64, 그 외 정수 → i64).
+pub fn parse_fields(s: &str) -> Result<Vec<(String, FieldValue)>, String> {
+    let mut out = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let key = if let Some(quoted) = rest.strip_prefix('"') {
+            let (k, used) = unquote(quoted)?;
+            rest = quoted[used..].strip_prefix('=').ok_or_else(|| format!("field without '=': {rest}"))?;
+            k
+        } else {
+            let eq = rest.find('=').ok_or_else(|| format!("field without '=': {rest}"))?;
+            let key = &rest[..eq];
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(format!("invalid field key: {key:?}"));
+            }
+            rest = &rest[eq + 1..];
+            key.to_string()
+        };
+        let (value, tail) = if let Some(quoted) = rest.strip_prefix('"') {
+            let (v, used) = unquote(quoted)?;
+            (FieldValue::Str(v), &quoted[used..])
+        } else {
+            let end = rest.find(' ').unwrap_or(rest.len());
+            (parse_scalar(&rest[..end])?, &rest[end..])
+        };
+        out.push((key, value));
+        rest = tail.trim_start();
+    }
+    Ok(out)
+}
+
+fn parse_scalar(t: &str) -> Result<FieldValue, String> {
+    match t {
+        "true" => return Ok(FieldValue::Bool(true)),
+        "false" => return Ok(FieldValue::Bool(false)),
+        _ => {}
+    }
+    if let Ok(v) = t.parse::<i64>() { return Ok(FieldValue::I64(v)); }
+    t.parse::<f64>().map(FieldValue::F64).map_err(|_| format!("invalid field value: {t:?}"))
+}
+
+// 여는 따옴표 다음부터 닫는 따옴표까지. 반환: (값, 닫는 따옴표 포함 소비 바이트)
+fn unquote(s: &str) -> Result<(String, usize), String> {
+    let mut out = String::new();
+    let mut it = s.char_indices();
+    while let Some((i, ch)) = it.next() {
+        match ch {
+            '"' => return Ok((out, i + 1)),
+            '\\' => match it.next().map(|(_, c)| c) {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some(c @ ('"' | '\\')) => out.push(c),
+                other => return Err(format!("invalid escape: \\{}", other.unwrap_or(' '))),
+            },
+            _ => out.push(ch),
+        }
+    }
+    Err("unterminated string field".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_roundtrip_keeps_types() {
+        let fields = vec![
+            ("user_id".to_string(), FieldValue::I64(42)),
+            ("region".to_string(), FieldValue::Str("eu \"west\"\t1".into())),
+            ("ratio".to_string(), FieldValue::F64(1.0)),
+            ("ok".to_string(), FieldValue::Bool(true)),
+        ];
+        let text = format_fields(fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())));
+        assert_eq!(text, r#"user_id=42 region="eu \"west\"\t1" ratio=1.0 ok=true"#);
+        assert_eq!(parse_fields(&text).unwrap(), fields);
+    }
+
+    #[test]
+    fn keys_with_spaces_equals_or_quotes_are_quoted() {
+        let fields = vec![
+            ("a b".to_string(), FieldValue::I64(1)),
+            ("x=\"y\"".to_string(), FieldValue::Bool(false)),
+            (String::new(), FieldValue::Str("v".into())),
+        ];
+        let text = format_fields(fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())));
+        assert_eq!(text, r#""a b"=1 "x=\"y\""=false ""="v""#);
+        assert_eq!(parse_fields(&text).unwrap(), fields);
+        assert!(parse_fields(r#""a b" 1"#).is_err());
+    }
+
+    #[test]
+    fn eq_text_follows_field_type() {
+        assert!(FieldValue::I64(42).eq_text("42"));
+        assert!(!FieldValue::I64(42).eq_text("42.5"));
+        assert!(FieldValue::F64(0.5).eq_text("0.5"));
+        assert!(FieldValue::Str("eu".into()).eq_text("eu"));
+        assert!(FieldValue::Bool(false).eq_text("false"));
+    }
+}
@@ -0,0 +1,17 @@
+// TCP 프레이밍: [len u32 LE][payload]
+
+pub const FRAME_PREFIX_LEN: usize = 4;
+
+pub fn write_frame(out: &mut Vec<u8>, payload: &[u8]) {
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// 버퍼 앞에서 완성된 프레임 1개를 꺼냅니다.
+/// 반환: (payload, 소비한 바이트 수). 아직 덜 들어왔으면 `None`.
+pub fn next_frame(buf: &[u8]) -> Option<(&[u8], usize)> {
+    let len = u32::from_le_bytes(buf.get(0..FRAME_PREFIX_LEN)?.try_into().ok()?) as usize;
+    let end = FRAME_PREFIX_LEN.checked_add(len)?;
+    let payload = buf.get(FRAME_PREFIX_LEN..end)?;
+    Some((payload, end))
+}
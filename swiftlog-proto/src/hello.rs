@@ -0,0 +1,114 @@
+// TCP 버전 협상
+//   client → server : Hello    = magic u32 ('SLGH') | min_version u16 | max_version u16
+//                               [| service_len u8 | service | host_len u8 | host | pid u32]
+//   server → client : HelloAck = magic u32 ('SLGA') | version u16 (0 = 협상 실패)
+// 둘 다 일반 배치와 같은 [len u32] 프레임에 실립니다.
+// Hello 뒤쪽의 송신자 정보(Identity)는 선택이며, 없으면 8바이트로 끝납니다.
+
+use crate::{peek_magic, DecodeError};
+
+pub const HELLO_MAGIC: u32 = 0x4847_4C53;     // 'SLGH' (LE)
+pub const HELLO_ACK_MAGIC: u32 = 0x4147_4C53; // 'SLGA' (LE)
+
+/// 연결 단위 송신자 정보. 이름은 각각 255바이트에서 잘립니다.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Identity {
+    pub service: String,
+    pub host: String,
+    pub pid: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hello {
+    pub min_version: u16,
+    pub max_version: u16,
+    pub identity: Option<Identity>,
+}
+
+impl Hello {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut b = Vec::with_capacity(64);
+        b.extend_from_slice(&HELLO_MAGIC.to_le_bytes());
+        b.extend_from_slice(&self.min_version.to_le_bytes());
+        b.extend_from_slice(&self.max_version.to_le_bytes());
+        if let Some(id) = &self.identity {
+            put_short_str(&mut b, &id.service);
+            put_short_str(&mut b, &id.host);
+            b.extend_from_slice(&id.pid.to_le_bytes());
+        }
+        b
+    }
+
+    pub fn decode(b: &[u8]) -> Result<Self, DecodeError> {
+        if b.len() < 8 { return Err(DecodeError::TooShort { len: b.len() }); }
+        let magic = peek_magic(b).unwrap_or(0);
+        if magic != HELLO_MAGIC { return Err(DecodeError::BadMagic(magic)); }
+        let identity = if b.len() == 8 { None } else { Some(decode_identity(b, 8)?) };
+        Ok(Self {
+            min_version: u16::from_le_bytes([b[4], b[5]]),
+            max_version: u16::from_le_bytes([b[6], b[7]]),
+            identity,
+        })
+    }
+}
+
+pub(crate) fn put_short_str(b: &mut Vec<u8>, s: &str) {
+    let mut end = s.len().min(u8::MAX as usize);
+    while !s.is_char_boundary(end) { end -= 1; }
+    b.push(end as u8);
+    b.extend_from_slice(&s.as_bytes()[..end]);
+}
+
+fn decode_identity(b: &[u8], mut p: usize) -> Result<Identity, DecodeError> {
+    let short_str = |p: &mut usize| -> Result<String, DecodeError> {
+        let len = *b.get(*p).ok_or(DecodeError::Truncated { offset: *p })? as usize;
+        let raw = b.get(*p + 1..*p + 1 + len).ok_or(DecodeError::Truncated { offset: *p })?;
+        let s = std::str::from_utf8(raw).map_err(|_| DecodeError::InvalidField { offset: *p })?;
+        *p += 1 + len;
+        Ok(s.to_string())
+    };
+    let service = short_str(&mut p)?;
+    let host = short_str(&mut p)?;
+    let pid = b.get(p..p + 4).ok_or(DecodeError::Truncated { offset: p })?;
+    Ok(Identity { service, host, pid: u32::from_le_bytes([pid[0], pid[1], pid[2], pid[3]]) })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HelloAck {
+    /// 0이면 서버가 지원하는 버전과 겹치지 않음
+    pub version: u16,
+}
+
+impl HelloAck {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut b = Vec::with_capacity(6);
+        b.extend_from_slice(&HELLO_ACK_MAGIC.to_le_bytes());
+        b.extend_from_slice(&self.version.to_le_bytes());
+        b
+    }
+
+    pub fn decode(b: &[u8]) -> Result<Self, DecodeError> {
+        if b.len() < 6 { return Err(DecodeError::TooShort { len: b.len() }); }
+        let magic = peek_magic(b).unwrap_or(0);
+        if magic != HELLO_ACK_MAGIC { return Err(DecodeError::BadMagic(magic)); }
+        Ok(Self { version: u16::from_le_bytes([b[4], b[5]]) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello_with_and_without_identity() {
+        let bare = Hello { min_version: 1, max_version: 2, identity: None };
+        assert_eq!(bare.encode().len(), 8);
+        assert_eq!(Hello::decode(&bare.encode()).unwrap(), bare);
+
+        let id = Identity { service: "game-server".into(), host: "gs-07".into(), pid: 4242 };
+        let full = Hello { identity: Some(id), ..bare };
+        let bytes = full.encode();
+        assert_eq!(Hello::decode(&bytes).unwrap(), full);
+        assert!(matches!(Hello::decode(&bytes[..bytes.len() - 1]), Err(DecodeError::Truncated { .. })));
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel { Trace=0, Debug=1, Info=2, Warn=3, Error=4 }
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error,
+    ];
+
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(LogLevel::Trace),
+            1 => Some(LogLevel::Debug),
+            2 => Some(LogLevel::Info),
+            3 => Some(LogLevel::Warn),
+            4 => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+
+    /// 알 수 없는 값은 Error로 취급 (기존 서버/뷰어 동작과 동일)
+    pub fn from_u8_lossy(v: u8) -> Self {
+        Self::from_u8(v).unwrap_or(LogLevel::Error)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "Trace",
+            LogLevel::Debug => "Debug",
+            LogLevel::Info  => "Info",
+            LogLevel::Warn  => "Warn",
+            LogLevel::Error => "Error",
+        }
+    }
+
+    /// 대소문자 무시 ("warn", "WARN", "Warn")
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.as_str().eq_ignore_ascii_case(s))
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
@@ -0,0 +1,91 @@
+// swiftlog-proto/src/lib.rs
+//! SwiftLog 와이어 포맷(SLG1) 공용 크레이트.
+//! 서버, 클라이언트 라이브러리(`Logger`), swl-monitor, swl-viewer가 모두 이 정의만 사용합니다.
+//!
+//! 배치 레이아웃 (모두 LE)
+//! ```text
+//! v1 header : magic u32 ('SLG1') | version u16 (=1) | count u16
+//! v2 header : magic u32 ('SLG1') | version u16 (=2) | flags u16 | count u32 | seq u64 | crc32c u32
+//! Record    : ts_ms u64 | level u8 | code u16 | msg_len u16 | msg bytes [| fields]
+//! ```
+//! v2 flags의 `FLAG_FIELDS`가 켜져 있으면 모든 레코드 msg 뒤에 구조화 필드 블록이 붙습니다
+//! (레이아웃은 `fields` 모듈 참고).
+//! v2의 crc32c는 헤더 뒤 페이로드(레코드 전체)에 대한 CRC-32C, seq는 송신측이 배치마다 1씩 올리는 번호입니다.
+//! 디코더는 배치 전체를 검증한 뒤에만 레코드를 내주므로, 손상된 배치는 부분 반영되지 않습니다.
+//! UDP는 데이터그램 1개 = 배치 1개, TCP는 배치마다 u32 길이 프리픽스로 프레이밍합니다.
+//! TCP 연결은 첫 프레임으로 [`Hello`]를 보내 버전을 협상하고 송신자 정보([`Identity`])를 알릴 수 있습니다(선택).
+//! 실시간 구독은 별도 포트에서 [`Subscribe`] 한 번 → [`SubEvent`] 스트림입니다(`subscribe` 모듈).
+//! 로그 파일/백업의 TSV 줄 규칙(이스케이프 포함)은 [`tsv`] 모듈 하나로 읽고 씁니다.
+
+mod crc32c;
+mod decode;
+mod encode;
+mod fields;
+mod frame;
+mod hello;
+mod level;
+mod subscribe;
+pub mod tsv;
+
+pub use crc32c::crc32c;
+pub use decode::{Batch, DecodeError, Fields, RecordRef, Records};
+pub use encode::BatchEncoder;
+pub use fields::{
+    format_fields, parse_fields, FieldValue, FieldValueRef, MAX_FIELDS, MAX_FIELD_KEY_LEN, MAX_FIELD_STR_LEN,
+};
+pub use frame::{next_frame, write_frame, FRAME_PREFIX_LEN};
+pub use hello::{Hello, HelloAck, Identity, HELLO_ACK_MAGIC, HELLO_MAGIC};
+pub use level::LogLevel;
+pub use subscribe::{SubEvent, SubRecord, Subscribe, EVENT_MAGIC, SUBSCRIBE_MAGIC, SUBSCRIBE_VERSION};
+
+pub const MAGIC: u32 = 0x3147_4C53; // 'SLG1' (LE)
+pub const VERSION_V1: u16 = 1;
+pub const VERSION_V2: u16 = 2;
+
+/// 이 빌드가 인코딩/디코딩할 수 있는 버전 범위
+pub const MIN_VERSION: u16 = VERSION_V1;
+pub const MAX_VERSION: u16 = VERSION_V2;
+
+/// v1: magic(4) + version(2) + count(2)
+pub const BATCH_HEADER_LEN_V1: usize = 8;
+/// v2: magic(4) + version(2) + flags(2) + count(4) + seq(8) + crc32c(4)
+pub const BATCH_HEADER_LEN_V2: usize = 24;
+
+/// v2 flag: 레코드마다 구조화 필드 블록이 있음
+pub const FLAG_FIELDS: u16 = 0x0001;
+
+/// v2 헤더 flags 중 이 빌드가 이해하는 비트. 모르는 비트가 켜진 배치는 거부합니다
+/// (레코드 레이아웃이 달라졌을 수 있으므로).
+pub const KNOWN_FLAGS: u16 = FLAG_FIELDS;
+
+/// ts_ms(8) + level(1) + code(2) + msg_len(2)
+pub const RECORD_FIXED_LEN: usize = 8 + 1 + 2 + 2;
+pub const MAX_MSG_LEN: usize = u16::MAX as usize;
+
+/// 상대가 지원하는 버전 범위와 우리 범위의 교집합 중 가장 높은 버전을 고릅니다.
+/// 겹치는 구간이 없으면 `None`.
+pub fn negotiate_version(peer_min: u16, peer_max: u16) -> Option<u16> {
+    let lo = peer_min.max(MIN_VERSION);
+    let hi = peer_max.min(MAX_VERSION);
+    if lo <= hi { Some(hi) } else { None }
+}
+
+pub fn is_supported_version(v: u16) -> bool {
+    (MIN_VERSION..=MAX_VERSION).contains(&v)
+}
+
+/// 버전별 배치 헤더 길이 (지원하지 않는 버전은 `None`)
+pub fn header_len(version: u16) -> Option<usize> {
+    match version {
+        VERSION_V1 => Some(BATCH_HEADER_LEN_V1),
+        VERSION_V2 => Some(BATCH_HEADER_LEN_V2),
+        _ => None,
+    }
+}
+
+/// 버퍼 앞 4바이트를 magic으로 읽습니다(배치/Hello 구분용).
+#[inline]
+pub fn peek_magic(bytes: &[u8]) -> Option<u32> {
+    let b: [u8; 4] = bytes.get(0..4)?.try_into().ok()?;
+    Some(u32::from_le_bytes(b))
+}
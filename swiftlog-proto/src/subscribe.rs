@@ -0,0 +1,196 @@
+// 실시간 구독 (follow 모드)
+//   client → server : Subscribe = magic u32 ('SLGQ') | version u16 (=1) | query_len u16 | query utf8
+//   server → client : Event     = magic u32 ('SLGE') | kind u8 | body
+//     kind 0 Ack     : (없음)       쿼리 수락. 이후 새로 저장되는 일치 레코드가 이어짐
+//     kind 1 Error   : len u16 | utf8   쿼리 거부. 서버가 연결을 닫음
+//     kind 2 Record  : id u64 | ts_ms u64 | level u8 | code u16 | source_len u8 | source
+//                      | msg_len u16 | msg | 필드 블록 (`fields` 모듈과 같은 레이아웃)
+//     kind 3 Dropped : count u64   구독자 버퍼가 가득 차 직전 Record 뒤로 건너뛴 레코드 수
+// 모두 배치와 같은 [len u32] 프레임에 실립니다. query는 서버 SelectLog와 같은 문법입니다.
+
+use crate::encode::{put_fields, truncate_utf8};
+use crate::hello::put_short_str;
+use crate::{peek_magic, DecodeError, FieldValue, FieldValueRef, Fields, LogLevel, MAX_MSG_LEN};
+
+pub const SUBSCRIBE_MAGIC: u32 = 0x5147_4C53; // 'SLGQ' (LE)
+pub const EVENT_MAGIC: u32 = 0x4547_4C53;     // 'SLGE' (LE)
+pub const SUBSCRIBE_VERSION: u16 = 1;
+
+const KIND_ACK: u8 = 0;
+const KIND_ERROR: u8 = 1;
+const KIND_RECORD: u8 = 2;
+const KIND_DROPPED: u8 = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscribe {
+    pub version: u16,
+    /// 빈 문자열 = 모든 레코드
+    pub query: String,
+}
+
+impl Subscribe {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self { version: SUBSCRIBE_VERSION, query: query.into() }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let q = truncate_utf8(&self.query, u16::MAX as usize);
+        let mut b = Vec::with_capacity(8 + q.len());
+        b.extend_from_slice(&SUBSCRIBE_MAGIC.to_le_bytes());
+        b.extend_from_slice(&self.version.to_le_bytes());
+        b.extend_from_slice(&(q.len() as u16).to_le_bytes());
+        b.extend_from_slice(q.as_bytes());
+        b
+    }
+
+    pub fn decode(b: &[u8]) -> Result<Self, DecodeError> {
+        if b.len() < 8 { return Err(DecodeError::TooShort { len: b.len() }); }
+        let magic = peek_magic(b).unwrap_or(0);
+        if magic != SUBSCRIBE_MAGIC { return Err(DecodeError::BadMagic(magic)); }
+        let version = u16::from_le_bytes([b[4], b[5]]);
+        if version != SUBSCRIBE_VERSION { return Err(DecodeError::UnsupportedVersion(version)); }
+        let len = u16::from_le_bytes([b[6], b[7]]) as usize;
+        let raw = b.get(8..8 + len).ok_or(DecodeError::Truncated { offset: 8 })?;
+        let query = std::str::from_utf8(raw).map_err(|_| DecodeError::InvalidField { offset: 8 })?;
+        Ok(Self { version, query: query.to_string() })
+    }
+}
+
+/// 구독으로 받는 레코드 1건
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubRecord {
+    /// 서버 LogStore ID (서버 재시작 전까지 단조 증가)
+    pub id: u64,
+    pub ts_ms: u64,
+    pub level: LogLevel,
+    pub code: u16,
+    /// "service@host" 또는 peer 주소, 모르면 빈 문자열
+    pub source: String,
+    pub msg: String,
+    pub fields: Vec<(String, FieldValue)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubEvent {
+    Ack,
+    Error(String),
+    Record(SubRecord),
+    Dropped(u64),
+}
+
+impl SubEvent {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut b = Vec::with_capacity(64);
+        b.extend_from_slice(&EVENT_MAGIC.to_le_bytes());
+        match self {
+            SubEvent::Ack => b.push(KIND_ACK),
+            SubEvent::Error(msg) => {
+                let m = truncate_utf8(msg, u16::MAX as usize);
+                b.push(KIND_ERROR);
+                b.extend_from_slice(&(m.len() as u16).to_le_bytes());
+                b.extend_from_slice(m.as_bytes());
+            }
+            SubEvent::Record(r) => {
+                let msg = truncate_utf8(&r.msg, MAX_MSG_LEN);
+                b.push(KIND_RECORD);
+                b.extend_from_slice(&r.id.to_le_bytes());
+                b.extend_from_slice(&r.ts_ms.to_le_bytes());
+                b.push(r.level as u8);
+                b.extend_from_slice(&r.code.to_le_bytes());
+                put_short_str(&mut b, &r.source);
+                b.extend_from_slice(&(msg.len() as u16).to_le_bytes());
+                b.extend_from_slice(msg.as_bytes());
+                let fields: Vec<(&str, FieldValueRef<'_>)> =
+                    r.fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())).collect();
+                put_fields(&mut b, &fields);
+            }
+            SubEvent::Dropped(n) => {
+                b.push(KIND_DROPPED);
+                b.extend_from_slice(&n.to_le_bytes());
+            }
+        }
+        b
+    }
+
+    pub fn decode(b: &[u8]) -> Result<Self, DecodeError> {
+        if b.len() < 5 { return Err(DecodeError::TooShort { len: b.len() }); }
+        let magic = peek_magic(b).unwrap_or(0);
+        if magic != EVENT_MAGIC { return Err(DecodeError::BadMagic(magic)); }
+        let mut r = Reader { b, p: 5 };
+        match b[4] {
+            KIND_ACK => Ok(SubEvent::Ack),
+            KIND_ERROR => {
+                let len = r.u16()? as usize;
+                Ok(SubEvent::Error(r.str(len)?))
+            }
+            KIND_RECORD => {
+                let id = r.u64()?;
+                let ts_ms = r.u64()?;
+                let level = LogLevel::from_u8_lossy(r.take(1)?[0]);
+                let code = r.u16()?;
+                let source_len = r.take(1)?[0] as usize;
+                let source = r.str(source_len)?;
+                let msg_len = r.u16()? as usize;
+                let msg = r.str(msg_len)?;
+                let at = r.p;
+                let (fields, _) = Fields::split(&b[at..]).ok_or(DecodeError::InvalidField { offset: at })?;
+                let fields = fields.iter().map(|(k, v)| (k.to_string(), v.to_owned())).collect();
+                Ok(SubEvent::Record(SubRecord { id, ts_ms, level, code, source, msg, fields }))
+            }
+            KIND_DROPPED => Ok(SubEvent::Dropped(r.u64()?)),
+            _ => Err(DecodeError::InvalidField { offset: 4 }),
+        }
+    }
+}
+
+struct Reader<'a> {
+    b: &'a [u8],
+    p: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        let s = self.b.get(self.p..self.p + n).ok_or(DecodeError::Truncated { offset: self.p })?;
+        self.p += n;
+        Ok(s)
+    }
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        let s = self.take(2)?;
+        Ok(u16::from_le_bytes([s[0], s[1]]))
+    }
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn str(&mut self, n: usize) -> Result<String, DecodeError> {
+        let at = self.p;
+        let s = self.take(n)?;
+        std::str::from_utf8(s).map(str::to_string).map_err(|_| DecodeError::InvalidField { offset: at })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribe_and_events_roundtrip() {
+        let sub = Subscribe::new("level>=Warn service=api");
+        assert_eq!(Subscribe::decode(&sub.encode()).unwrap(), sub);
+
+        let rec = SubRecord {
+            id: 9,
+            ts_ms: 1_700_000_000_000,
+            level: LogLevel::Warn,
+            code: 503,
+            source: "api@web-1".into(),
+            msg: "upstream \"slow\"".into(),
+            fields: vec![("ms".into(), FieldValue::I64(830)), ("ok".into(), FieldValue::Bool(false))],
+        };
+        for ev in [SubEvent::Ack, SubEvent::Error("bad query".into()), SubEvent::Record(rec), SubEvent::Dropped(17)] {
+            let bytes = ev.encode();
+            assert_eq!(SubEvent::decode(&bytes).unwrap(), ev);
+            assert!(SubEvent::decode(&bytes[..bytes.len() - 1]).is_err());
+        }
+        assert!(matches!(SubEvent::decode(&sub.encode()), Err(DecodeError::BadMagic(_))));
+    }
+}
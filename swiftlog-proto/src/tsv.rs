@@ -0,0 +1,162 @@
+// TSV 로그 줄 (서버 로그 파일, 백업, swl-viewer가 같은 규칙으로 읽고 씁니다)
+//
+//   ts_ms \t level_u8 \t code \t msg [\t fields] \n
+//
+// msg 이스케이프 (결과는 항상 탭/줄바꿈 없는 UTF-8):
+//   \  → \\      줄바꿈 → \n      CR → \r      탭 → \t
+//   UTF-8이 아닌 바이트 → \xHH (16진 2자리)
+// 풀 때 모르는 이스케이프(\q 등)와 끝에 홀로 남은 \는 글자 그대로 둡니다.
+// fields 컬럼은 `format_fields` 텍스트 (문자열 값과 특수문자가 든 키는 따옴표 안에서 이미 이스케이프됨).
+//
+// 이 규칙 이전 파일(msg를 그대로 쓰던 시절)도 읽을 수 있도록, 마지막 탭 뒤가 필드로 읽히지
+// 않으면 그 탭까지 msg로 봅니다.
+
+use crate::fields::{format_fields, parse_fields, FieldValue, FieldValueRef};
+
+/// 읽어 들인 한 줄
+#[derive(Clone, Debug, PartialEq)]
+pub struct TsvRecord {
+    pub ts_ms: u64,
+    /// 기록된 값 그대로 (범위 밖일 수 있음)
+    pub level_raw: u8,
+    pub code: u16,
+    /// 원래 바이트 (UTF-8이 아닐 수 있음)
+    pub msg: Vec<u8>,
+    pub fields: Vec<(String, FieldValue)>,
+}
+
+/// msg를 이스케이프해 덧붙임
+pub fn escape_msg(out: &mut Vec<u8>, msg: &[u8]) {
+    for chunk in msg.utf8_chunks() {
+        for &b in chunk.valid().as_bytes() {
+            match b {
+                b'\\' => out.extend_from_slice(b"\\\\"),
+                b'\n' => out.extend_from_slice(b"\\n"),
+                b'\r' => out.extend_from_slice(b"\\r"),
+                b'\t' => out.extend_from_slice(b"\\t"),
+                _ => out.push(b),
+            }
+        }
+        for &b in chunk.invalid() {
+            out.extend_from_slice(format!("\\x{b:02X}").as_bytes());
+        }
+    }
+}
+
+/// `escape_msg`의 역
+pub fn unescape_msg(s: &str) -> Vec<u8> {
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        if b[i] != b'\\' || i + 1 == b.len() {
+            out.push(b[i]);
+            i += 1;
+            continue;
+        }
+        let (byte, used) = match b[i + 1] {
+            b'\\' => (b'\\', 2),
+            b'n' => (b'\n', 2),
+            b'r' => (b'\r', 2),
+            b't' => (b'\t', 2),
+            b'x' => match s.get(i + 2..i + 4).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                Some(v) => (v, 4),
+                None => (b'\\', 1),
+            },
+            _ => (b'\\', 1),
+        };
+        out.push(byte);
+        i += used;
+    }
+    out
+}
+
+/// 한 줄을 줄바꿈까지 덧붙임
+pub fn write_line<'a, I>(out: &mut Vec<u8>, ts_ms: u64, level_raw: u8, code: u16, msg: &[u8], fields: I)
+where
+    I: IntoIterator<Item = (&'a str, FieldValueRef<'a>)>,
+{
+    out.extend_from_slice(format!("{ts_ms}\t{level_raw}\t{code}\t").as_bytes());
+    escape_msg(out, msg);
+    let fields = format_fields(fields);
+    if !fields.is_empty() {
+        out.push(b'\t');
+        out.extend_from_slice(fields.as_bytes());
+    }
+    out.push(b'\n');
+}
+
+/// 줄바꿈을 뺀 한 줄을 읽음
+pub fn parse_line(line: &str) -> Result<TsvRecord, &'static str> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut it = line.splitn(4, '\t');
+    let ts_ms = it.next().ok_or("missing ts_ms")?.parse().map_err(|_| "invalid ts_ms")?;
+    let level_raw = it.next().ok_or("missing level")?.parse().map_err(|_| "invalid level")?;
+    let code = it.next().ok_or("missing code")?.parse().map_err(|_| "invalid code")?;
+    let rest = it.next().ok_or("missing message")?;
+    let (msg, fields) = match rest.rsplit_once('\t') {
+        Some((msg, f)) => match parse_fields(f) {
+            Ok(fields) if !fields.is_empty() => (msg, fields),
+            _ => (rest, Vec::new()),
+        },
+        None => (rest, Vec::new()),
+    };
+    Ok(TsvRecord { ts_ms, level_raw, code, msg: unescape_msg(msg), fields })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn line(msg: &[u8], fields: &[(String, FieldValue)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_line(&mut out, 1, 2, 3, msg, fields.iter().map(|(k, v)| (k.as_str(), v.as_ref())));
+        out
+    }
+
+    #[test]
+    fn escapes_are_documented_and_legacy_lines_still_parse() {
+        let out = line(b"a\tb\nc\\d\re\xFF\xC3", &[]);
+        assert_eq!(out, b"1\t2\t3\ta\\tb\\nc\\\\d\\re\\xFF\\xC3\n");
+        // 모르는 이스케이프 / 끝의 \ / 잘린 \x는 그대로
+        assert_eq!(unescape_msg(r"C:\path\q \x4 \"), br"C:\path\q \x4 \");
+        // 예전 파일: msg 안의 탭이 그대로
+        let r = parse_line("5\t2\t4\ta\ttab").unwrap();
+        assert_eq!((r.msg.as_slice(), r.fields.len()), (&b"a\ttab"[..], 0));
+        assert_eq!(parse_line("5\t2"), Err("missing code"));
+        assert_eq!(parse_line("x\t2\t1\tm"), Err("invalid ts_ms"));
+    }
+
+    fn field_value() -> impl Strategy<Value = FieldValue> {
+        prop_oneof![
+            any::<String>().prop_map(FieldValue::Str),
+            any::<i64>().prop_map(FieldValue::I64),
+            // NaN은 자기 자신과 같지 않으므로 유한값만
+            (-1e300f64..1e300).prop_map(FieldValue::F64),
+            any::<bool>().prop_map(FieldValue::Bool),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn any_message_round_trips(msg in proptest::collection::vec(any::<u8>(), 0..200)) {
+            let out = line(&msg, &[]);
+            let text = std::str::from_utf8(&out).expect("escaped line is UTF-8");
+            prop_assert_eq!(text.matches('\n').count(), 1);
+            prop_assert_eq!(text.matches('\t').count(), 3);
+            prop_assert_eq!(parse_line(text.trim_end_matches('\n')).unwrap().msg, msg);
+        }
+
+        #[test]
+        fn text_with_escapes_and_fields_round_trips(
+            msg in "[a-z\\\\\t\n\r\"= ]{0,40}",
+            fields in proptest::collection::vec((any::<String>(), field_value()), 0..4),
+        ) {
+            let out = line(msg.as_bytes(), &fields);
+            let r = parse_line(std::str::from_utf8(&out).unwrap().trim_end_matches('\n')).unwrap();
+            prop_assert_eq!(r.msg, msg.into_bytes());
+            prop_assert_eq!(r.fields, fields);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// swiftlog-query/src/expr.rs
+// 조건식 AST와 평가. 레코드 쪽은 Record만 구현하면 됨 (서버 Log, 뷰어 LogEntry)
+
+use regex::Regex;
+use swiftlog_proto::LogLevel;
+
+use crate::text::{tokenize, TextTerm};
+
+/// 쿼리로 거를 수 있는 레코드
+pub trait Record {
+    fn ts_ms(&self) -> u64;
+    fn level(&self) -> LogLevel;
+    fn code(&self) -> u16;
+    fn msg(&self) -> &str;
+    /// `field.<key>=<value>`: 값은 필드 타입에 맞춰 비교 (`FieldValue::eq_text`)
+    fn field_eq(&self, key: &str, want: &str) -> bool;
+    /// `source=`: 송신자 정보가 없는 레코드(파일 등)는 false
+    fn source_matches(&self, _want: &str) -> bool { false }
+    /// `service=`: Hello로 알린 서비스 이름
+    fn service(&self) -> Option<&str> { None }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cmp { Eq, Lt, Le, Gt, Ge }
+
+impl Cmp {
+    pub fn test<T: Ord>(self, a: T, b: T) -> bool {
+        match self {
+            Cmp::Eq => a == b,
+            Cmp::Lt => a < b,
+            Cmp::Le => a <= b,
+            Cmp::Gt => a > b,
+            Cmp::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Pred(Pred),
+}
+
+/// 조건 하나. `!=`는 Not(Pred), `in (...)`은 Or 또는 CodeIn으로 파싱됨
+#[derive(Clone, Debug)]
+pub enum Pred {
+    Level(Cmp, LogLevel),
+    Code(Cmp, u16),
+    /// `code=a..b` (양 끝 포함)
+    CodeRange(u16, u16),
+    CodeIn(Vec<u16>),
+    Ts(Cmp, u64),
+    /// 부분 문자열
+    Contains(String),
+    Regex(Regex),
+    Text(TextTerm),
+    Source(String),
+    Service(String),
+    Field(String, String),
+}
+
+impl Expr {
+    pub fn matches<R: Record + ?Sized>(&self, r: &R) -> bool {
+        match self {
+            Expr::And(v) => v.iter().all(|e| e.matches(r)),
+            Expr::Or(v) => v.iter().any(|e| e.matches(r)),
+            Expr::Not(e) => !e.matches(r),
+            Expr::Pred(p) => p.matches(r),
+        }
+    }
+
+    /// 최상위 AND를 펼친 조건 목록 (인덱스로 좁힐 조건을 고를 때)
+    pub fn into_conjuncts(self) -> Vec<Expr> {
+        match self {
+            Expr::And(v) => v.into_iter().flat_map(Expr::into_conjuncts).collect(),
+            e => vec![e],
+        }
+    }
+}
+
+impl Pred {
+    pub fn matches<R: Record + ?Sized>(&self, r: &R) -> bool {
+        match self {
+            Pred::Level(c, l) => c.test(r.level(), *l),
+            Pred::Code(c, n) => c.test(r.code(), *n),
+            Pred::CodeRange(lo, hi) => (*lo..=*hi).contains(&r.code()),
+            Pred::CodeIn(codes) => codes.contains(&r.code()),
+            Pred::Ts(c, t) => c.test(r.ts_ms(), *t),
+            Pred::Contains(s) => r.msg().contains(s.as_str()),
+            Pred::Regex(re) => re.is_match(r.msg()),
+            Pred::Text(t) => t.matches(&tokenize(r.msg()).collect::<Vec<_>>()),
+            Pred::Source(s) => r.source_matches(s),
+            Pred::Service(s) => r.service() == Some(s.as_str()),
+            Pred::Field(k, v) => r.field_eq(k, v),
+        }
+    }
+}
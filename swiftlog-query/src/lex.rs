@@ -0,0 +1,81 @@
+// swiftlog-query/src/lex.rs
+// 쿼리 문자열 → 토큰. 위치는 문자(char) 단위
+
+use crate::expr::Cmp;
+use crate::ParseError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Tok {
+    /// 따옴표 없는 낱말: 키, 값, and/or/not/in, `word:xxx`
+    Word(String),
+    /// "..." / '...'
+    Str(String),
+    Cmp(Cmp),
+    Ne,
+    LParen,
+    RParen,
+    Comma,
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Token {
+    pub tok: Tok,
+    pub pos: usize,
+}
+
+// 낱말을 끊는 문자 (그 밖의 문자는 '.', ':', '*', '-', '|' 등 모두 낱말에 포함)
+fn is_special(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | ',' | '=' | '!' | '<' | '>' | '"' | '\'')
+}
+
+pub(crate) fn lex(src: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (c, pos) = (chars[i], i);
+        let next_eq = chars.get(i + 1) == Some(&'=');
+        let tok = match c {
+            c if c.is_whitespace() => { i += 1; continue; }
+            '(' => { i += 1; Tok::LParen }
+            ')' => { i += 1; Tok::RParen }
+            ',' => { i += 1; Tok::Comma }
+            '=' => { i += 1 + next_eq as usize; Tok::Cmp(Cmp::Eq) } // '==' 도 허용
+            '!' if next_eq => { i += 2; Tok::Ne }
+            '!' => return Err(ParseError::new(pos, "expected '!=' (use 'not' to negate)")),
+            '<' | '>' => {
+                i += 1 + next_eq as usize;
+                Tok::Cmp(match (c, next_eq) {
+                    ('<', false) => Cmp::Lt,
+                    ('<', true) => Cmp::Le,
+                    (_, false) => Cmp::Gt,
+                    (_, true) => Cmp::Ge,
+                })
+            }
+            '"' | '\'' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(ParseError::new(pos, "unterminated string")),
+                        Some(&q) if q == c => { i += 1; break; }
+                        Some('\\') if chars.get(i + 1).is_some_and(|&n| n == c || n == '\\') => {
+                            s.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&ch) => { s.push(ch); i += 1; }
+                    }
+                }
+                Tok::Str(s)
+            }
+            _ => {
+                while i < chars.len() && !is_special(chars[i]) { i += 1; }
+                Tok::Word(chars[pos..i].iter().collect())
+            }
+        };
+        out.push(Token { tok, pos });
+    }
+    out.push(Token { tok: Tok::Eof, pos: chars.len() });
+    Ok(out)
+}
@@ -0,0 +1,74 @@
+// swiftlog-query/src/lib.rs
+//! SelectLog 쿼리 언어 공용 크레이트.
+//! 서버 콘솔 / HTTP API / 구독 / 라우팅 / 백업과 swl-viewer가 모두 이 파서와 평가기를 씁니다.
+//!
+//! ```text
+//! query     := expr? (top-level에 latest / limit=N / offset=N 를 어디에 둬도 됨)
+//! expr      := and ("or" and)*
+//! and       := unary (["and"] unary)*          // 나란히 쓰면 AND (예전 문법과 호환)
+//! unary     := "not" unary | "(" expr ")" | predicate
+//! predicate := key op value | key ["not"] "in" "(" value ("," value)* ")"
+//!            | word:<단어> | word:<접두사>* | phrase:<구절>
+//! op        := = | != | < | <= | > | >=
+//! value     := 낱말 | "..." | '...'   (\" \\ 이스케이프)
+//! ```
+//! 키: `level`, `code`(`=a..b` 구간 포함), `code_in=a,b`, `ts`, `since`(= ts>=), `until`(= ts<=),
+//! `contains`, `regex`, `source`, `service`, `field.<key>`.
+//! 우선순위는 not > and > or. 예) `level>=warn and (code in (500, 503) or not service=api) latest limit=20`
+//! 괄호와 not은 합쳐 [`MAX_NESTING`]단계까지 중첩할 수 있습니다.
+//!
+//! 오류는 [`ParseError`]로, 문제가 된 문자 위치를 가리킵니다.
+
+mod expr;
+mod lex;
+mod parse;
+mod text;
+
+use std::fmt;
+
+pub use expr::{Cmp, Expr, Pred, Record};
+pub use parse::{parse, MAX_NESTING};
+pub use text::{tokenize, TextTerm};
+
+/// 파싱된 쿼리: 조건식 + 정렬/페이지 (조건이 없으면 전체)
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    pub filter: Option<Expr>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// true면 최신 우선
+    pub latest: bool,
+}
+
+impl Query {
+    /// 조건식만 검사 (limit/offset/latest는 호출하는 쪽에서)
+    pub fn matches<R: Record + ?Sized>(&self, r: &R) -> bool {
+        self.filter.as_ref().is_none_or(|e| e.matches(r))
+    }
+}
+
+/// 쿼리 오류. `pos`는 입력에서 0부터 센 문자(char) 위치
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub pos: usize,
+    pub msg: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(pos: usize, msg: impl Into<String>) -> Self {
+        Self { pos, msg: msg.into() }
+    }
+
+    /// 입력 한 줄 + 오류 위치 아래 `^` (콘솔/뷰어 표시용)
+    pub fn caret(&self, src: &str) -> String {
+        format!("{src}\n{}^", " ".repeat(self.pos))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at char {})", self.msg, self.pos + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,398 @@
+// swiftlog-query/src/parse.rs
+// 토큰 → Query (재귀 하강). 문법은 lib.rs 참고
+
+use regex::Regex;
+use swiftlog_proto::LogLevel;
+
+use crate::expr::{Cmp, Expr, Pred};
+use crate::lex::{lex, Tok, Token};
+use crate::text::TextTerm;
+use crate::{ParseError, Query};
+
+/// 괄호 / not 중첩 한도 (재귀 하강이라 한도가 없으면 긴 입력 하나로 스택이 넘침)
+pub const MAX_NESTING: usize = 64;
+
+/// SelectLog 쿼리 문자열 → Query
+pub fn parse(src: &str) -> Result<Query, ParseError> {
+    let mut p = Parser { toks: lex(src)?, at: 0, depth: 0, nesting: 0, q: Query::default() };
+    p.q.filter = p.or()?;
+    match p.peek() {
+        Tok::Eof => Ok(p.q),
+        _ => Err(p.err("unmatched ')'")),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Key { Level, Code, CodeIn, Ts, Since, Until, Contains, Regex, Source, Service, Field, Limit, Offset }
+
+impl Key {
+    fn lookup(w: &str) -> Option<Key> {
+        if w.starts_with("field.") { return Some(Key::Field); } // 필드 키는 대소문자 그대로
+        Some(match w.to_ascii_lowercase().as_str() {
+            "level" => Key::Level,
+            "code" => Key::Code,
+            "code_in" => Key::CodeIn,
+            "ts" => Key::Ts,
+            "since" => Key::Since,
+            "until" => Key::Until,
+            "contains" => Key::Contains,
+            "regex" => Key::Regex,
+            "source" => Key::Source,
+            "service" => Key::Service,
+            "limit" => Key::Limit,
+            "offset" => Key::Offset,
+            _ => return None,
+        })
+    }
+}
+
+// 키 뒤의 연산자
+enum Op { Cmp(Cmp), Ne, In { negated: bool } }
+
+struct Parser {
+    toks: Vec<Token>,
+    at: usize,
+    /// 괄호 깊이 (latest/limit/offset은 최상위에서만)
+    depth: usize,
+    /// 괄호 + not 중첩 (MAX_NESTING까지)
+    nesting: usize,
+    q: Query,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok { &self.toks[self.at].tok }
+    fn pos(&self) -> usize { self.toks[self.at].pos }
+    fn next(&mut self) -> Token {
+        let t = self.toks[self.at].clone();
+        if t.tok != Tok::Eof { self.at += 1; }
+        t
+    }
+    fn err(&self, msg: impl Into<String>) -> ParseError { ParseError::new(self.pos(), msg) }
+
+    fn peek_kw(&self, kw: &str) -> bool { is_kw(self.peek(), kw) }
+
+    // '(' / 'not' 하나 더 들어감. 나올 때 nesting -= 1
+    fn enter(&mut self, pos: usize) -> Result<(), ParseError> {
+        if self.nesting >= MAX_NESTING {
+            return Err(ParseError::new(pos, format!("nested too deeply (max {MAX_NESTING})")));
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
+    // 조건이 올 자리가 아니면 (끝, ')', 'or', 'and')
+    fn at_operand_end(&self) -> bool {
+        matches!(self.peek(), Tok::Eof | Tok::RParen) || self.peek_kw("or") || self.peek_kw("and")
+    }
+
+    fn or(&mut self) -> Result<Option<Expr>, ParseError> {
+        let first = self.and()?;
+        if !self.peek_kw("or") { return Ok(first); }
+        let mut terms = vec![first.ok_or_else(|| self.err("expected a condition before 'or'"))?];
+        while self.peek_kw("or") {
+            self.next();
+            if self.at_operand_end() { return Err(self.err("expected a condition after 'or'")); }
+            terms.push(self.and()?.ok_or_else(|| self.err("expected a condition after 'or'"))?);
+        }
+        Ok(Some(Expr::Or(terms)))
+    }
+
+    // 나란히 쓴 조건과 'and'로 이은 조건은 같음. latest/limit/offset은 조건을 만들지 않음(None)
+    fn and(&mut self) -> Result<Option<Expr>, ParseError> {
+        let mut terms = Vec::new();
+        loop {
+            if matches!(self.peek(), Tok::Eof | Tok::RParen) || self.peek_kw("or") { break; }
+            if self.peek_kw("and") {
+                if terms.is_empty() { return Err(self.err("expected a condition before 'and'")); }
+                self.next();
+                if self.at_operand_end() { return Err(self.err("expected a condition after 'and'")); }
+                continue;
+            }
+            if let Some(e) = self.unary()? { terms.push(e); }
+        }
+        Ok(match terms.len() {
+            0 => None,
+            1 => terms.pop(),
+            _ => Some(Expr::And(terms)),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Option<Expr>, ParseError> {
+        if !self.peek_kw("not") { return self.primary(); }
+        let pos = self.next().pos;
+        if self.at_operand_end() { return Err(self.err("expected a condition after 'not'")); }
+        self.enter(pos)?;
+        let e = self.unary()?;
+        self.nesting -= 1;
+        match e {
+            Some(e) => Ok(Some(Expr::Not(Box::new(e)))),
+            None => Err(ParseError::new(pos, "'not' needs a condition, not latest/limit/offset")),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Option<Expr>, ParseError> {
+        let Token { tok, pos } = self.next();
+        match tok {
+            Tok::LParen => {
+                self.enter(pos)?;
+                self.depth += 1;
+                let e = self.or()?;
+                self.depth -= 1;
+                self.nesting -= 1;
+                if self.peek() != &Tok::RParen { return Err(ParseError::new(pos, "unclosed '('")); }
+                self.next();
+                e.map(Some).ok_or_else(|| ParseError::new(pos, "empty group"))
+            }
+            Tok::Word(w) => self.predicate(w, pos),
+            Tok::Str(_) => Err(ParseError::new(pos, "expected a key before the string (e.g. contains=\"...\")")),
+            _ => Err(ParseError::new(pos, "expected a condition")),
+        }
+    }
+
+    fn predicate(&mut self, w: String, pos: usize) -> Result<Option<Expr>, ParseError> {
+        if w.eq_ignore_ascii_case("latest") {
+            self.top_level(pos, "latest")?;
+            self.q.latest = true;
+            return Ok(None);
+        }
+        for (prefix, phrase) in [("word:", false), ("phrase:", true)] {
+            if w.get(..prefix.len()).is_some_and(|p| p.eq_ignore_ascii_case(prefix)) {
+                let rest = &w[prefix.len()..];
+                let (v, vpos) = if rest.is_empty() { self.value()? } else { (rest.to_string(), pos + prefix.len()) };
+                let term = TextTerm::parse(&v, phrase).map_err(|m| ParseError::new(vpos, m))?;
+                return Ok(Some(Expr::Pred(Pred::Text(term))));
+            }
+        }
+        let key = Key::lookup(&w).ok_or_else(|| ParseError::new(pos, format!("unknown key: {w}")))?;
+        if key == Key::Field && w.len() == "field.".len() {
+            return Err(ParseError::new(pos, "empty field key"));
+        }
+        let op_pos = self.pos();
+        let op = match self.next().tok {
+            Tok::Cmp(c) => Op::Cmp(c),
+            Tok::Ne => Op::Ne,
+            Tok::Word(k) if k.eq_ignore_ascii_case("in") => Op::In { negated: false },
+            Tok::Word(k) if k.eq_ignore_ascii_case("not") && self.peek_kw("in") => {
+                self.next();
+                Op::In { negated: true }
+            }
+            _ => return Err(ParseError::new(op_pos, format!("expected an operator after '{w}'"))),
+        };
+
+        match key {
+            Key::Limit | Key::Offset => {
+                self.top_level(pos, &w)?;
+                if !matches!(op, Op::Cmp(Cmp::Eq)) { return Err(ParseError::new(op_pos, format!("{w} takes '='"))); }
+                let (v, vpos) = self.value()?;
+                let n = v.parse::<usize>().map_err(|_| ParseError::new(vpos, format!("invalid {w}: {v}")))?;
+                if key == Key::Limit { self.q.limit = Some(n) } else { self.q.offset = n }
+                return Ok(None);
+            }
+            // 예전 문법: code_in=1,2,3
+            Key::CodeIn => {
+                if !matches!(op, Op::Cmp(Cmp::Eq)) { return Err(ParseError::new(op_pos, "code_in takes '='")); }
+                let mut codes = Vec::new();
+                loop {
+                    let (v, vpos) = self.value()?;
+                    codes.push(parse_num::<u16>(&v, vpos, "code")?);
+                    if self.peek() != &Tok::Comma { break; }
+                    self.next();
+                }
+                return Ok(Some(Expr::Pred(Pred::CodeIn(codes))));
+            }
+            _ => {}
+        }
+
+        let expr = match op {
+            Op::Cmp(c) => {
+                let (v, vpos) = self.value()?;
+                Expr::Pred(compare(key, &w, c, &v, vpos, op_pos)?)
+            }
+            Op::Ne => {
+                let (v, vpos) = self.value()?;
+                Expr::Not(Box::new(Expr::Pred(compare(key, &w, Cmp::Eq, &v, vpos, op_pos)?)))
+            }
+            Op::In { negated } => {
+                if self.peek() != &Tok::LParen { return Err(self.err("expected '(' after 'in'")); }
+                self.next();
+                let mut preds = Vec::new();
+                loop {
+                    let (v, vpos) = self.value()?;
+                    preds.push(compare(key, &w, Cmp::Eq, &v, vpos, op_pos)?);
+                    match self.next() {
+                        Token { tok: Tok::Comma, .. } => continue,
+                        Token { tok: Tok::RParen, .. } => break,
+                        t => return Err(ParseError::new(t.pos, "expected ',' or ')'")),
+                    }
+                }
+                let codes: Option<Vec<u16>> = preds.iter()
+                    .map(|p| match p { Pred::Code(Cmp::Eq, c) => Some(*c), _ => None })
+                    .collect();
+                let e = match codes {
+                    Some(codes) => Expr::Pred(Pred::CodeIn(codes)),
+                    None if preds.len() == 1 => Expr::Pred(preds.remove(0)),
+                    None => Expr::Or(preds.into_iter().map(Expr::Pred).collect()),
+                };
+                if negated { Expr::Not(Box::new(e)) } else { e }
+            }
+        };
+        Ok(Some(expr))
+    }
+
+    fn value(&mut self) -> Result<(String, usize), ParseError> {
+        let pos = self.pos();
+        match self.next().tok {
+            Tok::Word(v) | Tok::Str(v) => Ok((v, pos)),
+            _ => Err(ParseError::new(pos, "expected a value")),
+        }
+    }
+
+    fn top_level(&self, pos: usize, what: &str) -> Result<(), ParseError> {
+        if self.depth > 0 { return Err(ParseError::new(pos, format!("{what} is only allowed outside parentheses"))); }
+        Ok(())
+    }
+}
+
+fn is_kw(t: &Tok, kw: &str) -> bool {
+    matches!(t, Tok::Word(w) if w.eq_ignore_ascii_case(kw))
+}
+
+fn parse_num<T: std::str::FromStr>(v: &str, pos: usize, what: &str) -> Result<T, ParseError> {
+    v.parse().map_err(|_| ParseError::new(pos, format!("invalid {what}: {v}")))
+}
+
+// `key <c> value` 하나. 문자열 키는 = / != / in 만
+fn compare(key: Key, name: &str, c: Cmp, v: &str, vpos: usize, op_pos: usize) -> Result<Pred, ParseError> {
+    let text_only = |p: Pred| match c {
+        Cmp::Eq => Ok(p),
+        _ => Err(ParseError::new(op_pos, format!("{name} supports only =, != and in"))),
+    };
+    match key {
+        Key::Level => {
+            let l = LogLevel::parse(v).ok_or_else(|| ParseError::new(vpos, format!("invalid level: {v}")))?;
+            Ok(Pred::Level(c, l))
+        }
+        Key::Code => match v.split_once("..") {
+            Some((a, b)) if c == Cmp::Eq => {
+                let (a, b) = (parse_num::<u16>(a, vpos, "code")?, parse_num::<u16>(b, vpos, "code")?);
+                Ok(Pred::CodeRange(a.min(b), a.max(b)))
+            }
+            Some(_) => Err(ParseError::new(op_pos, "code range takes '='")),
+            None => Ok(Pred::Code(c, parse_num(v, vpos, "code")?)),
+        },
+        Key::Ts => Ok(Pred::Ts(c, parse_num(v, vpos, "ts")?)),
+        Key::Since | Key::Until => {
+            if c != Cmp::Eq { return Err(ParseError::new(op_pos, format!("{name} takes '=' (or use ts)"))); }
+            Ok(Pred::Ts(if key == Key::Since { Cmp::Ge } else { Cmp::Le }, parse_num(v, vpos, name)?))
+        }
+        Key::Contains => text_only(Pred::Contains(v.to_string())),
+        Key::Regex => {
+            let re = Regex::new(v).map_err(|e| ParseError::new(vpos, format!("invalid regex: {e}")))?;
+            text_only(Pred::Regex(re))
+        }
+        Key::Source => text_only(Pred::Source(v.to_string())),
+        Key::Service => text_only(Pred::Service(v.to_string())),
+        Key::Field => text_only(Pred::Field(name["field.".len()..].to_string(), v.to_string())),
+        Key::CodeIn | Key::Limit | Key::Offset => unreachable!("handled by the caller"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Record;
+
+    struct Rec { ts: u64, level: LogLevel, code: u16, msg: &'static str, svc: &'static str }
+
+    impl Record for Rec {
+        fn ts_ms(&self) -> u64 { self.ts }
+        fn level(&self) -> LogLevel { self.level }
+        fn code(&self) -> u16 { self.code }
+        fn msg(&self) -> &str { self.msg }
+        fn field_eq(&self, key: &str, want: &str) -> bool { key == "svc" && self.svc == want }
+        fn service(&self) -> Option<&str> { Some(self.svc) }
+    }
+
+    fn recs() -> Vec<Rec> {
+        use LogLevel::*;
+        vec![
+            Rec { ts: 100, level: Info, code: 200, msg: "request ok", svc: "api" },
+            Rec { ts: 200, level: Warn, code: 503, msg: "upstream slow", svc: "api" },
+            Rec { ts: 300, level: Error, code: 500, msg: "Connection reset by peer", svc: "db" },
+            Rec { ts: 400, level: Error, code: 404, msg: "disk full", svc: "worker" },
+            Rec { ts: 500, level: Debug, code: 503, msg: "retry in 3ms", svc: "worker" },
+        ]
+    }
+
+    // 맞는 레코드의 ts
+    fn hits(src: &str) -> Vec<u64> {
+        let q = parse(src).unwrap_or_else(|e| panic!("{src}: {e}"));
+        recs().iter().filter(|r| q.matches(*r)).map(|r| r.ts).collect()
+    }
+
+    #[test]
+    fn legacy_space_separated_queries_still_and() {
+        assert_eq!(hits(""), [100, 200, 300, 400, 500]);
+        assert_eq!(hits("level>=Warn code=500..599"), [200, 300]);
+        assert_eq!(hits("code_in=404,200 since=150"), [400]);
+        assert_eq!(hits(r#"contains="disk full" until=400"#), [400]);
+        assert_eq!(hits("regex='^(request|disk)' field.svc=worker"), [400]);
+        assert_eq!(hits("word:reset phrase:'connection reset'"), [300]);
+        let q = parse("latest LIMIT=5 offset=2 level<=info").unwrap();
+        assert!(q.latest && q.limit == Some(5) && q.offset == 2 && q.filter.is_some());
+    }
+
+    #[test]
+    fn precedence_grouping_negation_and_sets() {
+        // not > and > or
+        assert_eq!(hits("level=error or level=warn code=503"), [200, 300, 400]);
+        assert_eq!(hits("(level=error or level=warn) and code>=500"), [200, 300]);
+        assert_eq!(hits("not level=error and code=503"), [200, 500]);
+        assert_eq!(hits("not (level=error or code=503)"), [100]);
+        assert_eq!(hits("service!=api ts<400"), [300]);
+        assert_eq!(hits("code in (200, 404) OR service in ('db')"), [100, 300, 400]);
+        assert_eq!(hits("level not in (debug, info, warn)"), [300, 400]);
+        assert_eq!(hits("contains!=\"r\" or word:ok"), [100, 400]);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_char() {
+        let err = |src: &str| parse(src).map(|_| ()).unwrap_err();
+        for (src, pos) in [
+            ("level>=loud", 7),
+            ("lvl=warn", 0),
+            ("level=warn or", 13),
+            ("(code=1 or code=2", 0),
+            ("code=1)", 6),
+            ("contains>x", 8),
+            ("contains=\"open", 9),
+            ("code in (1 2)", 11),
+            ("level ! warn", 6),
+            ("(latest)", 1),
+            ("limit=many", 6),
+            ("not", 3),
+            ("and code=1", 0),
+            ("word:*", 5),
+            ("field.=1", 0),
+            ("() or x", 0),
+        ] {
+            assert_eq!(err(src).pos, pos, "{src}: {}", err(src));
+        }
+        let e = err("code=1 and lates");
+        assert_eq!(e.to_string(), "unknown key: lates (at char 12)");
+        assert_eq!(e.caret("code=1 and lates"), "code=1 and lates\n           ^");
+        // 위치는 바이트가 아니라 문자 단위
+        assert_eq!(err("contains=\"디스크\" lvl=1").pos, 15);
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error_not_a_stack_overflow() {
+        let nested = |n: usize| format!("{}level=Info{}", "(".repeat(n), ")".repeat(n));
+        assert!(parse(&nested(MAX_NESTING)).is_ok());
+        assert_eq!(parse(&nested(MAX_NESTING + 1)).unwrap_err().pos, MAX_NESTING);
+        assert_eq!(parse(&nested(3000)).unwrap_err().pos, MAX_NESTING);
+        let nots = format!("{}level=Info", "not ".repeat(3000));
+        assert_eq!(parse(&nots).unwrap_err().pos, MAX_NESTING * 4);
+        assert_eq!(parse(&format!("{}code=1", "(not ".repeat(40))).unwrap_err().pos, 32 * 5);
+    }
+}
@@ -0,0 +1,49 @@
+// swiftlog-query/src/text.rs
+// 메시지 토큰 규칙과 word: / phrase: 조건 (서버 토큰 인덱스도 같은 tokenize를 씀)
+
+use std::borrow::Cow;
+
+/// 메시지 → 토큰 (글자/숫자/'_'가 이어진 부분, 소문자). `req-7f3a timeout`은 `req`, `7f3a`, `timeout`.
+/// 대문자가 없으면 빌려 씀 (서버는 append마다 부르므로 할당을 줄임)
+pub fn tokenize(msg: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    msg.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| !t.is_empty())
+        .map(|t| if t.chars().any(char::is_uppercase) { Cow::Owned(t.to_lowercase()) } else { Cow::Borrowed(t) })
+}
+
+/// 메시지 토큰 조건. 토큰은 [`tokenize`] 기준
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextTerm {
+    /// `word:timeout`
+    Word(String),
+    /// `word:time*`
+    Prefix(String),
+    /// `phrase:"connection reset"`: 토큰이 이 순서로 붙어서
+    Phrase(Vec<String>),
+}
+
+impl TextTerm {
+    /// 검색어를 메시지와 같은 규칙으로 토큰화. 여러 토큰이면 구절 (`word:req-7f3a` = `phrase:"req 7f3a"`)
+    pub(crate) fn parse(v: &str, phrase: bool) -> Result<TextTerm, String> {
+        let (text, prefix) = match v.strip_suffix('*') {
+            Some(p) if !phrase => (p, true),
+            _ => (v, false),
+        };
+        let mut words: Vec<String> = tokenize(text).map(|t| t.into_owned()).collect();
+        match (words.len(), prefix) {
+            (0, _) => Err(format!("no searchable word in {v:?}")),
+            (1, true) => Ok(TextTerm::Prefix(words.remove(0))),
+            (_, true) => Err(format!("prefix search takes one word: {v:?}")),
+            (1, false) => Ok(TextTerm::Word(words.remove(0))),
+            _ => Ok(TextTerm::Phrase(words)),
+        }
+    }
+
+    pub fn matches(&self, tokens: &[Cow<'_, str>]) -> bool {
+        match self {
+            TextTerm::Word(w) => tokens.iter().any(|t| t == w),
+            TextTerm::Prefix(p) => tokens.iter().any(|t| t.starts_with(p.as_str())),
+            TextTerm::Phrase(words) => tokens.windows(words.len()).any(|w| w == words.as_slice()),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{channel, Receiver, Sender},
+    Arc,
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use swiftlog_proto::{format_fields, next_frame, write_frame, SubEvent, SubRecord, Subscribe};
+
+// 정지 플래그 확인 주기 (블로킹 read 타임아웃)
+const POLL: Duration = Duration::from_millis(200);
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// 구독 스레드 → GUI 이벤트
+pub enum FollowEvent {
+    Connected,
+    Line(String),
+    /// 서버에서 버퍼가 넘쳐 건너뛴 레코드 수
+    Dropped(u64),
+    Error(String),
+}
+
+/// 구독 제어句
+pub struct FollowCtrl {
+    pub rx: Receiver<FollowEvent>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl FollowCtrl {
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+/// 서버 구독 포트에 붙어 `query`(SelectLog 문법)에 맞는 새 로그를 받습니다.
+/// 연결이 끊기면 정지할 때까지 재접속합니다. 쿼리 오류는 재시도하지 않습니다.
+pub fn start_follow(addr: SocketAddr, query: String) -> io::Result<FollowCtrl> {
+    let (tx, rx) = channel::<FollowEvent>();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_c = stop.clone();
+
+    let handle = thread::Builder::new()
+        .name("swiftlog-gui-follow".into())
+        .spawn(move || {
+            while !stop_c.load(Ordering::Relaxed) {
+                match follow_once(addr, &query, &tx, &stop_c) {
+                    Ok(()) => return,
+                    Err(e) => {
+                        let _ = tx.send(FollowEvent::Error(format!("{addr}: {e}")));
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        })?;
+
+    Ok(FollowCtrl { rx, stop, handle: Some(handle) })
+}
+
+// 정지했거나 서버가 쿼리를 거부하면 Ok, 연결 문제는 Err(재접속)
+fn follow_once(addr: SocketAddr, query: &str, tx: &Sender<FollowEvent>, stop: &AtomicBool) -> io::Result<()> {
+    let mut s = TcpStream::connect_timeout(&addr, Duration::from_secs(3))?;
+    s.set_read_timeout(Some(POLL))?;
+    let mut out = Vec::new();
+    write_frame(&mut out, &Subscribe::new(query).encode());
+    s.write_all(&out)?;
+
+    let mut buf = Vec::with_capacity(64 * 1024);
+    let mut tmp = [0u8; 16 * 1024];
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        match s.read(&mut tmp) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => buf.extend_from_slice(&tmp[..n]),
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e),
+        }
+        let mut offset = 0;
+        while let Some((frame, used)) = next_frame(&buf[offset..]) {
+            offset += used;
+            let ev = SubEvent::decode(frame).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let ev = match ev {
+                SubEvent::Ack => FollowEvent::Connected,
+                SubEvent::Record(r) => FollowEvent::Line(format_record(&r)),
+                SubEvent::Dropped(n) => FollowEvent::Dropped(n),
+                SubEvent::Error(e) => {
+                    let _ = tx.send(FollowEvent::Error(format!("server refused subscription: {e}")));
+                    return Ok(());
+                }
+            };
+            let _ = tx.send(ev);
+        }
+        buf.drain(..offset);
+    }
+}
+
+// "ts LEVEL code [source] msg | k=v ..."
+fn format_record(r: &SubRecord) -> String {
+    let mut line = format!("{} {:<5} {:>5} ", r.ts_ms, r.level.as_str().to_ascii_uppercase(), r.code);
+    if !r.source.is_empty() {
+        line.push_str(&format!("[{}] ", r.source));
+    }
+    line.push_str(&r.msg);
+    if !r.fields.is_empty() {
+        line.push_str(" | ");
+        line.push_str(&format_fields(r.fields.iter().map(|(k, v)| (k.as_str(), v.as_ref()))));
+    }
+    line
+}
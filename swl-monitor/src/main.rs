@@ -1,16 +1,15 @@
 mod protocol;
+mod follow;
 mod sender;
-mod tail;
 
 use eframe::{egui, NativeOptions};
 use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver};
 use std::collections::VecDeque;
 
 use protocol::{LogLevel};
 use sender::{Transport, start_sending, SendCtrl, SendStats};
-use tail::{start_tail, TailCtrl, TailEvent};
+use follow::{start_follow, FollowCtrl, FollowEvent};
 
 fn main() -> eframe::Result<()> {
     let opts = NativeOptions::default();
@@ -35,9 +34,10 @@ struct GuiApp {
     stats_rx: Option<Receiver<SendStats>>,
     last_stats: SendStats,
 
-    // 모니터링(파일 tail)
-    tail_path_str: String,
-    tail_ctrl: Option<TailCtrl>,
+    // 모니터링(서버 구독)
+    follow_addr_str: String,
+    follow_query: String,
+    follow_ctrl: Option<FollowCtrl>,
     lines: VecDeque<String>,
     max_lines: usize,
     errors: Vec<String>,
@@ -57,8 +57,9 @@ impl Default for GuiApp {
             stats_rx: None,
             last_stats: SendStats { sent: 0, errors: 0 },
 
-            tail_path_str: "logs/app.log".to_string(),
-            tail_ctrl: None,
+            follow_addr_str: "127.0.0.1:9503".to_string(), // 서버 [subscribe] bind
+            follow_query: String::new(),
+            follow_ctrl: None,
             lines: VecDeque::with_capacity(2000),
             max_lines: 1000,
             errors: Vec::new(),
@@ -117,16 +118,18 @@ impl eframe::App for GuiApp {
             });
 
             ui.separator();
-            ui.label("Tail Path");
-            ui.text_edit_singleline(&mut self.tail_path_str);
+            ui.label("Subscribe Address (ip:port)");
+            ui.text_edit_singleline(&mut self.follow_addr_str);
+            ui.label("Filter (SelectLog query, empty = all)");
+            ui.text_edit_singleline(&mut self.follow_query);
             ui.horizontal(|ui| {
-                if self.tail_ctrl.is_none() {
-                    if ui.button("Start Tail").clicked() {
-                        self.start_tail();
+                if self.follow_ctrl.is_none() {
+                    if ui.button("Start Follow").clicked() {
+                        self.start_follow();
                     }
                 } else {
-                    if ui.button("Stop Tail").clicked() {
-                        self.stop_tail();
+                    if ui.button("Stop Follow").clicked() {
+                        self.stop_follow();
                     }
                 }
                 ui.add(egui::DragValue::new(&mut self.max_lines).clamp_range(100..=50_000));
@@ -143,11 +146,11 @@ impl eframe::App for GuiApp {
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Live Log (tail)");
+            ui.heading("Live Log (follow)");
             ui.separator();
 
             // 수신 라인 갱신
-            self.pump_tail_events();
+            self.pump_follow_events();
 
             egui::ScrollArea::vertical().auto_shrink([false,false]).stick_to_bottom(true).show(ui, |ui| {
                 for line in &self.lines {
@@ -175,13 +178,7 @@ impl GuiApp {
             Ok(a) => a,
             Err(e) => { self.errors.push(format!("addr parse error: {}", e)); return; }
         };
-        let level = match self.level_idx {
-            0 => LogLevel::Trace,
-            1 => LogLevel::Debug,
-            2 => LogLevel::Info,
-            3 => LogLevel::Warn,
-            _ => LogLevel::Error,
-        };
+        let level = LogLevel::from_u8_lossy(self.level_idx as u8);
 
         let (tx, rx) = mpsc::channel::<SendStats>();
         match start_sending(
@@ -208,39 +205,39 @@ impl GuiApp {
         self.stats_rx = None;
     }
 
-    fn start_tail(&mut self) {
-        let path = PathBuf::from(self.tail_path_str.clone());
-        match start_tail(path) {
-            Ok(ctrl) => { self.tail_ctrl = Some(ctrl); }
-            Err(e) => { self.errors.push(format!("tail start error: {}", e)); }
+    fn start_follow(&mut self) {
+        let addr: SocketAddr = match self.follow_addr_str.parse() {
+            Ok(a) => a,
+            Err(e) => { self.errors.push(format!("addr parse error: {}", e)); return; }
+        };
+        match start_follow(addr, self.follow_query.clone()) {
+            Ok(ctrl) => { self.follow_ctrl = Some(ctrl); }
+            Err(e) => { self.errors.push(format!("follow start error: {}", e)); }
         }
     }
 
-    fn stop_tail(&mut self) {
-        if let Some(ctrl) = self.tail_ctrl.take() {
+    fn stop_follow(&mut self) {
+        if let Some(ctrl) = self.follow_ctrl.take() {
             ctrl.stop();
         }
     }
 
-    fn pump_tail_events(&mut self) {
-        if let Some(ctrl) = &self.tail_ctrl {
-            for _ in 0..512 {
-                match ctrl.rx.try_recv() {
-                    Ok(TailEvent::Line(line)) => {
-                        self.lines.push_back(line);
-                        while self.lines.len() > self.max_lines {
-                            self.lines.pop_front();
-                        }
-                    }
-                    Ok(TailEvent::Rotated) => {
-                        self.lines.push_back(String::from("--- [rotated] ---"));
-                    }
-                    Ok(TailEvent::Error(e)) => {
-                        self.errors.push(format!("tail error: {}", e));
-                    }
-                    Err(std::sync::mpsc::TryRecvError::Empty) => break,
-                    Err(std::sync::mpsc::TryRecvError::Disconnected) => break,
-                }
+    fn push_line(&mut self, line: String) {
+        self.lines.push_back(line);
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+        }
+    }
+
+    fn pump_follow_events(&mut self) {
+        let Some(ctrl) = &self.follow_ctrl else { return };
+        let events: Vec<FollowEvent> = ctrl.rx.try_iter().take(512).collect();
+        for ev in events {
+            match ev {
+                FollowEvent::Line(line) => self.push_line(line),
+                FollowEvent::Connected => self.push_line(String::from("--- [subscribed] ---")),
+                FollowEvent::Dropped(n) => self.push_line(format!("--- [server dropped {n} record(s): monitor too slow] ---")),
+                FollowEvent::Error(e) => self.errors.push(format!("follow error: {}", e)),
             }
         }
     }
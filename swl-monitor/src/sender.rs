@@ -1,4 +1,5 @@
 use crate::protocol::{encode_batch, LogLevel};
+use swiftlog_proto::{write_frame, FRAME_PREFIX_LEN};
 use std::io::{self, Write};
 use std::net::{SocketAddr, TcpStream, UdpSocket};
 use std::sync::{
@@ -23,6 +24,7 @@ pub struct SendCtrl {
 }
 
 impl SendCtrl {
+    #[allow(dead_code)]
     pub fn is_running(&self) -> bool {
         self.handle.is_some() && !self.stop.load(Ordering::Relaxed)
     }
@@ -83,7 +85,7 @@ pub fn start_sending(
                         } else {
                             thread::yield_now();
                         }
-                        if sent % 50 == 0 {
+                        if sent.is_multiple_of(50) {
                             let _ = stats_tx.send(SendStats { sent, errors });
                         }
                     }
@@ -98,11 +100,9 @@ pub fn start_sending(
                     while !stop_c.load(Ordering::Relaxed) {
                         let payload = format!("{} #{sent}", msg_template);
                         let batch = encode_batch(&[(level, code, &payload)]);
-                        let len = batch.len() as u32;
 
-                        let mut frame = Vec::with_capacity(4 + batch.len());
-                        frame.extend_from_slice(&len.to_le_bytes());
-                        frame.extend_from_slice(&batch);
+                        let mut frame = Vec::with_capacity(FRAME_PREFIX_LEN + batch.len());
+                        write_frame(&mut frame, &batch);
 
                         match stream.write_all(&frame) {
                             Ok(_) => sent += 1,
@@ -113,7 +113,7 @@ pub fn start_sending(
                         } else {
                             thread::yield_now();
                         }
-                        if sent % 50 == 0 {
+                        if sent.is_multiple_of(50) {
                             let _ = stats_tx.send(SendStats { sent, errors });
                         }
                     }